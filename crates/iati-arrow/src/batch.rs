@@ -0,0 +1,422 @@
+//! Arrow schemas and batch builders for flat transactions and activities.
+
+use std::sync::Arc;
+
+use arrow_array::builder::{Date32Builder, Decimal128Builder, StringBuilder, UInt16Builder, UInt32Builder};
+use arrow_array::{ArrayRef, RecordBatch};
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+use chrono::NaiveDate;
+use iati_transform::{flatten_activity, flatten_transactions, FlatActivity, FlatTransaction};
+use iati_types::Activity;
+use rust_decimal::Decimal;
+
+use crate::ExportError;
+
+/// Decimal128 precision used for amounts (the maximum).
+pub const AMOUNT_PRECISION: u8 = 38;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArrowOptions {
+    /// Decimal places kept in `Decimal128` amount columns, at most
+    /// [`AMOUNT_PRECISION`].
+    pub amount_scale: u8,
+    /// Round amounts with more decimal places than `amount_scale` (typically
+    /// converted values) half-to-even. When `false`, such amounts are
+    /// [`ExportError::PrecisionLoss`].
+    pub round_amounts: bool,
+    /// Rows per `RecordBatch` (and Parquet row group) when streaming.
+    pub batch_rows: usize,
+}
+
+impl Default for ArrowOptions {
+    fn default() -> Self {
+        ArrowOptions {
+            amount_scale: 6,
+            round_amounts: false,
+            batch_rows: 8192,
+        }
+    }
+}
+
+fn date32(date: NaiveDate) -> i32 {
+    (date - NaiveDate::from_ymd_opt(1970, 1, 1).unwrap()).num_days() as i32
+}
+
+/// `amount` as a Decimal128 value with `options.amount_scale` decimal places.
+fn decimal128(amount: Decimal, options: &ArrowOptions) -> Result<i128, ExportError> {
+    let scale = u32::from(options.amount_scale);
+    let rounded = amount.round_dp(scale);
+    if rounded != amount && !options.round_amounts {
+        return Err(ExportError::PrecisionLoss(amount));
+    }
+    let widen = 10i128.checked_pow(scale - rounded.scale());
+    widen
+        .and_then(|w| rounded.mantissa().checked_mul(w))
+        .filter(|v| v.unsigned_abs() < 10u128.pow(u32::from(AMOUNT_PRECISION)))
+        .ok_or(ExportError::AmountOverflow(amount))
+}
+
+fn amount_type(scale: u8) -> DataType {
+    DataType::Decimal128(AMOUNT_PRECISION, scale as i8)
+}
+
+/// Schema of [`TransactionBatchBuilder`] batches: the `FlatTransaction` columns, in order.
+pub fn transactions_schema(options: &ArrowOptions) -> SchemaRef {
+    let text = |name: &str, nullable| Field::new(name, DataType::Utf8, nullable);
+    Arc::new(Schema::new(vec![
+        text("iati_identifier", false),
+        text("reporting_org_ref", true),
+        text("reporting_org_name", true),
+        Field::new("activity_start", DataType::Date32, true),
+        Field::new("activity_end", DataType::Date32, true),
+        Field::new("transaction_index", DataType::UInt32, false),
+        text("transaction_ref", true),
+        Field::new("tx_type", DataType::UInt16, false),
+        text("tx_type_name", false),
+        Field::new("date", DataType::Date32, false),
+        Field::new("amount", amount_type(options.amount_scale), false),
+        text("currency", true),
+        Field::new("value_date", DataType::Date32, true),
+        text("provider_org_ref", true),
+        text("provider_org_name", true),
+        text("provider_activity_id", true),
+        text("receiver_org_ref", true),
+        text("receiver_org_name", true),
+        text("receiver_activity_id", true),
+        Field::new("converted_amount", amount_type(options.amount_scale), true),
+        text("converted_currency", true),
+    ]))
+}
+
+/// Schema of [`ActivityBatchBuilder`] batches: the `FlatActivity` columns, in order.
+pub fn activities_schema() -> SchemaRef {
+    let text = |name: &str, nullable| Field::new(name, DataType::Utf8, nullable);
+    Arc::new(Schema::new(vec![
+        text("iati_identifier", false),
+        text("reporting_org_ref", true),
+        text("reporting_org_name", true),
+        text("default_currency", true),
+        Field::new("activity_start", DataType::Date32, true),
+        Field::new("activity_end", DataType::Date32, true),
+        Field::new("transactions", DataType::UInt32, false),
+        Field::new("budgets", DataType::UInt32, false),
+        text("sectors", false),
+    ]))
+}
+
+/// Accumulates flat transactions into column builders.
+pub struct TransactionBatchBuilder {
+    options: ArrowOptions,
+    schema: SchemaRef,
+    rows: usize,
+    iati_identifier: StringBuilder,
+    reporting_org_ref: StringBuilder,
+    reporting_org_name: StringBuilder,
+    activity_start: Date32Builder,
+    activity_end: Date32Builder,
+    transaction_index: UInt32Builder,
+    transaction_ref: StringBuilder,
+    tx_type: UInt16Builder,
+    tx_type_name: StringBuilder,
+    date: Date32Builder,
+    amount: Decimal128Builder,
+    currency: StringBuilder,
+    value_date: Date32Builder,
+    provider_org_ref: StringBuilder,
+    provider_org_name: StringBuilder,
+    provider_activity_id: StringBuilder,
+    receiver_org_ref: StringBuilder,
+    receiver_org_name: StringBuilder,
+    receiver_activity_id: StringBuilder,
+    converted_amount: Decimal128Builder,
+    converted_currency: StringBuilder,
+}
+
+impl TransactionBatchBuilder {
+    /// # Panics
+    ///
+    /// If `options.amount_scale` is greater than [`AMOUNT_PRECISION`].
+    pub fn new(options: ArrowOptions) -> Self {
+        assert!(
+            options.amount_scale <= AMOUNT_PRECISION,
+            "amount_scale {} exceeds Decimal128 precision {AMOUNT_PRECISION}",
+            options.amount_scale
+        );
+        TransactionBatchBuilder {
+            options,
+            schema: transactions_schema(&options),
+            rows: 0,
+            iati_identifier: StringBuilder::new(),
+            reporting_org_ref: StringBuilder::new(),
+            reporting_org_name: StringBuilder::new(),
+            activity_start: Date32Builder::new(),
+            activity_end: Date32Builder::new(),
+            transaction_index: UInt32Builder::new(),
+            transaction_ref: StringBuilder::new(),
+            tx_type: UInt16Builder::new(),
+            tx_type_name: StringBuilder::new(),
+            date: Date32Builder::new(),
+            amount: Decimal128Builder::new(),
+            currency: StringBuilder::new(),
+            value_date: Date32Builder::new(),
+            provider_org_ref: StringBuilder::new(),
+            provider_org_name: StringBuilder::new(),
+            provider_activity_id: StringBuilder::new(),
+            receiver_org_ref: StringBuilder::new(),
+            receiver_org_name: StringBuilder::new(),
+            receiver_activity_id: StringBuilder::new(),
+            converted_amount: Decimal128Builder::new(),
+            converted_currency: StringBuilder::new(),
+        }
+    }
+
+    pub fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    /// Rows appended since the last [`finish`](Self::finish).
+    pub fn len(&self) -> usize {
+        self.rows
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows == 0
+    }
+
+    pub fn append(&mut self, row: &FlatTransaction) -> Result<(), ExportError> {
+        // Check amounts first so a failed row leaves every column the same length.
+        let amount = decimal128(row.amount, &self.options)?;
+        let converted = row.converted_amount.map(|a| decimal128(a, &self.options)).transpose()?;
+
+        self.iati_identifier.append_value(&row.iati_identifier);
+        self.reporting_org_ref.append_option(row.reporting_org_ref.as_deref());
+        self.reporting_org_name.append_option(row.reporting_org_name.as_deref());
+        self.activity_start.append_option(row.activity_start.map(date32));
+        self.activity_end.append_option(row.activity_end.map(date32));
+        self.transaction_index.append_value(row.transaction_index as u32);
+        self.transaction_ref.append_option(row.transaction_ref.as_deref());
+        self.tx_type.append_value(row.tx_type);
+        self.tx_type_name.append_value(&row.tx_type_name);
+        self.date.append_value(date32(row.date));
+        self.amount.append_value(amount);
+        self.currency.append_option(row.currency.as_ref().map(|c| c.0.as_str()));
+        self.value_date.append_option(row.value_date.map(date32));
+        self.provider_org_ref.append_option(row.provider_org_ref.as_deref());
+        self.provider_org_name.append_option(row.provider_org_name.as_deref());
+        self.provider_activity_id.append_option(row.provider_activity_id.as_deref());
+        self.receiver_org_ref.append_option(row.receiver_org_ref.as_deref());
+        self.receiver_org_name.append_option(row.receiver_org_name.as_deref());
+        self.receiver_activity_id.append_option(row.receiver_activity_id.as_deref());
+        self.converted_amount.append_option(converted);
+        self.converted_currency.append_option(row.converted_currency.as_ref().map(|c| c.0.as_str()));
+        self.rows += 1;
+        Ok(())
+    }
+
+    /// Append every transaction of `activity` (without converted amounts).
+    pub fn append_activity(&mut self, activity: &Activity) -> Result<(), ExportError> {
+        for row in flatten_transactions(activity) {
+            self.append(&row)?;
+        }
+        Ok(())
+    }
+
+    /// Build a batch from the rows appended so far and reset the builder.
+    pub fn finish(&mut self) -> Result<RecordBatch, ExportError> {
+        let amount_type = amount_type(self.options.amount_scale);
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(self.iati_identifier.finish()),
+            Arc::new(self.reporting_org_ref.finish()),
+            Arc::new(self.reporting_org_name.finish()),
+            Arc::new(self.activity_start.finish()),
+            Arc::new(self.activity_end.finish()),
+            Arc::new(self.transaction_index.finish()),
+            Arc::new(self.transaction_ref.finish()),
+            Arc::new(self.tx_type.finish()),
+            Arc::new(self.tx_type_name.finish()),
+            Arc::new(self.date.finish()),
+            Arc::new(self.amount.finish().with_data_type(amount_type.clone())),
+            Arc::new(self.currency.finish()),
+            Arc::new(self.value_date.finish()),
+            Arc::new(self.provider_org_ref.finish()),
+            Arc::new(self.provider_org_name.finish()),
+            Arc::new(self.provider_activity_id.finish()),
+            Arc::new(self.receiver_org_ref.finish()),
+            Arc::new(self.receiver_org_name.finish()),
+            Arc::new(self.receiver_activity_id.finish()),
+            Arc::new(self.converted_amount.finish().with_data_type(amount_type)),
+            Arc::new(self.converted_currency.finish()),
+        ];
+        self.rows = 0;
+        Ok(RecordBatch::try_new(self.schema.clone(), columns)?)
+    }
+}
+
+/// Accumulates flat activities into column builders.
+pub struct ActivityBatchBuilder {
+    schema: SchemaRef,
+    rows: usize,
+    iati_identifier: StringBuilder,
+    reporting_org_ref: StringBuilder,
+    reporting_org_name: StringBuilder,
+    default_currency: StringBuilder,
+    activity_start: Date32Builder,
+    activity_end: Date32Builder,
+    transactions: UInt32Builder,
+    budgets: UInt32Builder,
+    sectors: StringBuilder,
+}
+
+impl Default for ActivityBatchBuilder {
+    fn default() -> Self {
+        ActivityBatchBuilder {
+            schema: activities_schema(),
+            rows: 0,
+            iati_identifier: StringBuilder::new(),
+            reporting_org_ref: StringBuilder::new(),
+            reporting_org_name: StringBuilder::new(),
+            default_currency: StringBuilder::new(),
+            activity_start: Date32Builder::new(),
+            activity_end: Date32Builder::new(),
+            transactions: UInt32Builder::new(),
+            budgets: UInt32Builder::new(),
+            sectors: StringBuilder::new(),
+        }
+    }
+}
+
+impl ActivityBatchBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows == 0
+    }
+
+    pub fn append(&mut self, row: &FlatActivity) {
+        self.iati_identifier.append_value(&row.iati_identifier);
+        self.reporting_org_ref.append_option(row.reporting_org_ref.as_deref());
+        self.reporting_org_name.append_option(row.reporting_org_name.as_deref());
+        self.default_currency.append_option(row.default_currency.as_ref().map(|c| c.0.as_str()));
+        self.activity_start.append_option(row.activity_start.map(date32));
+        self.activity_end.append_option(row.activity_end.map(date32));
+        self.transactions.append_value(row.transactions as u32);
+        self.budgets.append_value(row.budgets as u32);
+        self.sectors.append_value(&row.sectors);
+        self.rows += 1;
+    }
+
+    pub fn append_activity(&mut self, activity: &Activity) {
+        self.append(&flatten_activity(activity));
+    }
+
+    /// Build a batch from the rows appended so far and reset the builder.
+    pub fn finish(&mut self) -> Result<RecordBatch, ExportError> {
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(self.iati_identifier.finish()),
+            Arc::new(self.reporting_org_ref.finish()),
+            Arc::new(self.reporting_org_name.finish()),
+            Arc::new(self.default_currency.finish()),
+            Arc::new(self.activity_start.finish()),
+            Arc::new(self.activity_end.finish()),
+            Arc::new(self.transactions.finish()),
+            Arc::new(self.budgets.finish()),
+            Arc::new(self.sectors.finish()),
+        ];
+        self.rows = 0;
+        Ok(RecordBatch::try_new(self.schema.clone(), columns)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::{Array, Date32Array, Decimal128Array, StringArray};
+    use iati_transform::CsvRow;
+    use iati_types::{money::Money, tx::Transaction, CurrencyCode, TxType};
+
+    fn activity() -> Activity {
+        let mut a = Activity::new("XM-1");
+        a.default_currency = Some(CurrencyCode::from("EUR"));
+        let date = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap();
+        a.transactions.push(Transaction::new(TxType::Disbursement, date, Money::new(Decimal::new(123456, 2))));
+        let mut value = Money::new(Decimal::new(15, 7)); // 0.0000015, below the default scale
+        value.currency = Some(CurrencyCode::from("USD"));
+        value.value_date = Some(date);
+        a.transactions.push(Transaction::new(TxType::Expenditure, date, value));
+        a
+    }
+
+    #[test]
+    fn builds_typed_transaction_columns() {
+        let options = ArrowOptions { round_amounts: true, ..Default::default() };
+        let mut b = TransactionBatchBuilder::new(options);
+        let mut a = activity();
+        a.activity_start = NaiveDate::from_ymd_opt(2024, 1, 1);
+        b.append_activity(&a).unwrap();
+        assert_eq!(b.len(), 2);
+        let batch = b.finish().unwrap();
+        assert!(b.is_empty());
+        assert_eq!(batch.num_rows(), 2);
+        assert_eq!(batch.schema(), transactions_schema(&options));
+        let names: Vec<&str> = batch.schema_ref().fields().iter().map(|f| f.name().as_str()).collect();
+        assert_eq!(names, FlatTransaction::COLUMNS);
+
+        let amount = batch.column_by_name("amount").unwrap().as_any().downcast_ref::<Decimal128Array>().unwrap();
+        assert_eq!(amount.value_as_string(0), "1234.560000");
+        assert_eq!(amount.value_as_string(1), "0.000002");
+        let date = batch.column_by_name("date").unwrap().as_any().downcast_ref::<Date32Array>().unwrap();
+        assert_eq!(date.value_as_date(0), NaiveDate::from_ymd_opt(2024, 3, 15));
+        let start = batch.column_by_name("activity_start").unwrap().as_any().downcast_ref::<Date32Array>().unwrap();
+        assert_eq!(start.value_as_date(1), NaiveDate::from_ymd_opt(2024, 1, 1));
+        assert!(batch.column_by_name("activity_end").unwrap().is_null(0));
+        let value_date = batch.column_by_name("value_date").unwrap();
+        assert!(value_date.is_null(0) && value_date.is_valid(1));
+        let currency = batch.column_by_name("currency").unwrap().as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!((currency.value(0), currency.value(1)), ("EUR", "USD"));
+    }
+
+    #[test]
+    fn rejects_lossy_amounts_unless_rounding() {
+        let mut b = TransactionBatchBuilder::new(ArrowOptions::default());
+        let rows: Vec<FlatTransaction> = flatten_transactions(&activity()).collect();
+        b.append(&rows[0]).unwrap();
+        assert!(matches!(b.append(&rows[1]), Err(ExportError::PrecisionLoss(_))));
+        assert_eq!(b.finish().unwrap().num_rows(), 1);
+
+        // trailing zeros beyond the scale lose nothing
+        let mut row = rows[0].clone();
+        row.amount = Decimal::new(1_000_000_000, 8);
+        b.append(&row).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "exceeds Decimal128 precision")]
+    fn rejects_scale_beyond_precision() {
+        TransactionBatchBuilder::new(ArrowOptions { amount_scale: 39, ..Default::default() });
+    }
+
+    #[test]
+    fn rejects_amounts_beyond_decimal128() {
+        let mut a = activity();
+        a.transactions[0].value.amount = Decimal::MAX;
+        let mut b = TransactionBatchBuilder::new(ArrowOptions { amount_scale: 12, ..Default::default() });
+        assert!(matches!(b.append_activity(&a), Err(ExportError::AmountOverflow(_))));
+        assert_eq!(b.finish().unwrap().num_rows(), 0);
+
+        let mut b = ActivityBatchBuilder::new();
+        b.append_activity(&a);
+        let batch = b.finish().unwrap();
+        assert_eq!(batch.schema(), activities_schema());
+        assert_eq!(batch.num_rows(), 1);
+    }
+}
@@ -0,0 +1,29 @@
+//! iati-arrow: Apache Arrow and Parquet export for IATI activities.
+//!
+//! Transactions and activities are flattened with `iati-transform` and
+//! appended to [`TransactionBatchBuilder`] / [`ActivityBatchBuilder`], which
+//! produce Arrow `RecordBatch`es with `Decimal128` amounts and `Date32` dates.
+//! [`ParquetSink`] writes batches as they fill, so memory stays bounded by the
+//! batch size however many activities are streamed through it.
+
+use arrow_schema::ArrowError;
+use parquet::errors::ParquetError;
+use thiserror::Error;
+
+pub mod batch;
+pub mod sink;
+
+pub use crate::batch::{activities_schema, transactions_schema, ActivityBatchBuilder, ArrowOptions, TransactionBatchBuilder};
+pub use crate::sink::{write_activities_parquet, write_transactions_parquet, ParquetSink};
+
+#[derive(Debug, Error)]
+pub enum ExportError {
+    #[error("arrow error: {0}")]
+    Arrow(#[from] ArrowError),
+    #[error("parquet error: {0}")]
+    Parquet(#[from] ParquetError),
+    #[error("amount {0} does not fit Decimal128 at the configured scale")]
+    AmountOverflow(rust_decimal::Decimal),
+    #[error("amount {0} has more decimal places than the configured scale")]
+    PrecisionLoss(rust_decimal::Decimal),
+}
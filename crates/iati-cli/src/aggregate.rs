@@ -0,0 +1,130 @@
+use std::collections::BTreeMap;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+
+use chrono::Datelike;
+use clap::ValueEnum;
+use iati_fx::{convert_activity_partial, ConvertOptions, OnFailure};
+use iati_transform::csv::escape;
+use iati_transform::{accumulate, FxStrategy};
+use iati_types::{Activity, CurrencyCode, MoneyBag, RoundingPolicy, Transaction};
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::fx::Provider;
+use crate::output::print_json;
+use crate::{input, Failures, TableFormat};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub(crate) enum Dimension {
+    /// Calendar year of the transaction date.
+    Year,
+    /// Transaction type code.
+    Type,
+    /// Value currency (always included).
+    Currency,
+    /// iati-identifier.
+    Activity,
+}
+
+impl Dimension {
+    fn name(self) -> &'static str {
+        match self {
+            Dimension::Year => "year",
+            Dimension::Type => "type",
+            Dimension::Currency => "currency",
+            Dimension::Activity => "activity",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Row {
+    #[serde(flatten)]
+    key: BTreeMap<&'static str, String>,
+    currency: CurrencyCode,
+    amount: Decimal,
+}
+
+pub(crate) fn run(
+    inputs: &[PathBuf],
+    by: &[Dimension],
+    fx: Option<(String, Provider)>,
+    round: bool,
+    format: TableFormat,
+    failures: &mut Failures,
+) -> crate::Result<()> {
+    let dims: Vec<Dimension> = by.iter().copied().filter(|d| *d != Dimension::Currency).collect();
+    let fx = fx.map(|(to, provider)| (CurrencyCode::from(to.as_str()), provider));
+    let options = ConvertOptions::default().with_on_failure(OnFailure::Drop);
+
+    let mut totals: BTreeMap<Vec<String>, MoneyBag> = BTreeMap::new();
+    for (source, activity) in input::activities(inputs) {
+        let activity = match activity {
+            Ok(a) => a,
+            Err(e) => {
+                failures.report(&source, e);
+                continue;
+            }
+        };
+        let activity = match &fx {
+            Some((target, provider)) => {
+                let converted = convert_activity_partial(&activity, target, provider, &options);
+                for (value, error) in converted.failures {
+                    let at = crate::fx::value_label(value);
+                    failures.report(&source, format!("{} {at}: {error}", activity.iati_identifier));
+                }
+                converted.activity
+            }
+            None => activity,
+        };
+        let key = |tx: &Transaction| key(&dims, &activity, tx);
+        if let Err(e) = accumulate(&mut totals, &activity, &FxStrategy::Native, key) {
+            failures.report(&source, format!("{}: {e}", activity.iati_identifier));
+        }
+    }
+
+    let policy = RoundingPolicy::default();
+    let rows: Vec<Row> = totals
+        .into_iter()
+        .flat_map(|(key, bag)| {
+            let bag = if round { bag.rounded(&policy) } else { bag };
+            let key: BTreeMap<&'static str, String> = dims.iter().map(|d| d.name()).zip(key).collect();
+            BTreeMap::<CurrencyCode, Decimal>::from(bag)
+                .into_iter()
+                .map(move |(currency, amount)| Row { key: key.clone(), currency, amount })
+                .collect::<Vec<_>>()
+        })
+        .collect();
+
+    match format {
+        TableFormat::Json => print_json(&rows)?,
+        TableFormat::Csv => {
+            let mut out = BufWriter::new(io::stdout().lock());
+            let header: Vec<&str> = dims.iter().map(|d| d.name()).chain(["currency", "amount"]).collect();
+            write!(out, "{}\r\n", header.join(","))?;
+            for row in &rows {
+                let cells: Vec<String> = dims
+                    .iter()
+                    .map(|d| row.key[d.name()].clone())
+                    .chain([row.currency.to_string(), row.amount.to_string()])
+                    .collect();
+                let cells: Vec<_> = cells.iter().map(|c| escape(c)).collect();
+                write!(out, "{}\r\n", cells.join(","))?;
+            }
+            out.flush()?;
+        }
+    }
+    Ok(())
+}
+
+fn key(dims: &[Dimension], activity: &Activity, tx: &Transaction) -> Vec<String> {
+    dims.iter()
+        .map(|d| match d {
+            Dimension::Year => tx.date.year().to_string(),
+            Dimension::Type => tx.tx_type.to_string(),
+            Dimension::Activity => activity.iati_identifier.clone(),
+            Dimension::Currency => unreachable!("currency is always a column"),
+        })
+        .collect()
+}
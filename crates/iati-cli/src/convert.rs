@@ -0,0 +1,38 @@
+use std::path::PathBuf;
+
+use iati_fx::{convert_activity_partial, OnFailure};
+use iati_types::CurrencyCode;
+
+use crate::output::RecordWriter;
+use crate::{fx, input, DataFormat, Failures, FxArgs};
+
+pub(crate) fn run(
+    inputs: &[PathBuf],
+    args: &FxArgs,
+    format: DataFormat,
+    drop_failed: bool,
+    failures: &mut Failures,
+) -> crate::Result<()> {
+    let provider = fx::load(&args.fx, args.fx_format)?;
+    let target = CurrencyCode::from(args.to.as_str());
+    let on_failure = if drop_failed { OnFailure::Drop } else { OnFailure::KeepOriginal };
+    let options = fx::options(args.date, args.round).with_on_failure(on_failure);
+
+    let mut writer = RecordWriter::new(format);
+    for (source, activity) in input::activities(inputs) {
+        let activity = match activity {
+            Ok(a) => a,
+            Err(e) => {
+                failures.report(&source, e);
+                continue;
+            }
+        };
+        let converted = convert_activity_partial(&activity, &target, &provider, &options);
+        for (value, error) in &converted.failures {
+            let at = fx::value_label(*value);
+            failures.report(&source, format!("{} {at}: {error}", activity.iati_identifier));
+        }
+        writer.write(&converted.activity)?;
+    }
+    writer.finish()
+}
@@ -0,0 +1,187 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+use iati_arrow::{ActivityBatchBuilder, ArrowOptions, ExportError, ParquetSink, TransactionBatchBuilder};
+use iati_fx::DatePolicy;
+use iati_transform::{convert_flat, flatten_activity, flatten_transactions, CsvWriter, FlatActivity, FlatTransaction, FxStrategy};
+use iati_types::CurrencyCode;
+
+use crate::{fx, input, Failures};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub(crate) enum Table {
+    /// One row per transaction, with activity fields repeated.
+    Transactions,
+    /// One row per activity.
+    Activities,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub(crate) enum ExportFormat {
+    Csv,
+    /// Apache Parquet (Decimal128 amounts, Date32 dates); needs --output.
+    Parquet,
+}
+
+/// Destination for flat rows, in one of the export formats.
+trait Rows {
+    /// Write one transaction. `Ok(Some(e))` means this row was skipped because
+    /// of `e` (e.g. an amount that does not fit the column); later rows still go out.
+    fn transaction(&mut self, row: &FlatTransaction) -> crate::Result<Option<ExportError>>;
+    fn activity(&mut self, row: &FlatActivity) -> crate::Result<()>;
+    fn finish(self: Box<Self>) -> crate::Result<()>;
+}
+
+impl<W: Write> Rows for CsvWriter<W> {
+    fn transaction(&mut self, row: &FlatTransaction) -> crate::Result<Option<ExportError>> {
+        self.write(row)?;
+        Ok(None)
+    }
+
+    fn activity(&mut self, row: &FlatActivity) -> crate::Result<()> {
+        Ok(self.write(row)?)
+    }
+
+    fn finish(self: Box<Self>) -> crate::Result<()> {
+        self.into_inner()?;
+        Ok(())
+    }
+}
+
+/// Buffers rows into Arrow batches and writes a row group per full batch.
+struct ParquetRows {
+    transactions: TransactionBatchBuilder,
+    activities: ActivityBatchBuilder,
+    table: Table,
+    sink: ParquetSink<File>,
+    batch_rows: usize,
+}
+
+impl ParquetRows {
+    fn new(file: File, table: Table, round_amounts: bool) -> crate::Result<Self> {
+        let options = ArrowOptions { round_amounts, ..ArrowOptions::default() };
+        let transactions = TransactionBatchBuilder::new(options);
+        let activities = ActivityBatchBuilder::new();
+        let schema = match table {
+            Table::Transactions => transactions.schema(),
+            Table::Activities => activities.schema(),
+        };
+        Ok(ParquetRows {
+            transactions,
+            activities,
+            table,
+            sink: ParquetSink::new(file, schema)?,
+            batch_rows: options.batch_rows,
+        })
+    }
+
+    fn flush(&mut self) -> crate::Result<()> {
+        let batch = match self.table {
+            Table::Transactions => self.transactions.finish()?,
+            Table::Activities => self.activities.finish()?,
+        };
+        Ok(self.sink.write(&batch)?)
+    }
+}
+
+impl Rows for ParquetRows {
+    fn transaction(&mut self, row: &FlatTransaction) -> crate::Result<Option<ExportError>> {
+        match self.transactions.append(row) {
+            Ok(()) => {}
+            Err(e @ (ExportError::AmountOverflow(_) | ExportError::PrecisionLoss(_))) => return Ok(Some(e)),
+            Err(e) => return Err(e.into()),
+        }
+        if self.transactions.len() >= self.batch_rows {
+            self.flush()?;
+        }
+        Ok(None)
+    }
+
+    fn activity(&mut self, row: &FlatActivity) -> crate::Result<()> {
+        self.activities.append(row);
+        if self.activities.len() >= self.batch_rows {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> crate::Result<()> {
+        self.flush()?;
+        self.sink.close()?;
+        Ok(())
+    }
+}
+
+pub(crate) struct Conversion<'a> {
+    pub(crate) to: &'a str,
+    pub(crate) fx: &'a Path,
+    pub(crate) fx_format: fx::FxFormat,
+    pub(crate) date: fx::DateArg,
+}
+
+pub(crate) fn run(
+    inputs: &[PathBuf],
+    table: Table,
+    format: ExportFormat,
+    output: Option<&Path>,
+    round_amounts: bool,
+    conversion: Option<Conversion<'_>>,
+    failures: &mut Failures,
+) -> crate::Result<()> {
+    let converter = match &conversion {
+        Some(c) => Some((CurrencyCode::from(c.to), fx::load(c.fx, c.fx_format)?, DatePolicy::from(c.date))),
+        None => None,
+    };
+    let strategy = converter.as_ref().map(|(target, provider, dates)| FxStrategy::Convert {
+        target,
+        provider: provider.as_ref(),
+        dates,
+    });
+
+    let mut rows: Box<dyn Rows> = match format {
+        ExportFormat::Csv => {
+            let out: Box<dyn Write> = match output {
+                Some(path) => Box::new(File::create(path)?),
+                None => Box::new(io::stdout().lock()),
+            };
+            let mut csv = CsvWriter::new(BufWriter::new(out));
+            match table {
+                Table::Transactions => csv.write_header::<FlatTransaction>()?,
+                Table::Activities => csv.write_header::<FlatActivity>()?,
+            }
+            Box::new(csv)
+        }
+        ExportFormat::Parquet => {
+            let path = output.ok_or("parquet output needs --output FILE")?;
+            Box::new(ParquetRows::new(File::create(path)?, table, round_amounts)?)
+        }
+    };
+    for (source, activity) in input::activities(inputs) {
+        let activity = match activity {
+            Ok(a) => a,
+            Err(e) => {
+                failures.report(&source, e);
+                continue;
+            }
+        };
+        match table {
+            Table::Activities => rows.activity(&flatten_activity(&activity))?,
+            Table::Transactions => {
+                for mut row in flatten_transactions(&activity) {
+                    let at = format!("{} transaction[{}]", activity.iati_identifier, row.transaction_index);
+                    if let Some(strategy) = &strategy
+                        && let Err(e) = convert_flat(&mut row, &activity, strategy)
+                    {
+                        failures.report(&source, format!("{at}: {e}"));
+                    }
+                    if let Some(e) = rows.transaction(&row)? {
+                        failures.report(&source, format!("{at}: skipped: {e}"));
+                    }
+                }
+            }
+        }
+    }
+    rows.finish()
+}
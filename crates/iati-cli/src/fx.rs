@@ -0,0 +1,73 @@
+//! Loading rate files for `convert` and `aggregate`.
+
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::Path;
+
+use clap::ValueEnum;
+use iati_fx::{
+    load_annual_csv, load_ecb_csv, load_monthly_csv, ConvertOptions, DatePolicy, FxProvider, FxSnapshot,
+    ValueRef,
+};
+use iati_types::RoundingPolicy;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub(crate) enum FxFormat {
+    /// `currency,year,month,rate` (currency per USD, IMF layout).
+    Monthly,
+    /// `currency,year,rate` (currency per USD, DAC annual averages).
+    Annual,
+    /// ECB `eurofxref-hist.csv` (currency per EUR, daily).
+    Ecb,
+    /// An `FxSnapshot`, JSON (`.json`) or binary.
+    Snapshot,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub(crate) enum DateArg {
+    /// `value/@value-date`, else the transaction date or budget period start.
+    ValueDate,
+    /// The transaction date or budget period start.
+    TransactionDate,
+    /// The activity start date.
+    ActivityStart,
+}
+
+impl From<DateArg> for DatePolicy {
+    fn from(arg: DateArg) -> Self {
+        match arg {
+            DateArg::ValueDate => DatePolicy::ValueDateFirst,
+            DateArg::TransactionDate => DatePolicy::TransactionDate,
+            DateArg::ActivityStart => DatePolicy::ActivityStart,
+        }
+    }
+}
+
+pub(crate) type Provider = Box<dyn FxProvider>;
+
+pub(crate) fn load(path: &Path, format: FxFormat) -> crate::Result<Provider> {
+    let open = || File::open(path).map(BufReader::new);
+    let err = |e: &dyn std::fmt::Display| format!("{}: {e}", path.display());
+    Ok(match format {
+        FxFormat::Monthly => Box::new(load_monthly_csv(open()?).map_err(|e| err(&e))?),
+        FxFormat::Annual => Box::new(load_annual_csv(open()?).map_err(|e| err(&e))?),
+        FxFormat::Ecb => Box::new(load_ecb_csv(open()?).map_err(|e| err(&e))?),
+        FxFormat::Snapshot if path.extension().is_some_and(|ext| ext == "json") => {
+            Box::new(FxSnapshot::from_json(&fs::read_to_string(path)?).map_err(|e| err(&e))?)
+        }
+        FxFormat::Snapshot => Box::new(FxSnapshot::read_binary(open()?).map_err(|e| err(&e))?),
+    })
+}
+
+pub(crate) fn options(date: DateArg, round: bool) -> ConvertOptions {
+    let options = ConvertOptions::default().with_date_policy(date.into());
+    if round { options.with_rounding(RoundingPolicy::default()) } else { options }
+}
+
+/// Where an unconverted value sits in its activity, e.g. `transaction[0]`.
+pub(crate) fn value_label(value: ValueRef) -> String {
+    match value {
+        ValueRef::Transaction(i) => format!("transaction[{i}]"),
+        ValueRef::Budget(i) => format!("budget[{i}]"),
+    }
+}
@@ -0,0 +1,74 @@
+//! Input discovery: files, directories and stdin.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::PathBuf;
+
+use iati_transform::NdjsonReader;
+use iati_types::Activity;
+use iati_xml::{decompressed, ActivityReader, DatasetReader};
+
+pub(crate) type InputError = Box<dyn std::error::Error>;
+
+/// One input to read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Source {
+    Stdin,
+    File(PathBuf),
+}
+
+impl Source {
+    pub(crate) fn name(&self) -> String {
+        match self {
+            Source::Stdin => "<stdin>".to_string(),
+            Source::File(path) => path.display().to_string(),
+        }
+    }
+
+    /// Activities written by `iati parse --format ndjson` rather than XML.
+    fn is_ndjson(&self) -> bool {
+        matches!(self, Source::File(path) if path.extension().is_some_and(|ext| ext == "ndjson" || ext == "jsonl"))
+    }
+
+    /// The input, gunzipped if compressed.
+    fn open(&self) -> io::Result<Box<dyn BufRead>> {
+        match self {
+            Source::Stdin => decompressed(io::stdin().lock()),
+            Source::File(path) => decompressed(BufReader::new(File::open(path)?)),
+        }
+    }
+}
+
+/// The command-line inputs: no inputs or `-` is stdin, anything else a path.
+pub(crate) fn sources(inputs: &[PathBuf]) -> Vec<Source> {
+    if inputs.is_empty() {
+        return vec![Source::Stdin];
+    }
+    inputs
+        .iter()
+        .map(|input| if input.as_os_str() == "-" { Source::Stdin } else { Source::File(input.clone()) })
+        .collect()
+}
+
+/// Every activity in every input, streamed, with the name of the input it came
+/// from (`archive.zip/member.xml` for zip members). Directories are searched
+/// recursively for `*.xml`, `*.xml.gz` and `*.zip` files (in sorted order) as
+/// they are reached; a directory that cannot be read is an error item like any
+/// other, so the remaining inputs are still read.
+pub(crate) fn activities(inputs: &[PathBuf]) -> impl Iterator<Item = (String, Result<Activity, InputError>)> {
+    sources(inputs).into_iter().flat_map(|source| {
+        if let Source::File(path) = &source
+            && !source.is_ndjson()
+        {
+            return Box::new(DatasetReader::new([path]).map(|(path, r)| (path.display().to_string(), r.map_err(InputError::from))))
+                as Box<dyn Iterator<Item = _>>;
+        }
+        let name = source.name();
+        let items: Box<dyn Iterator<Item = Result<Activity, InputError>>> = match source.open() {
+            Ok(reader) if source.is_ndjson() => Box::new(NdjsonReader::new(reader).map(|r| r.map_err(InputError::from))),
+            Ok(reader) => Box::new(ActivityReader::new(reader).map(|r| r.map_err(InputError::from))),
+            Err(e) => Box::new(std::iter::once(Err(e.into()))),
+        };
+        Box::new(items.map(move |item| (name.clone(), item)))
+    })
+}
@@ -0,0 +1,25 @@
+use std::path::{Path, PathBuf};
+
+use iati_db::{Database, LoadOptions};
+
+use crate::{input, Failures};
+
+pub(crate) fn run(inputs: &[PathBuf], db: &Path, batch_size: usize, failures: &mut Failures) -> crate::Result<()> {
+    let mut database = Database::open(db)?;
+    let activities = input::activities(inputs).filter_map(|(source, activity)| match activity {
+        Ok(a) => Some(a),
+        Err(e) => {
+            failures.report(&source, e);
+            None
+        }
+    });
+    let stats = database.load(activities, &LoadOptions { batch_size })?;
+    println!(
+        "{}: {} inserted, {} updated, {} activities in total",
+        db.display(),
+        stats.inserted,
+        stats.updated,
+        database.count()?
+    );
+    Ok(())
+}
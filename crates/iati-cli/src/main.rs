@@ -0,0 +1,243 @@
+//! `iati`: parse, validate, convert and aggregate IATI activity files.
+//!
+//! Every subcommand reads XML files (plain, `*.xml.gz` or in `*.zip` archives),
+//! NDJSON activity files (`*.ndjson`, `*.jsonl`), directories (searched
+//! recursively for dataset files) or XML on stdin (no inputs, or `-`). Problems with individual inputs are
+//! reported on stderr and processing continues; the exit status is 1 if any
+//! input failed, and 2 for usage errors.
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use chrono::NaiveDate;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+mod aggregate;
+mod convert;
+mod export;
+mod fx;
+mod input;
+mod load;
+mod output;
+mod parse;
+mod stats;
+mod validate;
+
+pub(crate) type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+#[derive(Debug, Parser)]
+#[command(name = "iati", version, about = "Work with IATI activity XML from the command line")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Parse activities to JSON or NDJSON.
+    Parse {
+        #[command(flatten)]
+        input: InputArgs,
+        #[arg(long, value_enum, default_value_t = DataFormat::Json)]
+        format: DataFormat,
+        /// One record per transaction, with its activity's fields repeated.
+        #[arg(long)]
+        flat: bool,
+    },
+    /// Run the data quality rules.
+    Validate {
+        #[command(flatten)]
+        input: InputArgs,
+        #[arg(long, value_enum, default_value_t = ReportFormat::Text)]
+        format: ReportFormat,
+        /// Reference date for "in the future" checks (default: today).
+        #[arg(long)]
+        today: Option<NaiveDate>,
+        /// Lowest severity that makes the command fail.
+        #[arg(long, value_enum, default_value_t = FailOn::Error)]
+        fail_on: FailOn,
+    },
+    /// Convert every value to one currency.
+    Convert {
+        #[command(flatten)]
+        input: InputArgs,
+        #[command(flatten)]
+        fx: FxArgs,
+        #[arg(long, value_enum, default_value_t = DataFormat::Json)]
+        format: DataFormat,
+        /// Remove values that cannot be converted instead of keeping them unconverted.
+        #[arg(long)]
+        drop_failed: bool,
+    },
+    /// Sum transaction values by year, type, currency or activity.
+    Aggregate {
+        #[command(flatten)]
+        input: InputArgs,
+        /// Grouping columns, comma-separated. Totals are always split by currency.
+        #[arg(long, value_enum, value_delimiter = ',', default_values_t = [aggregate::Dimension::Type])]
+        by: Vec<aggregate::Dimension>,
+        /// Convert to this currency first (requires --fx).
+        #[arg(long, requires = "fx")]
+        to: Option<String>,
+        #[arg(long, requires = "to")]
+        fx: Option<PathBuf>,
+        #[arg(long, value_enum, default_value_t = fx::FxFormat::Monthly)]
+        fx_format: fx::FxFormat,
+        /// Round totals to the currency's minor unit.
+        #[arg(long)]
+        round: bool,
+        #[arg(long, value_enum, default_value_t = TableFormat::Csv)]
+        format: TableFormat,
+    },
+    /// Write a flat CSV table for spreadsheets.
+    Export {
+        #[command(flatten)]
+        input: InputArgs,
+        #[arg(long, value_enum, default_value_t = export::Table::Transactions)]
+        table: export::Table,
+        #[arg(long, value_enum, default_value_t = export::ExportFormat::Csv)]
+        format: export::ExportFormat,
+        /// Write to this file instead of stdout (required for parquet).
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+        /// Also fill the converted_amount column in this currency (requires --fx).
+        #[arg(long, requires = "fx")]
+        to: Option<String>,
+        #[arg(long, requires = "to")]
+        fx: Option<PathBuf>,
+        #[arg(long, value_enum, default_value_t = fx::FxFormat::Monthly)]
+        fx_format: fx::FxFormat,
+        #[arg(long, value_enum, default_value_t = fx::DateArg::ValueDate)]
+        date: fx::DateArg,
+        /// Parquet: round amounts with more than 6 decimal places instead of skipping their rows.
+        #[arg(long)]
+        round_amounts: bool,
+    },
+    /// Insert or update activities in a SQLite database (created if missing).
+    Load {
+        #[command(flatten)]
+        input: InputArgs,
+        #[arg(long)]
+        db: PathBuf,
+        /// Activities per SQLite transaction.
+        #[arg(long, default_value_t = 1000)]
+        batch_size: usize,
+    },
+    /// Summarise the inputs: counts, currencies, transaction types and dates.
+    Stats {
+        #[command(flatten)]
+        input: InputArgs,
+        #[arg(long, value_enum, default_value_t = ReportFormat::Text)]
+        format: ReportFormat,
+    },
+}
+
+#[derive(Debug, Args)]
+struct InputArgs {
+    /// XML or NDJSON files, or directories; stdin if none (or `-`).
+    inputs: Vec<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+struct FxArgs {
+    /// Target currency, e.g. USD.
+    #[arg(long)]
+    to: String,
+    /// Rate file.
+    #[arg(long)]
+    fx: PathBuf,
+    #[arg(long, value_enum, default_value_t = fx::FxFormat::Monthly)]
+    fx_format: fx::FxFormat,
+    /// Which date each value is converted at.
+    #[arg(long, value_enum, default_value_t = fx::DateArg::ValueDate)]
+    date: fx::DateArg,
+    /// Round converted values to the currency's minor unit.
+    #[arg(long)]
+    round: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum DataFormat {
+    /// One pretty-printed JSON array.
+    Json,
+    /// One JSON object per line.
+    Ndjson,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ReportFormat {
+    Text,
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum TableFormat {
+    Csv,
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum FailOn {
+    Error,
+    Warning,
+    Never,
+}
+
+/// Number of inputs, activities or values that failed, for the exit status.
+#[derive(Debug, Default)]
+pub(crate) struct Failures(usize);
+
+impl Failures {
+    pub(crate) fn report(&mut self, source: &str, error: impl std::fmt::Display) {
+        eprintln!("{source}: {error}");
+        self.0 += 1;
+    }
+
+    pub(crate) fn count(&self) -> usize {
+        self.0
+    }
+}
+
+fn run(cli: Cli) -> Result<Failures> {
+    let mut failures = Failures::default();
+    match cli.command {
+        Command::Parse { input, format, flat } => parse::run(&input.inputs, format, flat, &mut failures)?,
+        Command::Validate { input, format, today, fail_on } => {
+            validate::run(&input.inputs, format, today, fail_on, &mut failures)?
+        }
+        Command::Convert { input, fx, format, drop_failed } => {
+            convert::run(&input.inputs, &fx, format, drop_failed, &mut failures)?
+        }
+        Command::Aggregate { input, by, to, fx, fx_format, round, format } => {
+            let fx = match (to, fx) {
+                (Some(to), Some(path)) => Some(fx::load(&path, fx_format).map(|table| (to, table))?),
+                _ => None,
+            };
+            aggregate::run(&input.inputs, &by, fx, round, format, &mut failures)?
+        }
+        Command::Export { input, table, format, output, to, fx, fx_format, date, round_amounts } => {
+            let conversion = match (&to, &fx) {
+                (Some(to), Some(fx)) => Some(export::Conversion { to, fx, fx_format, date }),
+                _ => None,
+            };
+            export::run(&input.inputs, table, format, output.as_deref(), round_amounts, conversion, &mut failures)?
+        }
+        Command::Load { input, db, batch_size } => load::run(&input.inputs, &db, batch_size, &mut failures)?,
+        Command::Stats { input, format } => stats::run(&input.inputs, format, &mut failures)?,
+    }
+    Ok(failures)
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse()) {
+        Ok(failures) if failures.count() == 0 => ExitCode::SUCCESS,
+        Ok(failures) => {
+            eprintln!("iati: {} failure(s)", failures.count());
+            ExitCode::FAILURE
+        }
+        Err(e) => {
+            eprintln!("iati: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,59 @@
+//! Writing records to stdout.
+
+use std::io::{self, BufWriter, Write};
+
+use iati_transform::NdjsonWriter;
+use serde::Serialize;
+
+use crate::DataFormat;
+
+type Stdout = BufWriter<io::StdoutLock<'static>>;
+
+/// Writes records as a JSON array or as NDJSON, streaming in both cases.
+pub(crate) enum RecordWriter {
+    Json { out: Stdout, written: usize },
+    Ndjson(NdjsonWriter<Stdout>),
+}
+
+impl RecordWriter {
+    pub(crate) fn new(format: DataFormat) -> Self {
+        let out = BufWriter::new(io::stdout().lock());
+        match format {
+            DataFormat::Json => RecordWriter::Json { out, written: 0 },
+            DataFormat::Ndjson => RecordWriter::Ndjson(NdjsonWriter::new(out)),
+        }
+    }
+
+    pub(crate) fn write(&mut self, record: &impl Serialize) -> crate::Result<()> {
+        match self {
+            RecordWriter::Json { out, written } => {
+                out.write_all(if *written == 0 { b"[\n" } else { b",\n" })?;
+                serde_json::to_writer_pretty(&mut *out, record)?;
+                *written += 1;
+            }
+            RecordWriter::Ndjson(writer) => writer.write(record)?,
+        }
+        Ok(())
+    }
+
+    pub(crate) fn finish(self) -> crate::Result<()> {
+        match self {
+            RecordWriter::Json { mut out, written } => {
+                out.write_all(if written == 0 { b"[]\n" } else { b"\n]\n" })?;
+                out.flush()?;
+            }
+            RecordWriter::Ndjson(writer) => {
+                writer.into_inner()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Pretty-print one JSON value to stdout.
+pub(crate) fn print_json(value: &impl Serialize) -> crate::Result<()> {
+    let mut out = io::stdout().lock();
+    serde_json::to_writer_pretty(&mut out, value)?;
+    writeln!(out)?;
+    Ok(())
+}
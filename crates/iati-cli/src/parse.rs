@@ -0,0 +1,22 @@
+use std::path::PathBuf;
+
+use iati_transform::flatten_transactions;
+
+use crate::output::RecordWriter;
+use crate::{input, DataFormat, Failures};
+
+pub(crate) fn run(inputs: &[PathBuf], format: DataFormat, flat: bool, failures: &mut Failures) -> crate::Result<()> {
+    let mut writer = RecordWriter::new(format);
+    for (source, activity) in input::activities(inputs) {
+        match activity {
+            Ok(activity) if flat => {
+                for row in flatten_transactions(&activity) {
+                    writer.write(&row)?;
+                }
+            }
+            Ok(activity) => writer.write(&activity)?,
+            Err(e) => failures.report(&source, e),
+        }
+    }
+    writer.finish()
+}
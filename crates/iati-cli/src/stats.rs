@@ -0,0 +1,75 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use chrono::NaiveDate;
+use serde::Serialize;
+
+use crate::output::print_json;
+use crate::{input, Failures, ReportFormat};
+
+#[derive(Debug, Default, Serialize)]
+struct Stats {
+    activities: usize,
+    parse_errors: usize,
+    transactions: usize,
+    budgets: usize,
+    /// Transaction type code -> count.
+    transaction_types: BTreeMap<u16, usize>,
+    /// Currency (explicit or activity default) -> number of values.
+    currencies: BTreeMap<String, usize>,
+    /// Values with no currency and no activity default.
+    values_without_currency: usize,
+    first_transaction: Option<NaiveDate>,
+    last_transaction: Option<NaiveDate>,
+}
+
+pub(crate) fn run(inputs: &[PathBuf], format: ReportFormat, failures: &mut Failures) -> crate::Result<()> {
+    let mut stats = Stats::default();
+    for (source, activity) in input::activities(inputs) {
+        let activity = match activity {
+            Ok(a) => a,
+            Err(e) => {
+                stats.parse_errors += 1;
+                failures.report(&source, e);
+                continue;
+            }
+        };
+        stats.activities += 1;
+        stats.transactions += activity.transactions.len();
+        stats.budgets += activity.budgets.len();
+
+        let values = activity.transactions.iter().map(|t| &t.value).chain(activity.budgets.iter().map(|b| &b.value));
+        for value in values {
+            match value.currency.as_ref().or(activity.default_currency.as_ref()) {
+                Some(c) => *stats.currencies.entry(c.0.clone()).or_default() += 1,
+                None => stats.values_without_currency += 1,
+            }
+        }
+        for tx in &activity.transactions {
+            *stats.transaction_types.entry(tx.tx_type.code()).or_default() += 1;
+            stats.first_transaction = Some(stats.first_transaction.map_or(tx.date, |d| d.min(tx.date)));
+            stats.last_transaction = Some(stats.last_transaction.map_or(tx.date, |d| d.max(tx.date)));
+        }
+    }
+
+    match format {
+        ReportFormat::Json => print_json(&stats)?,
+        ReportFormat::Text => {
+            println!("activities:    {}", stats.activities);
+            println!("parse errors:  {}", stats.parse_errors);
+            println!("transactions:  {}", stats.transactions);
+            println!("budgets:       {}", stats.budgets);
+            if let (Some(first), Some(last)) = (stats.first_transaction, stats.last_transaction) {
+                println!("dates:         {first} to {last}");
+            }
+            let types: Vec<String> = stats.transaction_types.iter().map(|(t, n)| format!("{t}={n}")).collect();
+            println!("tx types:      {}", types.join(" "));
+            let currencies: Vec<String> = stats.currencies.iter().map(|(c, n)| format!("{c}={n}")).collect();
+            println!("currencies:    {}", currencies.join(" "));
+            if stats.values_without_currency > 0 {
+                println!("no currency:   {}", stats.values_without_currency);
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,55 @@
+use std::path::PathBuf;
+
+use chrono::NaiveDate;
+use iati_validate::{Context, Report, Severity, Validator};
+
+use crate::output::print_json;
+use crate::{input, FailOn, Failures, ReportFormat};
+
+pub(crate) fn run(
+    inputs: &[PathBuf],
+    format: ReportFormat,
+    today: Option<NaiveDate>,
+    fail_on: FailOn,
+    failures: &mut Failures,
+) -> crate::Result<()> {
+    let ctx = today.map(Context::new).unwrap_or_default();
+    let validator = Validator::new(ctx);
+
+    let mut report = Report::default();
+    for (source, activity) in input::activities(inputs) {
+        match activity {
+            Ok(activity) => {
+                report.activities_checked += 1;
+                report.findings.extend(validator.validate(&activity));
+            }
+            Err(e) => failures.report(&source, e),
+        }
+    }
+
+    match format {
+        ReportFormat::Json => print_json(&report)?,
+        ReportFormat::Text => {
+            for f in &report.findings {
+                println!("{:?}\t{}\t{}\t{}: {}", f.severity, f.rule_id, f.iati_identifier, f.path, f.message);
+            }
+            println!(
+                "{} activities checked: {} errors, {} warnings, {} info",
+                report.activities_checked,
+                report.count(Severity::Error),
+                report.count(Severity::Warning),
+                report.count(Severity::Info),
+            );
+        }
+    }
+
+    let failing = match fail_on {
+        FailOn::Error => report.count(Severity::Error),
+        FailOn::Warning => report.count(Severity::Error) + report.count(Severity::Warning),
+        FailOn::Never => 0,
+    };
+    if failing > 0 {
+        failures.report("validate", format!("{failing} finding(s) at or above the --fail-on severity"));
+    }
+    Ok(())
+}
@@ -0,0 +1,219 @@
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+
+use arrow_array::{Array, Decimal128Array, RecordBatch};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+const XML: &str = r#"<iati-activities version="2.03">
+  <iati-activity default-currency="EUR">
+    <iati-identifier>XM-TEST-1</iati-identifier>
+    <transaction>
+      <transaction-type code="3"/>
+      <transaction-date iso-date="2024-01-10"/>
+      <value>100</value>
+    </transaction>
+    <transaction>
+      <transaction-type code="3"/>
+      <transaction-date iso-date="2024-02-10"/>
+      <value currency="USD">50</value>
+    </transaction>
+  </iati-activity>
+  <iati-activity default-currency="EUR">
+    <iati-identifier>XM-TEST-2</iati-identifier>
+    <transaction>
+      <transaction-type code="2"/>
+      <transaction-date iso-date="2023-06-01"/>
+      <value>10</value>
+    </transaction>
+  </iati-activity>
+</iati-activities>
+"#;
+
+/// Monthly rates for the 2024 transactions in `XML`; none for 2023.
+const RATES: &str = "currency,year,month,rate\nEUR,2024,1,0.5\nEUR,2024,2,0.5\nUSD,2024,1,1\nUSD,2024,2,1\n";
+
+fn iati(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_iati"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    // Commands reading files may exit without reading stdin.
+    let written = child.stdin.take().unwrap().write_all(stdin.as_bytes());
+    assert!(written.is_ok() || stdin.is_empty(), "{written:?}");
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+/// A file in a per-test temporary directory.
+fn temp_file(test: &str, name: &str, contents: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("iati-cli-{test}-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+#[test]
+fn test_parse_and_stats() {
+    let out = iati(&["parse", "--format", "ndjson"], XML);
+    assert!(out.status.success());
+    let lines: Vec<serde_json::Value> = stdout(&out).lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[1]["iati_identifier"], "XM-TEST-2");
+
+    let json: serde_json::Value = serde_json::from_str(&stdout(&iati(&["parse"], XML))).unwrap();
+    assert_eq!(json.as_array().unwrap().len(), 2);
+
+    let out = iati(&["stats", "--format", "json"], XML);
+    let stats: serde_json::Value = serde_json::from_str(&stdout(&out)).unwrap();
+    assert_eq!(stats["activities"], 2);
+    assert_eq!(stats["transactions"], 3);
+    assert_eq!(stats["currencies"]["EUR"], 2);
+    assert_eq!(stats["first_transaction"], "2023-06-01");
+
+    let bad = iati(&["parse"], "<iati-activity><transaction><value>x</value></transaction></iati-activity>");
+    assert!(!bad.status.success());
+}
+
+#[test]
+fn test_parse_flat_and_read_ndjson() {
+    let out = iati(&["parse", "--flat", "--format", "ndjson"], XML);
+    let rows: Vec<serde_json::Value> = stdout(&out).lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+    assert_eq!(rows.len(), 3);
+    assert_eq!(rows[1]["iati_identifier"], "XM-TEST-1");
+    assert_eq!(rows[1]["currency"], "USD");
+    assert_eq!(rows[2]["tx_type"], 2);
+
+    let ndjson = temp_file("ndjson", "acts.ndjson", &stdout(&iati(&["parse", "--format", "ndjson"], XML)));
+    let out = iati(&["stats", "--format", "json", ndjson.to_str().unwrap()], "");
+    assert!(out.status.success());
+    let stats: serde_json::Value = serde_json::from_str(&stdout(&out)).unwrap();
+    assert_eq!(stats["transactions"], 3);
+}
+
+#[test]
+fn test_aggregate_and_convert_with_rate_file() {
+    let out = iati(&["aggregate", "--by", "year,type"], XML);
+    assert!(out.status.success());
+    assert_eq!(
+        stdout(&out),
+        "year,type,currency,amount\r\n2023,2,EUR,10\r\n2024,3,EUR,100\r\n2024,3,USD,50\r\n"
+    );
+
+    let rates = temp_file("convert", "rates.csv", RATES);
+    let rates = rates.to_str().unwrap();
+    let out = iati(&["aggregate", "--by", "type", "--to", "USD", "--fx", rates], XML);
+    // the 2023 commitment has no EUR rate
+    assert!(!out.status.success());
+    assert_eq!(stdout(&out), "type,currency,amount\r\n3,USD,250.0\r\n");
+    assert!(String::from_utf8_lossy(&out.stderr).contains("XM-TEST-2 transaction[0]: No FX rate for EUR"));
+    assert_eq!(iati(&["aggregate", "--fx", rates], XML).status.code(), Some(2));
+
+
+    let out = iati(&["convert", "--to", "USD", "--fx", rates, "--format", "ndjson", "--drop-failed"], XML);
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("XM-TEST-2 transaction[0]: No FX rate for EUR"));
+    let first: serde_json::Value = serde_json::from_str(stdout(&out).lines().next().unwrap()).unwrap();
+    assert_eq!(first["default_currency"], "USD");
+    assert_eq!(first["transactions"][0]["value"]["amount"], "200.0");
+}
+
+#[test]
+fn test_export_csv() {
+    let rates = temp_file("export", "rates.csv", RATES);
+    let rates = rates.to_str().unwrap();
+    let out = iati(&["export", "--to", "USD", "--fx", rates], XML);
+    assert!(!out.status.success());
+    let csv = stdout(&out);
+    let lines: Vec<&str> = csv.lines().collect();
+    assert!(lines[0].starts_with("iati_identifier,reporting_org_ref,reporting_org_name,activity_start,activity_end,"));
+    assert!(lines[0].ends_with(",converted_amount,converted_currency"));
+    assert!(lines[1].ends_with(",200.0,USD"));
+    assert!(lines[3].ends_with(",,"));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("XM-TEST-2 transaction[0]: FX conversion failed"));
+
+    let out = iati(&["export", "--table", "activities"], XML);
+    assert_eq!(stdout(&out).lines().nth(2), Some("XM-TEST-2,,,EUR,,,1,0,"));
+}
+
+#[test]
+fn test_export_parquet() {
+    let rates = temp_file("parquet", "rates.csv", RATES);
+    let rates = rates.to_str().unwrap();
+    let parquet = temp_file("parquet", "transactions.parquet", "");
+    let out = iati(&["export", "--to", "USD", "--fx", rates, "--format", "parquet", "-o", parquet.to_str().unwrap()], XML);
+    assert!(stdout(&out).is_empty());
+    let reader = ParquetRecordBatchReaderBuilder::try_new(std::fs::File::open(&parquet).unwrap()).unwrap();
+    let batches: Vec<RecordBatch> = reader.build().unwrap().collect::<Result<_, _>>().unwrap();
+    assert_eq!(batches.iter().map(RecordBatch::num_rows).sum::<usize>(), 3);
+    let converted: Vec<Option<String>> = batches
+        .iter()
+        .flat_map(|b| {
+            let col = b.column_by_name("converted_amount").unwrap().as_any().downcast_ref::<Decimal128Array>().unwrap().clone();
+            (0..col.len()).map(move |i| col.is_valid(i).then(|| col.value_as_string(i)))
+        })
+        .collect();
+    // the 2023 commitment has no EUR rate
+    assert_eq!(converted, vec![Some("200.000000".to_string()), Some("50.000000".to_string()), None]);
+    assert!(!iati(&["export", "--format", "parquet"], XML).status.success());
+
+    let tiny = XML.replace("<value>100</value>", "<value>0.0000001</value>");
+    let out = iati(&["export", "--format", "parquet", "-o", parquet.to_str().unwrap()], &tiny);
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("XM-TEST-1 transaction[0]: skipped: amount 0.0000001"));
+    assert!(std::fs::read(&parquet).unwrap().ends_with(b"PAR1"));
+    let args = ["export", "--format", "parquet", "--round-amounts", "-o", parquet.to_str().unwrap()];
+    assert!(iati(&args, &tiny).status.success());
+}
+
+#[test]
+fn test_validate_exit_status() {
+    let out = iati(&["validate", "--today", "2024-12-31", "--format", "json"], XML);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stdout));
+    let report: serde_json::Value = serde_json::from_str(&stdout(&out)).unwrap();
+    assert_eq!(report["activities_checked"], 2);
+
+    // the same data with one future transaction date
+    let future = XML.replace("2024-02-10", "2030-01-01");
+    let out = iati(&["validate", "--today", "2024-12-31", "--fail-on", "error"], &future);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(stdout(&out).contains("tx-date-in-future"));
+    assert!(iati(&["validate", "--today", "2024-12-31", "--fail-on", "never"], &future).status.success());
+
+    let dir = temp_file("validate", "a.xml", XML).parent().unwrap().to_path_buf();
+    let out = iati(&["stats", "--format", "json", dir.to_str().unwrap()], "");
+    let stats: serde_json::Value = serde_json::from_str(&stdout(&out)).unwrap();
+    assert_eq!(stats["activities"], 2);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_unreadable_directory_does_not_stop_other_inputs() {
+    let file = temp_file("walk", "a.xml", XML);
+    let dir = file.parent().unwrap().join("looped");
+    std::fs::create_dir_all(&dir).unwrap();
+    // a symlink to itself: reading it fails with ELOOP
+    std::os::unix::fs::symlink("loop", dir.join("loop")).ok();
+    let out = iati(&["stats", "--format", "json", dir.to_str().unwrap(), file.to_str().unwrap()], "");
+    assert_eq!(out.status.code(), Some(1));
+    let stats: serde_json::Value = serde_json::from_str(&stdout(&out)).unwrap();
+    assert_eq!(stats["activities"], 2);
+}
+
+#[test]
+fn test_load_upserts() {
+    let db = temp_file("load", "activities.sqlite", "");
+    std::fs::remove_file(&db).unwrap();
+    let out = iati(&["load", "--db", db.to_str().unwrap()], XML);
+    assert_eq!(stdout(&out).split(": ").nth(1), Some("2 inserted, 0 updated, 2 activities in total\n"));
+    let future = XML.replace("2024-02-10", "2030-01-01");
+    let out = iati(&["load", "--db", db.to_str().unwrap()], &future);
+    assert_eq!(stdout(&out).split(": ").nth(1), Some("0 inserted, 2 updated, 2 activities in total\n"));
+}
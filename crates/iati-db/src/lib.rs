@@ -0,0 +1,95 @@
+//! iati-db: load IATI activities into a local SQLite database for ad-hoc queries.
+//!
+//! [`Database::load`] bulk-inserts from any iterator of activities, committing
+//! every [`LoadOptions::batch_size`] activities. Activities are keyed by
+//! `iati_identifier`: loading one that is already present replaces its row and
+//! all of its transactions, budgets and sectors. See [`schema`] for the tables.
+
+use std::path::Path;
+
+use rusqlite::Connection;
+use thiserror::Error;
+
+mod read;
+pub mod schema;
+mod write;
+
+pub use crate::schema::SCHEMA_VERSION;
+
+#[derive(Debug, Error)]
+pub enum DbError {
+    #[error("sqlite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+    #[error("database schema version {found} is not supported (expected {expected})")]
+    UnsupportedSchema { found: i32, expected: i32 },
+    #[error("invalid {column} value {value:?} in table {table}")]
+    InvalidValue {
+        table: &'static str,
+        column: &'static str,
+        value: String,
+    },
+}
+
+/// Options for [`Database::load`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoadOptions {
+    /// Activities per SQLite transaction.
+    pub batch_size: usize,
+}
+
+impl Default for LoadOptions {
+    fn default() -> Self {
+        LoadOptions { batch_size: 1000 }
+    }
+}
+
+/// Counts from [`Database::load`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LoadStats {
+    /// Activities not previously in the database.
+    pub inserted: usize,
+    /// Activities that replaced an existing one with the same identifier.
+    pub updated: usize,
+}
+
+/// A SQLite database with the iati-db schema.
+pub struct Database {
+    conn: Connection,
+}
+
+impl Database {
+    /// Open (or create) a database file and create any missing tables.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, DbError> {
+        Self::from_connection(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> Result<Self, DbError> {
+        Self::from_connection(Connection::open_in_memory()?)
+    }
+
+    /// Use an existing connection, creating any missing tables.
+    pub fn from_connection(conn: Connection) -> Result<Self, DbError> {
+        let version: i32 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        if version != 0 && version != SCHEMA_VERSION {
+            return Err(DbError::UnsupportedSchema { found: version, expected: SCHEMA_VERSION });
+        }
+        conn.execute_batch(schema::SCHEMA)?;
+        conn.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+        conn.pragma_update(None, "foreign_keys", true)?;
+        Ok(Database { conn })
+    }
+
+    /// The underlying connection, for queries.
+    pub fn connection(&self) -> &Connection {
+        &self.conn
+    }
+
+    pub fn into_connection(self) -> Connection {
+        self.conn
+    }
+
+    /// Number of activities in the database.
+    pub fn count(&self) -> Result<usize, DbError> {
+        Ok(self.conn.query_row("SELECT COUNT(*) FROM activities", [], |row| row.get(0))?)
+    }
+}
@@ -0,0 +1,179 @@
+//! Reading activities back out of the database.
+
+use std::str::FromStr;
+
+use iati_types::{Activity, Budget, CurrencyCode, Money, OrgRef, OtherIdentifier, Sector, Transaction, TxType};
+use rusqlite::{OptionalExtension, Row};
+use rust_decimal::Decimal;
+
+use crate::{Database, DbError};
+
+impl Database {
+    /// Rebuild an activity from its rows, exactly as it was loaded.
+    pub fn activity(&self, iati_identifier: &str) -> Result<Option<Activity>, DbError> {
+        let found = self
+            .conn
+            .prepare_cached(
+                "SELECT id, default_currency, reporting_org_ref, reporting_org_name, activity_start, activity_end
+                 FROM activities WHERE iati_identifier = ?1",
+            )?
+            .query_row([iati_identifier], |row| {
+                let mut activity = Activity::new(iati_identifier);
+                activity.default_currency = currency(row, 1)?;
+                activity.reporting_org = org(row, 2, 3, None)?;
+                activity.activity_start = row.get(4)?;
+                activity.activity_end = row.get(5)?;
+                Ok((row.get::<_, i64>(0)?, activity))
+            })
+            .optional()?;
+        let Some((id, mut activity)) = found else {
+            return Ok(None);
+        };
+
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT ref, tx_type, date, amount, currency, value_date, currency_hint, provider_org_ref, provider_org_name,
+             provider_activity_id, receiver_org_ref, receiver_org_name, receiver_activity_id
+             FROM transactions WHERE activity_id = ?1 ORDER BY idx",
+        )?;
+        let mut rows = stmt.query([id])?;
+        while let Some(row) = rows.next()? {
+            let value = Money {
+                amount: decimal(row, 3, "transactions", "amount")?,
+                currency: currency(row, 4)?,
+                value_date: row.get(5)?,
+            };
+            let mut t = Transaction::new(TxType::from(row.get::<_, u16>(1)?), row.get(2)?, value);
+            t.ref_id = row.get(0)?;
+            t.currency_hint = currency(row, 6)?;
+            t.provider_org = org(row, 7, 8, Some(9))?;
+            t.receiver_org = org(row, 10, 11, Some(12))?;
+            activity.transactions.push(t);
+        }
+
+        let mut stmt = self.conn.prepare_cached(
+            "SELECT budget_type, status, period_start, period_end, amount, currency, value_date
+             FROM budgets WHERE activity_id = ?1 ORDER BY idx",
+        )?;
+        let mut rows = stmt.query([id])?;
+        while let Some(row) = rows.next()? {
+            let value = Money {
+                amount: decimal(row, 4, "budgets", "amount")?,
+                currency: currency(row, 5)?,
+                value_date: row.get(6)?,
+            };
+            let budget = Budget::new(row.get(2)?, row.get(3)?, value)
+                .with_type(row.get::<_, u16>(0)?.into())
+                .with_status(row.get::<_, u16>(1)?.into());
+            activity.budgets.push(budget);
+        }
+
+        let mut stmt = self
+            .conn
+            .prepare_cached("SELECT code, vocabulary, percentage FROM sectors WHERE activity_id = ?1 ORDER BY idx")?;
+        let mut rows = stmt.query([id])?;
+        while let Some(row) = rows.next()? {
+            let percentage = match row.get::<_, Option<String>>(2)? {
+                Some(_) => Some(decimal(row, 2, "sectors", "percentage")?),
+                None => None,
+            };
+            activity.sectors.push(Sector { code: row.get(0)?, vocabulary: row.get(1)?, percentage });
+        }
+
+        let mut stmt = self
+            .conn
+            .prepare_cached("SELECT ref, type_code FROM other_identifiers WHERE activity_id = ?1 ORDER BY idx")?;
+        let mut rows = stmt.query([id])?;
+        while let Some(row) = rows.next()? {
+            activity.other_identifiers.push(OtherIdentifier { ref_id: row.get(0)?, type_code: row.get(1)? });
+        }
+        Ok(Some(activity))
+    }
+}
+
+fn decimal(row: &Row<'_>, idx: usize, table: &'static str, column: &'static str) -> Result<Decimal, DbError> {
+    let text: String = row.get(idx)?;
+    Decimal::from_str(&text).map_err(|_| DbError::InvalidValue { table, column, value: text })
+}
+
+fn currency(row: &Row<'_>, idx: usize) -> rusqlite::Result<Option<CurrencyCode>> {
+    Ok(row.get::<_, Option<String>>(idx)?.map(CurrencyCode))
+}
+
+/// An `OrgRef` from its columns, or `None` if they are all NULL.
+fn org(row: &Row<'_>, ref_idx: usize, name_idx: usize, activity_idx: Option<usize>) -> rusqlite::Result<Option<OrgRef>> {
+    let org = OrgRef {
+        ref_id: row.get(ref_idx)?,
+        name: row.get(name_idx)?,
+        activity_id: activity_idx.map(|i| row.get(i)).transpose()?.flatten(),
+    };
+    Ok((org != OrgRef::default()).then_some(org))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use iati_types::{BudgetStatus, CurrencyCode, Money, Sector, TxType};
+    use rust_decimal::Decimal;
+
+    use super::*;
+    use crate::LoadOptions;
+
+    fn activity(id: &str, amount: i64) -> Activity {
+        let date = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap();
+        let mut a = Activity::new(id);
+        a.default_currency = Some(CurrencyCode::from("EUR"));
+        a.reporting_org = Some(OrgRef { ref_id: Some("XM-DAC-1".into()), name: Some("Donor".into()), activity_id: None });
+        a.activity_start = Some(date);
+        let mut value = Money::new(Decimal::new(amount, 2));
+        value.value_date = Some(date);
+        let tx = Transaction::new(TxType::Disbursement, date, value)
+            .with_receiver(OrgRef { ref_id: Some("XM-OCHA-2".into()), name: None, activity_id: Some("XM-OCHA-2-A".into()) })
+            .with_ref("T-1");
+        a.transactions.push(tx);
+        a.budgets.push(
+            Budget::new(date, NaiveDate::from_ymd_opt(2024, 12, 31).unwrap(), Money::new(Decimal::new(1, 10)))
+                .with_status(BudgetStatus::Committed),
+        );
+        a.sectors.push(Sector { code: "11110".into(), vocabulary: None, percentage: Some(Decimal::new(1000, 1)) });
+        a.other_identifiers.push(OtherIdentifier { ref_id: "XM-OLD".into(), type_code: Some("B1".into()) });
+        a
+    }
+
+    #[test]
+    fn round_trips_activities_exactly() {
+        let mut db = Database::open_in_memory().unwrap();
+        let a = activity("XM-DAC-1-A", 123456);
+        let stats = db.load([a.clone(), activity("XM-DAC-1-B", 1)], &LoadOptions { batch_size: 1 }).unwrap();
+        assert_eq!((stats.inserted, stats.updated), (2, 0));
+        assert_eq!(db.activity("XM-DAC-1-A").unwrap(), Some(a));
+        assert_eq!(db.activity("missing").unwrap(), None);
+
+        let amount: String = db
+            .connection()
+            .query_row("SELECT amount FROM budgets", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(amount, "0.0000000001");
+        let orgs: i64 = db.connection().query_row("SELECT COUNT(*) FROM organisations", [], |row| row.get(0)).unwrap();
+        assert_eq!(orgs, 2);
+    }
+
+    #[test]
+    fn upserts_replace_child_rows() {
+        let mut db = Database::open_in_memory().unwrap();
+        db.load([activity("XM-DAC-1-A", 100)], &LoadOptions::default()).unwrap();
+
+        let mut revised = activity("XM-DAC-1-A", 250);
+        revised.transactions.push(revised.transactions[0].clone());
+        revised.sectors.clear();
+        assert!(db.upsert(&revised).unwrap());
+        assert_eq!(db.count().unwrap(), 1);
+        assert_eq!(db.activity("XM-DAC-1-A").unwrap(), Some(revised));
+        let sectors: i64 = db.connection().query_row("SELECT COUNT(*) FROM sectors", [], |row| row.get(0)).unwrap();
+        assert_eq!(sectors, 0);
+
+        assert!(db.remove("XM-DAC-1-A").unwrap());
+        let transactions: i64 =
+            db.connection().query_row("SELECT COUNT(*) FROM transactions", [], |row| row.get(0)).unwrap();
+        assert_eq!((db.count().unwrap(), transactions), (0, 0));
+    }
+}
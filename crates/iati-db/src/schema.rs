@@ -0,0 +1,79 @@
+//! The SQLite schema.
+//!
+//! Amounts and percentages are stored as TEXT in `rust_decimal` notation
+//! ("1234.50"), so they read back exactly; `CAST(amount AS REAL)` gives an
+//! approximate number for ad-hoc SQL. Dates are ISO 8601 TEXT ("2024-03-15").
+//! Child rows keep their position in the activity in `idx`.
+
+/// Bumped whenever the tables below change; stored in `PRAGMA user_version`.
+pub const SCHEMA_VERSION: i32 = 1;
+
+pub(crate) const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS organisations (
+    ref  TEXT PRIMARY KEY,
+    name TEXT
+);
+
+CREATE TABLE IF NOT EXISTS activities (
+    id                 INTEGER PRIMARY KEY,
+    iati_identifier    TEXT NOT NULL UNIQUE,
+    default_currency   TEXT,
+    reporting_org_ref  TEXT REFERENCES organisations (ref),
+    reporting_org_name TEXT,
+    activity_start     TEXT,
+    activity_end       TEXT
+);
+
+CREATE TABLE IF NOT EXISTS transactions (
+    activity_id          INTEGER NOT NULL REFERENCES activities (id) ON DELETE CASCADE,
+    idx                  INTEGER NOT NULL,
+    ref                  TEXT,
+    tx_type              INTEGER NOT NULL,
+    date                 TEXT NOT NULL,
+    amount               TEXT NOT NULL,
+    currency             TEXT,
+    value_date           TEXT,
+    currency_hint        TEXT,
+    provider_org_ref     TEXT REFERENCES organisations (ref),
+    provider_org_name    TEXT,
+    provider_activity_id TEXT,
+    receiver_org_ref     TEXT REFERENCES organisations (ref),
+    receiver_org_name    TEXT,
+    receiver_activity_id TEXT,
+    PRIMARY KEY (activity_id, idx)
+);
+
+CREATE TABLE IF NOT EXISTS budgets (
+    activity_id  INTEGER NOT NULL REFERENCES activities (id) ON DELETE CASCADE,
+    idx          INTEGER NOT NULL,
+    budget_type  INTEGER NOT NULL,
+    status       INTEGER NOT NULL,
+    period_start TEXT NOT NULL,
+    period_end   TEXT NOT NULL,
+    amount       TEXT NOT NULL,
+    currency     TEXT,
+    value_date   TEXT,
+    PRIMARY KEY (activity_id, idx)
+);
+
+CREATE TABLE IF NOT EXISTS sectors (
+    activity_id INTEGER NOT NULL REFERENCES activities (id) ON DELETE CASCADE,
+    idx         INTEGER NOT NULL,
+    code        TEXT NOT NULL,
+    vocabulary  TEXT,
+    percentage  TEXT,
+    PRIMARY KEY (activity_id, idx)
+);
+
+CREATE TABLE IF NOT EXISTS other_identifiers (
+    activity_id INTEGER NOT NULL REFERENCES activities (id) ON DELETE CASCADE,
+    idx         INTEGER NOT NULL,
+    ref         TEXT NOT NULL,
+    type_code   TEXT,
+    PRIMARY KEY (activity_id, idx)
+);
+
+CREATE INDEX IF NOT EXISTS transactions_provider ON transactions (provider_org_ref);
+CREATE INDEX IF NOT EXISTS transactions_receiver ON transactions (receiver_org_ref);
+CREATE INDEX IF NOT EXISTS sectors_code ON sectors (code);
+";
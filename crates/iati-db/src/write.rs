@@ -0,0 +1,164 @@
+//! Inserting and upserting activities.
+
+use iati_types::{Activity, OrgRef};
+use rusqlite::{params, OptionalExtension, Transaction as SqlTransaction};
+
+use crate::{Database, DbError, LoadOptions, LoadStats};
+
+impl Database {
+    /// Insert or replace a single activity, in its own SQLite transaction.
+    /// Returns `true` if it replaced an existing activity.
+    pub fn upsert(&mut self, activity: &Activity) -> Result<bool, DbError> {
+        let tx = self.conn.transaction()?;
+        let updated = upsert(&tx, activity)?;
+        tx.commit()?;
+        Ok(updated)
+    }
+
+    /// Insert or replace every activity, committing every `options.batch_size`
+    /// activities. On error, activities in earlier batches stay committed.
+    pub fn load(
+        &mut self,
+        activities: impl IntoIterator<Item = Activity>,
+        options: &LoadOptions,
+    ) -> Result<LoadStats, DbError> {
+        let mut stats = LoadStats::default();
+        let mut activities = activities.into_iter().peekable();
+        while activities.peek().is_some() {
+            let tx = self.conn.transaction()?;
+            for activity in activities.by_ref().take(options.batch_size.max(1)) {
+                match upsert(&tx, &activity)? {
+                    true => stats.updated += 1,
+                    false => stats.inserted += 1,
+                }
+            }
+            tx.commit()?;
+        }
+        Ok(stats)
+    }
+
+    /// Remove an activity and its child rows. Returns `false` if it was not present.
+    pub fn remove(&mut self, iati_identifier: &str) -> Result<bool, DbError> {
+        let n = self.conn.execute("DELETE FROM activities WHERE iati_identifier = ?1", [iati_identifier])?;
+        Ok(n > 0)
+    }
+}
+
+fn upsert(tx: &SqlTransaction<'_>, activity: &Activity) -> Result<bool, DbError> {
+    let existing: Option<i64> = tx
+        .prepare_cached("SELECT id FROM activities WHERE iati_identifier = ?1")?
+        .query_row([&activity.iati_identifier], |row| row.get(0))
+        .optional()?;
+
+    let reporting_org = activity.reporting_org.as_ref();
+    upsert_org(tx, reporting_org)?;
+    let activity_id = match existing {
+        Some(id) => {
+            tx.prepare_cached(
+                "UPDATE activities SET default_currency = ?2, reporting_org_ref = ?3, reporting_org_name = ?4,
+                 activity_start = ?5, activity_end = ?6 WHERE id = ?1",
+            )?
+            .execute(params![
+                id,
+                activity.default_currency.as_ref().map(|c| &c.0),
+                reporting_org.and_then(|o| o.ref_id.as_ref()),
+                reporting_org.and_then(|o| o.name.as_ref()),
+                activity.activity_start,
+                activity.activity_end,
+            ])?;
+            for table in ["transactions", "budgets", "sectors", "other_identifiers"] {
+                tx.prepare_cached(&format!("DELETE FROM {table} WHERE activity_id = ?1"))?.execute([id])?;
+            }
+            id
+        }
+        None => {
+            tx.prepare_cached(
+                "INSERT INTO activities (iati_identifier, default_currency, reporting_org_ref, reporting_org_name,
+                 activity_start, activity_end) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            )?
+            .execute(params![
+                activity.iati_identifier,
+                activity.default_currency.as_ref().map(|c| &c.0),
+                reporting_org.and_then(|o| o.ref_id.as_ref()),
+                reporting_org.and_then(|o| o.name.as_ref()),
+                activity.activity_start,
+                activity.activity_end,
+            ])?;
+            tx.last_insert_rowid()
+        }
+    };
+
+    let mut insert = tx.prepare_cached(
+        "INSERT INTO transactions (activity_id, idx, ref, tx_type, date, amount, currency, value_date, currency_hint,
+         provider_org_ref, provider_org_name, provider_activity_id, receiver_org_ref, receiver_org_name, receiver_activity_id)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
+    )?;
+    for (idx, t) in activity.transactions.iter().enumerate() {
+        upsert_org(tx, t.provider_org.as_ref())?;
+        upsert_org(tx, t.receiver_org.as_ref())?;
+        let (provider, receiver) = (t.provider_org.as_ref(), t.receiver_org.as_ref());
+        insert.execute(params![
+            activity_id,
+            idx,
+            t.ref_id,
+            t.tx_type.code(),
+            t.date,
+            t.value.amount.to_string(),
+            t.value.currency.as_ref().map(|c| &c.0),
+            t.value.value_date,
+            t.currency_hint.as_ref().map(|c| &c.0),
+            provider.and_then(|o| o.ref_id.as_ref()),
+            provider.and_then(|o| o.name.as_ref()),
+            provider.and_then(|o| o.activity_id.as_ref()),
+            receiver.and_then(|o| o.ref_id.as_ref()),
+            receiver.and_then(|o| o.name.as_ref()),
+            receiver.and_then(|o| o.activity_id.as_ref()),
+        ])?;
+    }
+
+    let mut insert = tx.prepare_cached(
+        "INSERT INTO budgets (activity_id, idx, budget_type, status, period_start, period_end, amount, currency, value_date)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+    )?;
+    for (idx, b) in activity.budgets.iter().enumerate() {
+        insert.execute(params![
+            activity_id,
+            idx,
+            b.budget_type.code(),
+            b.status.code(),
+            b.period_start,
+            b.period_end,
+            b.value.amount.to_string(),
+            b.value.currency.as_ref().map(|c| &c.0),
+            b.value.value_date,
+        ])?;
+    }
+
+    let mut insert = tx.prepare_cached(
+        "INSERT INTO sectors (activity_id, idx, code, vocabulary, percentage) VALUES (?1, ?2, ?3, ?4, ?5)",
+    )?;
+    for (idx, s) in activity.sectors.iter().enumerate() {
+        insert.execute(params![activity_id, idx, s.code, s.vocabulary, s.percentage.map(|p| p.to_string())])?;
+    }
+
+    let mut insert = tx.prepare_cached(
+        "INSERT INTO other_identifiers (activity_id, idx, ref, type_code) VALUES (?1, ?2, ?3, ?4)",
+    )?;
+    for (idx, o) in activity.other_identifiers.iter().enumerate() {
+        insert.execute(params![activity_id, idx, o.ref_id, o.type_code])?;
+    }
+
+    Ok(existing.is_some())
+}
+
+/// Record an organisation by its ref, keeping the latest non-empty name.
+fn upsert_org(tx: &SqlTransaction<'_>, org: Option<&OrgRef>) -> Result<(), DbError> {
+    if let Some(OrgRef { ref_id: Some(ref_id), name, .. }) = org {
+        tx.prepare_cached(
+            "INSERT INTO organisations (ref, name) VALUES (?1, ?2)
+             ON CONFLICT (ref) DO UPDATE SET name = COALESCE(excluded.name, name)",
+        )?
+        .execute(params![ref_id, name])?;
+    }
+    Ok(())
+}
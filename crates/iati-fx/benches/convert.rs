@@ -0,0 +1,72 @@
+//! Repeated `convert_money` against `FxTable` vs `IndexedFxTable::convert_many`.
+//!
+//! Run with `cargo bench -p iati-fx`.
+
+use chrono::NaiveDate;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use iati_fx::{convert_money, FxTable, IndexedFxTable, YearMonth};
+use iati_types::{CurrencyCode, Money};
+use rust_decimal::Decimal;
+
+const CURRENCIES: [&str; 8] = ["USD", "EUR", "GBP", "DKK", "SEK", "NOK", "JPY", "CHF"];
+
+fn table() -> FxTable {
+    let mut table = FxTable::new();
+    for (i, code) in CURRENCIES.iter().enumerate() {
+        for year in 2000..2025 {
+            for month in 1..=12 {
+                let rate = Decimal::new(1000 + (i as i64) * 250 + i64::from(month), 3);
+                table.insert(CurrencyCode::from(*code), YearMonth { year, month }, rate).unwrap();
+            }
+        }
+    }
+    table
+}
+
+fn values(n: usize) -> (Vec<Money>, Vec<Option<NaiveDate>>) {
+    let mut values = Vec::with_capacity(n);
+    let mut dates = Vec::with_capacity(n);
+    for i in 0..n {
+        let mut money = Money::new(Decimal::new(i as i64 * 137 % 100_000, 2));
+        money.currency = Some(CurrencyCode::from(CURRENCIES[i % CURRENCIES.len()]));
+        values.push(money);
+        let year = 2000 + (i % 25) as i32;
+        let month = (i % 12) as u32 + 1;
+        dates.push(NaiveDate::from_ymd_opt(year, month, 15));
+    }
+    (values, dates)
+}
+
+fn bench_convert(c: &mut Criterion) {
+    let table = table();
+    let indexed = IndexedFxTable::from_table(&table).unwrap();
+    let target = CurrencyCode::from("USD");
+
+    let mut group = c.benchmark_group("convert");
+    for n in [1_000, 100_000] {
+        let (values, dates) = values(n);
+        group.throughput(Throughput::Elements(n as u64));
+
+        group.bench_with_input(BenchmarkId::new("convert_money/FxTable", n), &n, |b, _| {
+            b.iter(|| {
+                for (money, date) in values.iter().zip(&dates) {
+                    black_box(convert_money(money, None, &target, *date, &table).unwrap());
+                }
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("convert_money/IndexedFxTable", n), &n, |b, _| {
+            b.iter(|| {
+                for (money, date) in values.iter().zip(&dates) {
+                    black_box(convert_money(money, None, &target, *date, &indexed).unwrap());
+                }
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("convert_many/IndexedFxTable", n), &n, |b, _| {
+            b.iter(|| black_box(indexed.convert_many(&values, &dates, None, &target)))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_convert);
+criterion_main!(benches);
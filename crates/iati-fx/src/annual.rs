@@ -0,0 +1,115 @@
+use std::collections::BTreeMap;
+
+use chrono::{Datelike, NaiveDate};
+use iati_types::CurrencyCode;
+use rust_decimal::Decimal;
+
+use crate::check::RateIssue;
+use crate::provider::{check_rate, cross_rate, FxError, FxProvider};
+use crate::table::FxTable;
+
+/// What to do with a year that has fewer than 12 monthly rates when deriving
+/// annual averages from a monthly table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MissingMonths {
+    /// Leave the year out unless all 12 months are present.
+    #[default]
+    Strict,
+    /// Average whichever months are present.
+    AverageAvailable,
+    /// Average the months present if there are at least this many.
+    MinMonths(u32),
+}
+
+impl MissingMonths {
+    fn accepts(self, months: u32) -> bool {
+        match self {
+            MissingMonths::Strict => months >= 12,
+            MissingMonths::AverageAvailable => months > 0,
+            MissingMonths::MinMonths(n) => months >= n.max(1),
+        }
+    }
+}
+
+/// Annual average "domestic currency per USD" rates, as used for OECD DAC statistics.
+///
+/// Values are converted at the rate for the calendar year of their date. USD is
+/// implicitly 1 when the table has no USD row.
+#[derive(Debug, Clone, Default)]
+pub struct AnnualFxTable {
+    /// Map ((currency, year) -> rate)
+    ncu_per_usd: BTreeMap<(CurrencyCode, i32), Decimal>,
+}
+
+impl AnnualFxTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add one rate, rejecting zero and negative values.
+    pub fn insert(&mut self, currency: CurrencyCode, year: i32, rate: Decimal) -> Result<(), RateIssue> {
+        if rate <= Decimal::ZERO {
+            return Err(RateIssue::NonPositiveAnnual { currency, year, rate });
+        }
+        self.ncu_per_usd.insert((currency, year), rate);
+        Ok(())
+    }
+
+    /// All rates, keyed by (currency, year).
+    pub fn rates(&self) -> &BTreeMap<(CurrencyCode, i32), Decimal> {
+        &self.ncu_per_usd
+    }
+
+    /// Derive annual averages as the arithmetic mean of the monthly rates in `monthly`.
+    pub fn from_monthly(monthly: &FxTable, missing: MissingMonths) -> Self {
+        // (currency, year) -> (sum, months)
+        let mut acc: BTreeMap<(CurrencyCode, i32), (Decimal, u32)> = BTreeMap::new();
+        for ((currency, ym), rate) in &monthly.ncu_per_usd {
+            let e = acc.entry((currency.clone(), ym.year)).or_default();
+            e.0 += *rate;
+            e.1 += 1;
+        }
+        let ncu_per_usd = acc
+            .into_iter()
+            .filter(|(_, (_, months))| missing.accepts(*months))
+            .map(|(key, (sum, months))| (key, sum / Decimal::from(months)))
+            .collect();
+        AnnualFxTable { ncu_per_usd }
+    }
+
+    /// Rate (currency per USD) for `year`.
+    pub fn rate_for(&self, code: &CurrencyCode, year: i32) -> Option<Decimal> {
+        match self.ncu_per_usd.get(&(code.clone(), year)) {
+            Some(r) => Some(*r),
+            None if code.0 == "USD" => Some(Decimal::ONE),
+            None => None,
+        }
+    }
+
+    fn get_annual_usd_rate(&self, code: &CurrencyCode, date: NaiveDate) -> Result<Decimal, FxError> {
+        if let Some(rate) = self.rate_for(code, date.year()) {
+            return check_rate(code, date, rate);
+        }
+        let year_start = |((_, year), _): (&(CurrencyCode, i32), _)| NaiveDate::from_ymd_opt(*year, 1, 1);
+        let before = self.ncu_per_usd.range((code.clone(), i32::MIN)..(code.clone(), date.year())).next_back();
+        let after = self.ncu_per_usd.range((code.clone(), date.year())..=(code.clone(), i32::MAX)).next();
+        Err(FxError::missing(code, date, before.and_then(year_start), after.and_then(year_start)))
+    }
+}
+
+impl FxProvider for AnnualFxTable {
+    fn get_rate(
+        &self,
+        source_currency: &CurrencyCode,
+        target_currency: &CurrencyCode,
+        date: NaiveDate,
+    ) -> Result<Decimal, FxError> {
+        if source_currency == target_currency {
+            return Ok(Decimal::ONE);
+        }
+
+        let r_from: Decimal = self.get_annual_usd_rate(source_currency, date)?;
+        let r_to: Decimal = self.get_annual_usd_rate(target_currency, date).map_err(FxError::on_target_leg)?;
+        cross_rate(r_to, r_from)
+    }
+}
@@ -0,0 +1,174 @@
+//! Sanity checks and coverage reporting for monthly rate tables.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use chrono::NaiveDate;
+use iati_types::CurrencyCode;
+use rust_decimal::Decimal;
+use thiserror::Error;
+
+use crate::table::{FxTable, YearMonth};
+
+/// A suspicious or unusable rate found in an [`FxTable`], or rejected on insert.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum RateIssue {
+    #[error("{currency} {period}: rate {rate} is not positive")]
+    NonPositive {
+        currency: CurrencyCode,
+        period: YearMonth,
+        rate: Decimal,
+    },
+
+    #[error("{currency} {year}: rate {rate} is not positive")]
+    NonPositiveAnnual {
+        currency: CurrencyCode,
+        year: i32,
+        rate: Decimal,
+    },
+
+    #[error("{currency} {date}: rate {rate} is not positive")]
+    NonPositiveDaily {
+        currency: CurrencyCode,
+        date: NaiveDate,
+        rate: Decimal,
+    },
+
+    /// The rate moved by more than the jump threshold since the previous
+    /// available month, e.g. a unit error or an unrecorded redenomination.
+    #[error("{currency} {from} -> {to}: rate jumped from {previous} to {rate}")]
+    Jump {
+        currency: CurrencyCode,
+        from: YearMonth,
+        to: YearMonth,
+        previous: Decimal,
+        rate: Decimal,
+    },
+
+    /// Months without a rate between the currency's first and last month (inclusive range).
+    #[error("{currency}: no rates from {from} to {to}")]
+    Gap {
+        currency: CurrencyCode,
+        from: YearMonth,
+        to: YearMonth,
+    },
+}
+
+/// Thresholds for [`FxTable::check`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CheckOptions {
+    /// Flag month-over-month changes by more than this factor in either direction.
+    pub max_jump: Decimal,
+}
+
+impl Default for CheckOptions {
+    fn default() -> Self {
+        CheckOptions { max_jump: Decimal::from(5) }
+    }
+}
+
+/// Months available for one currency.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CurrencyCoverage {
+    pub first: YearMonth,
+    pub last: YearMonth,
+    /// Number of months with a rate.
+    pub months: usize,
+    /// Gaps between `first` and `last`, as inclusive `(from, to)` ranges.
+    pub gaps: Vec<(YearMonth, YearMonth)>,
+}
+
+impl CurrencyCoverage {
+    pub fn is_complete(&self) -> bool {
+        self.gaps.is_empty()
+    }
+}
+
+/// Which currencies and months an [`FxTable`] covers.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Coverage {
+    pub currencies: BTreeMap<CurrencyCode, CurrencyCoverage>,
+}
+
+impl Coverage {
+    pub fn get(&self, currency: &CurrencyCode) -> Option<&CurrencyCoverage> {
+        self.currencies.get(currency)
+    }
+}
+
+impl fmt::Display for Coverage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (currency, c) in &self.currencies {
+            write!(f, "{currency}: {} to {} ({} months", c.first, c.last, c.months)?;
+            if !c.gaps.is_empty() {
+                let gaps: Vec<String> = c.gaps.iter().map(|(from, to)| format!("{from}..{to}")).collect();
+                write!(f, ", missing {}", gaps.join(", "))?;
+            }
+            writeln!(f, ")")?;
+        }
+        Ok(())
+    }
+}
+
+impl FxTable {
+    /// Check every rate: non-positive values, month-over-month jumps beyond
+    /// `options.max_jump`, and coverage gaps, grouped by currency.
+    pub fn check(&self, options: &CheckOptions) -> Vec<RateIssue> {
+        let mut issues = Vec::new();
+        let mut previous: Option<(&CurrencyCode, YearMonth, Decimal)> = None;
+        for ((currency, period), rate) in &self.ncu_per_usd {
+            let period = *period;
+            if *rate <= Decimal::ZERO {
+                issues.push(RateIssue::NonPositive { currency: currency.clone(), period, rate: *rate });
+            }
+            if let Some((prev_currency, prev_period, prev_rate)) = previous
+                && prev_currency == currency
+            {
+                if prev_period.next() != period {
+                    issues.push(RateIssue::Gap {
+                        currency: currency.clone(),
+                        from: prev_period.next(),
+                        to: period.prev(),
+                    });
+                }
+                if prev_rate > Decimal::ZERO && *rate > Decimal::ZERO {
+                    let ratio = (*rate / prev_rate).max(prev_rate / *rate);
+                    if ratio > options.max_jump {
+                        issues.push(RateIssue::Jump {
+                            currency: currency.clone(),
+                            from: prev_period,
+                            to: period,
+                            previous: prev_rate,
+                            rate: *rate,
+                        });
+                    }
+                }
+            }
+            previous = Some((currency, period, *rate));
+        }
+        issues
+    }
+
+    /// Per-currency summary of the months covered.
+    pub fn coverage(&self) -> Coverage {
+        let mut currencies: BTreeMap<CurrencyCode, CurrencyCoverage> = BTreeMap::new();
+        for (currency, period) in self.ncu_per_usd.keys() {
+            match currencies.get_mut(currency) {
+                Some(c) => {
+                    if c.last.next() != *period {
+                        c.gaps.push((c.last.next(), period.prev()));
+                    }
+                    c.last = *period;
+                    c.months += 1;
+                }
+                None => {
+                    currencies.insert(
+                        currency.clone(),
+                        CurrencyCoverage { first: *period, last: *period, months: 1, gaps: Vec::new() },
+                    );
+                }
+            }
+        }
+        Coverage { currencies }
+    }
+}
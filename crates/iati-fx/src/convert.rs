@@ -1,7 +1,8 @@
 use chrono::NaiveDate;
-use iati_types::{Activity, CurrencyCode, Money};
+use iati_types::{Activity, Budget, CurrencyCode, Money, MoneyBag, RoundingPolicy, Transaction};
 use rust_decimal::Decimal;
 
+use crate::date_policy::{DateContext, DatePolicy};
 use crate::provider::{FxError, FxProvider};
 
 /// Follows IATI logic: use explicit transaction currency, else fall back to activity.default_currency.
@@ -24,13 +25,13 @@ pub fn convert_money(
     fx: &impl FxProvider,
 ) -> Result<Money, FxError> {
     let src: CurrencyCode = resolve_source_currency(money, activity_default)
-        .ok_or(FxError::UnsupportedCurrency(CurrencyCode::from("UNKNOWN")))?;
+        .ok_or(FxError::MissingSourceCurrency)?;
 
     let date: NaiveDate = value_date.ok_or(FxError::MissingDate)?;
 
     let rate: Decimal = fx.get_rate(&src, target, date)?;
 
-    let new_amount: Decimal = money.amount * rate;
+    let new_amount: Decimal = money.amount.checked_mul(rate).ok_or(FxError::Overflow)?;
 
     Ok(Money {
         amount: new_amount,
@@ -39,27 +40,191 @@ pub fn convert_money(
     })
 }
 
+/// Convert a Money amount at the date chosen by `policy`.
+/// Errors with `FxError::MissingDate` when the policy's date is unavailable.
+pub fn convert_money_with(
+    money: &Money,
+    activity_default: Option<&CurrencyCode>,
+    target: &CurrencyCode,
+    policy: &DatePolicy,
+    ctx: &DateContext,
+    fx: &impl FxProvider,
+) -> Result<Money, FxError> {
+    convert_money(money, activity_default, target, policy.resolve(ctx), fx)
+}
+
+/// Convert every total in a `MoneyBag` to `target` at `date` and add them up.
+/// Errors with `FxError::Overflow` if a converted total or the sum overflows.
+pub fn convert_bag(
+    bag: &MoneyBag,
+    target: &CurrencyCode,
+    date: NaiveDate,
+    fx: &impl FxProvider,
+) -> Result<Money, FxError> {
+    let mut total = MoneyBag::new();
+    for (currency, amount) in bag.iter() {
+        let converted = amount.checked_mul(fx.get_rate(currency, target, date)?).ok_or(FxError::Overflow)?;
+        total.add(target.clone(), converted).map_err(|_| FxError::Overflow)?;
+    }
+    Ok(Money {
+        amount: total.get(target).unwrap_or_default(),
+        currency: Some(target.clone()),
+        value_date: Some(date),
+    })
+}
+
+/// What [`convert_activity_partial`] does with a value that fails to convert.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OnFailure {
+    /// Keep the value unconverted, with its source currency made explicit
+    /// (the converted activity's default currency is the target). A value with
+    /// no currency at all cannot be kept and is dropped.
+    #[default]
+    KeepOriginal,
+    /// Remove the transaction or budget from the converted activity.
+    Drop,
+}
+
+/// Options for [`convert_activity_with`] and [`convert_activity_partial`].
+#[derive(Debug, Clone, Default)]
+pub struct ConvertOptions {
+    /// Rounding applied to every converted value; `None` keeps full precision.
+    pub rounding: Option<RoundingPolicy>,
+    /// Which date each value is converted at.
+    pub date_policy: DatePolicy,
+    /// Handling of failed values in [`convert_activity_partial`].
+    pub on_failure: OnFailure,
+}
+
+impl ConvertOptions {
+    /// Choose the FX date with `policy` (builder-style).
+    pub fn with_date_policy(mut self, policy: DatePolicy) -> Self {
+        self.date_policy = policy;
+        self
+    }
+
+    /// Round converted values with `policy` (builder-style).
+    pub fn with_rounding(mut self, policy: RoundingPolicy) -> Self {
+        self.rounding = Some(policy);
+        self
+    }
+
+    /// Set the handling of failed values (builder-style).
+    pub fn with_on_failure(mut self, on_failure: OnFailure) -> Self {
+        self.on_failure = on_failure;
+        self
+    }
+}
+
+/// A value in the input activity, by index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ValueRef {
+    Transaction(usize),
+    Budget(usize),
+}
+
+/// Result of [`convert_activity_partial`].
+#[derive(Debug)]
+pub struct PartialConversion {
+    pub activity: Activity,
+    /// Values that could not be converted, in input order.
+    pub failures: Vec<(ValueRef, FxError)>,
+}
+
+impl PartialConversion {
+    pub fn is_complete(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
 /// Convert an entire Activity, producing a new Activity with converted amounts. 
+/// Values keep full precision; see [`convert_activity_with`] to round them.
 pub fn convert_activity(
     activity: &Activity,
     target: &CurrencyCode,
     fx: &impl FxProvider,
 ) -> Result<Activity, FxError> {
+    convert_activity_with(activity, target, fx, &ConvertOptions::default())
+}
+
+/// Convert an entire Activity with explicit options, failing on the first value
+/// that cannot be converted. `options.on_failure` is not used.
+pub fn convert_activity_with(
+    activity: &Activity,
+    target: &CurrencyCode,
+    fx: &impl FxProvider,
+    options: &ConvertOptions,
+) -> Result<Activity, FxError> {
+    convert_values(activity, target, fx, options, |_, e| Err(e))
+}
+
+/// Convert every value that can be converted and report the rest, handling
+/// failed values as `options.on_failure` says.
+pub fn convert_activity_partial(
+    activity: &Activity,
+    target: &CurrencyCode,
+    fx: &impl FxProvider,
+    options: &ConvertOptions,
+) -> PartialConversion {
+    let mut failures = Vec::new();
+    let activity = convert_values(activity, target, fx, options, |at, e| {
+        failures.push((at, e));
+        Ok(())
+    })
+    .expect("failure handler never aborts");
+    PartialConversion { activity, failures }
+}
+
+/// Shared conversion loop. `on_error` either aborts with an error or records
+/// the failure, in which case `options.on_failure` decides what happens to the value.
+fn convert_values(
+    activity: &Activity,
+    target: &CurrencyCode,
+    fx: &impl FxProvider,
+    options: &ConvertOptions,
+    mut on_error: impl FnMut(ValueRef, FxError) -> Result<(), FxError>,
+) -> Result<Activity, FxError> {
+    let default = activity.default_currency.as_ref();
+    let finish = |m: Money| match &options.rounding {
+        Some(policy) => m.rounded(policy),
+        None => m,
+    };
+    // Some(value) to keep, None to drop.
+    let mut convert = |at: ValueRef, value: &Money, ctx: &DateContext| -> Result<Option<Money>, FxError> {
+        match convert_money_with(value, default, target, &options.date_policy, ctx, fx) {
+            Ok(m) => Ok(Some(finish(m))),
+            Err(e) => {
+                on_error(at, e)?;
+                let source = resolve_source_currency(value, default);
+                Ok(match (options.on_failure, source) {
+                    (OnFailure::KeepOriginal, Some(currency)) => Some(Money {
+                        currency: Some(currency),
+                        ..value.clone()
+                    }),
+                    _ => None,
+                })
+            }
+        }
+    };
+
     let mut out: Activity = activity.clone();
     out.default_currency = Some(target.clone());
 
-    for tx in &mut out.transactions {
-        let date: Option<NaiveDate> = tx.value.value_date.or(Some(tx.date));
-
-        let conv: Money = convert_money(
-            &tx.value,
-            activity.default_currency.as_ref(),
-            target,
-            date,
-            fx,
-        )?;
+    out.transactions.clear();
+    for (i, tx) in activity.transactions.iter().enumerate() {
+        let ctx = DateContext::for_transaction(activity, tx);
+        if let Some(value) = convert(ValueRef::Transaction(i), &tx.value, &ctx)? {
+            out.transactions.push(Transaction { value, ..tx.clone() });
+        }
+    }
 
-        tx.value = conv;
+    // Under the default policy, budgets without a value-date are converted at the start of their period.
+    out.budgets.clear();
+    for (i, budget) in activity.budgets.iter().enumerate() {
+        let ctx = DateContext::for_budget(activity, budget);
+        if let Some(value) = convert(ValueRef::Budget(i), &budget.value, &ctx)? {
+            out.budgets.push(Budget { value, ..budget.clone() });
+        }
     }
     Ok(out)
 }
@@ -0,0 +1,104 @@
+use std::collections::BTreeMap;
+
+use chrono::NaiveDate;
+use iati_types::CurrencyCode;
+use rust_decimal::Decimal;
+
+use crate::check::RateIssue;
+use crate::provider::{check_rate, cross_rate, FxError, FxProvider};
+
+/// Daily reference rates quoted against a base currency, e.g. ECB euro
+/// reference rates ("units of currency per 1 EUR").
+///
+/// Dates without a published rate (weekends, holidays) fall back to the most
+/// recent earlier publication, up to `max_lookback_days` back.
+#[derive(Debug, Clone)]
+pub struct DailyFxTable {
+    base: CurrencyCode,
+    /// currency -> date -> units of currency per 1 base
+    rates: BTreeMap<CurrencyCode, BTreeMap<NaiveDate, Decimal>>,
+    max_lookback_days: u32,
+}
+
+impl DailyFxTable {
+    /// Default fallback window: long enough for Easter and year-end closures.
+    pub const DEFAULT_MAX_LOOKBACK_DAYS: u32 = 7;
+
+    pub fn new(base: CurrencyCode) -> Self {
+        DailyFxTable {
+            base,
+            rates: BTreeMap::new(),
+            max_lookback_days: Self::DEFAULT_MAX_LOOKBACK_DAYS,
+        }
+    }
+
+    /// Set how many days back to look for the previous business day (builder-style).
+    pub fn with_max_lookback(mut self, days: u32) -> Self {
+        self.max_lookback_days = days;
+        self
+    }
+
+    pub fn base(&self) -> &CurrencyCode {
+        &self.base
+    }
+
+    /// Record `rate` units of `currency` per 1 base currency on `date`,
+    /// rejecting zero and negative values.
+    pub fn insert(&mut self, currency: CurrencyCode, date: NaiveDate, rate: Decimal) -> Result<(), RateIssue> {
+        if rate <= Decimal::ZERO {
+            return Err(RateIssue::NonPositiveDaily { currency, date, rate });
+        }
+        self.rates.entry(currency).or_default().insert(date, rate);
+        Ok(())
+    }
+
+    /// Currencies with at least one rate (the base currency is implicit).
+    pub fn currencies(&self) -> impl Iterator<Item = &CurrencyCode> {
+        self.rates.keys()
+    }
+
+    /// Number of (currency, date) rates.
+    pub fn len(&self) -> usize {
+        self.rates.values().map(BTreeMap::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Units of `currency` per 1 base currency, with the publication date actually used.
+    /// Errors describe `currency` as the source leg.
+    pub fn rate_on(&self, currency: &CurrencyCode, date: NaiveDate) -> Result<(NaiveDate, Decimal), FxError> {
+        if *currency == self.base {
+            return Ok((date, Decimal::ONE));
+        }
+        let earliest = date - chrono::Duration::days(i64::from(self.max_lookback_days));
+        let Some(by_date) = self.rates.get(currency) else {
+            return Err(FxError::missing(currency, date, None, None));
+        };
+        if let Some((d, r)) = by_date.range(earliest..=date).next_back() {
+            return Ok((*d, check_rate(currency, *d, *r)?));
+        }
+        let before = by_date.range(..earliest).next_back().map(|(d, _)| *d);
+        let after = by_date.range(date..).next().map(|(d, _)| *d);
+        Err(FxError::missing(currency, date, before, after))
+    }
+}
+
+impl FxProvider for DailyFxTable {
+    fn get_rate(
+        &self,
+        source_currency: &CurrencyCode,
+        target_currency: &CurrencyCode,
+        date: NaiveDate,
+    ) -> Result<Decimal, FxError> {
+        if source_currency == target_currency {
+            return Ok(Decimal::ONE);
+        }
+
+        // 1 base = r_from source = r_to target, so 1 source = (r_to / r_from) target
+        let (_, r_from) = self.rate_on(source_currency, date)?;
+        let (_, r_to) = self.rate_on(target_currency, date).map_err(FxError::on_target_leg)?;
+        cross_rate(r_to, r_from)
+    }
+}
@@ -0,0 +1,92 @@
+use std::fmt;
+use std::sync::Arc;
+
+use chrono::NaiveDate;
+use iati_types::{Activity, Budget, Transaction};
+
+/// Dates available when choosing the FX date for one value.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DateContext {
+    /// 'value/@value-date'
+    pub value_date: Option<NaiveDate>,
+    /// 'transaction-date/@iso-date', for transaction values.
+    pub transaction_date: Option<NaiveDate>,
+    /// 'period-start/@iso-date', for budget values.
+    pub period_start: Option<NaiveDate>,
+    pub period_end: Option<NaiveDate>,
+    pub activity_start: Option<NaiveDate>,
+    pub activity_end: Option<NaiveDate>,
+}
+
+impl DateContext {
+    pub fn for_transaction(activity: &Activity, tx: &Transaction) -> Self {
+        DateContext {
+            value_date: tx.value.value_date,
+            transaction_date: Some(tx.date),
+            activity_start: activity.activity_start,
+            activity_end: activity.activity_end,
+            ..Default::default()
+        }
+    }
+
+    pub fn for_budget(activity: &Activity, budget: &Budget) -> Self {
+        DateContext {
+            value_date: budget.value.value_date,
+            period_start: Some(budget.period_start),
+            period_end: Some(budget.period_end),
+            activity_start: activity.activity_start,
+            activity_end: activity.activity_end,
+            ..Default::default()
+        }
+    }
+}
+
+/// Caller-supplied date rule for `DatePolicy::Custom`.
+pub type DateFn = Arc<dyn Fn(&DateContext) -> Option<NaiveDate> + Send + Sync>;
+
+/// Which date a value is converted at.
+#[derive(Clone, Default)]
+pub enum DatePolicy {
+    /// 'value/@value-date', else the transaction date (or budget period start).
+    #[default]
+    ValueDateFirst,
+    /// The transaction date, ignoring value-dates; budgets use their period start.
+    TransactionDate,
+    /// The activity start date for every value.
+    ActivityStart,
+    /// One date for every value.
+    Fixed(NaiveDate),
+    /// Caller-supplied rule; `None` means no date (`FxError::MissingDate`).
+    Custom(DateFn),
+}
+
+impl DatePolicy {
+    /// Wrap a closure as `DatePolicy::Custom`.
+    pub fn custom(f: impl Fn(&DateContext) -> Option<NaiveDate> + Send + Sync + 'static) -> Self {
+        DatePolicy::Custom(Arc::new(f))
+    }
+
+    /// The FX date for a value, or `None` if the policy's date is unavailable.
+    pub fn resolve(&self, ctx: &DateContext) -> Option<NaiveDate> {
+        let own_date = ctx.transaction_date.or(ctx.period_start);
+        match self {
+            DatePolicy::ValueDateFirst => ctx.value_date.or(own_date),
+            DatePolicy::TransactionDate => own_date,
+            DatePolicy::ActivityStart => ctx.activity_start,
+            DatePolicy::Fixed(date) => Some(*date),
+            DatePolicy::Custom(f) => f(ctx),
+        }
+    }
+}
+
+impl fmt::Debug for DatePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DatePolicy::ValueDateFirst => f.write_str("ValueDateFirst"),
+            DatePolicy::TransactionDate => f.write_str("TransactionDate"),
+            DatePolicy::ActivityStart => f.write_str("ActivityStart"),
+            DatePolicy::Fixed(date) => f.debug_tuple("Fixed").field(date).finish(),
+            DatePolicy::Custom(_) => f.write_str("Custom(..)"),
+        }
+    }
+}
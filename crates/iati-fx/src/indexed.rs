@@ -0,0 +1,244 @@
+//! Pre-indexed monthly rates for bulk conversion.
+
+use std::collections::HashMap;
+
+use chrono::{Datelike, NaiveDate};
+use iati_types::{CurrencyCode, Money};
+use rust_decimal::Decimal;
+use thiserror::Error;
+
+use crate::convert::resolve_source_currency;
+use crate::provider::{check_rate, cross_rate, FxError, FxProvider};
+use crate::table::{FxTable, YearMonth};
+
+/// Why an [`FxTable`] cannot be turned into an [`IndexedFxTable`].
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum IndexError {
+    #[error("{currency} {period}: month is not in 1..=12")]
+    InvalidMonth { currency: CurrencyCode, period: YearMonth },
+
+    /// The currency's rates run over more months than
+    /// [`IndexedFxTable::MAX_SPAN_MONTHS`], usually because of a mistyped year.
+    #[error("{currency}: rates from {first} to {last} span too many months to index")]
+    SpanTooLarge {
+        currency: CurrencyCode,
+        first: YearMonth,
+        last: YearMonth,
+    },
+}
+
+/// Interned currency, valid only for the [`IndexedFxTable`] that issued it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CurrencyId(u32);
+
+/// One currency's rates as a dense array of consecutive months.
+#[derive(Debug, Clone)]
+struct Series {
+    /// Month index (`year * 12 + month - 1`) of `rates[0]`.
+    first: i64,
+    rates: Vec<Option<Decimal>>,
+}
+
+impl Series {
+    fn slot(&self, month: i64) -> Option<usize> {
+        usize::try_from(month - self.first).ok().filter(|i| *i < self.rates.len())
+    }
+
+    fn get(&self, month: i64) -> Option<Decimal> {
+        self.slot(month).and_then(|i| self.rates[i])
+    }
+
+    /// Nearest months with a rate strictly before and after `month`.
+    fn nearest(&self, month: i64) -> (Option<i64>, Option<i64>) {
+        let offset = month - self.first;
+        let at = |i: usize| self.first + i as i64;
+        let before = match usize::try_from(offset) {
+            Err(_) => None,
+            Ok(i) => self.rates[..i.min(self.rates.len())].iter().rposition(Option::is_some).map(at),
+        };
+        let start = usize::try_from(offset + 1).unwrap_or(0);
+        let after = self
+            .rates
+            .get(start..)
+            .and_then(|rest| rest.iter().position(Option::is_some))
+            .map(|i| at(start + i));
+        (before, after)
+    }
+}
+
+fn month_index(year: i32, month: u32) -> i64 {
+    i64::from(year) * 12 + i64::from(month) - 1
+}
+
+fn month_start(index: i64) -> Option<NaiveDate> {
+    let year = i32::try_from(index.div_euclid(12)).ok()?;
+    NaiveDate::from_ymd_opt(year, index.rem_euclid(12) as u32 + 1, 1)
+}
+
+/// Read-only form of an [`FxTable`] built for converting many values: currencies
+/// are interned to [`CurrencyId`]s and each currency's rates are a dense monthly
+/// array, so a lookup is a hash of the code's bytes plus an index, with no
+/// allocation. Results and errors match the `FxTable` it was built from.
+#[derive(Debug, Clone, Default)]
+pub struct IndexedFxTable {
+    ids: HashMap<String, CurrencyId>,
+    codes: Vec<CurrencyCode>,
+    series: Vec<Series>,
+}
+
+impl IndexedFxTable {
+    /// Longest run of months one currency may cover (500 years), which bounds
+    /// the dense array allocated per currency.
+    pub const MAX_SPAN_MONTHS: usize = 12 * 500;
+
+    /// Index `table`. Fails on a month outside 1..=12, or when a currency's
+    /// rates span more than [`Self::MAX_SPAN_MONTHS`].
+    pub fn from_table(table: &FxTable) -> Result<Self, IndexError> {
+        let mut indexed = IndexedFxTable::default();
+        // Keys are sorted by currency, then month.
+        let mut first_period = YearMonth { year: 0, month: 1 };
+        for ((code, ym), rate) in table.rates() {
+            if !(1..=12).contains(&ym.month) {
+                return Err(IndexError::InvalidMonth { currency: code.clone(), period: *ym });
+            }
+            let month = month_index(ym.year, ym.month);
+            let id = match indexed.ids.get(code.0.as_str()) {
+                Some(id) => *id,
+                None => {
+                    let id = CurrencyId(indexed.codes.len() as u32);
+                    indexed.ids.insert(code.0.clone(), id);
+                    indexed.codes.push(code.clone());
+                    indexed.series.push(Series { first: month, rates: Vec::new() });
+                    first_period = *ym;
+                    id
+                }
+            };
+            let series = &mut indexed.series[id.0 as usize];
+            let slot = usize::try_from(month - series.first)
+                .ok()
+                .filter(|slot| *slot < Self::MAX_SPAN_MONTHS)
+                .ok_or_else(|| IndexError::SpanTooLarge { currency: code.clone(), first: first_period, last: *ym })?;
+            series.rates.resize(slot, None);
+            series.rates.push(Some(*rate));
+        }
+        Ok(indexed)
+    }
+
+    pub fn id(&self, code: &CurrencyCode) -> Option<CurrencyId> {
+        self.ids.get(code.0.as_str()).copied()
+    }
+
+    pub fn code(&self, id: CurrencyId) -> &CurrencyCode {
+        &self.codes[id.0 as usize]
+    }
+
+    pub fn currencies(&self) -> &[CurrencyCode] {
+        &self.codes
+    }
+
+    /// Rate (currency per USD) for the month of `date`, by id.
+    pub fn usd_rate(&self, id: CurrencyId, date: NaiveDate) -> Result<Decimal, FxError> {
+        let month = month_index(date.year(), date.month());
+        let series = &self.series[id.0 as usize];
+        match series.get(month) {
+            Some(rate) => check_rate(self.code(id), date, rate),
+            None => {
+                let (before, after) = series.nearest(month);
+                Err(FxError::missing(self.code(id), date, before.and_then(month_start), after.and_then(month_start)))
+            }
+        }
+    }
+
+    /// Cross rate between two interned currencies.
+    pub fn rate_by_id(&self, source: CurrencyId, target: CurrencyId, date: NaiveDate) -> Result<Decimal, FxError> {
+        if source == target {
+            return Ok(Decimal::ONE);
+        }
+        let r_from = self.usd_rate(source, date)?;
+        let r_to = self.usd_rate(target, date).map_err(FxError::on_target_leg)?;
+        cross_rate(r_to, r_from)
+    }
+
+    /// Convert `values[i]` at `dates[i]`; each result equals what
+    /// [`convert_money`](crate::convert_money) returns for the same inputs.
+    ///
+    /// # Panics
+    /// If `values` and `dates` differ in length.
+    pub fn convert_many(
+        &self,
+        values: &[Money],
+        dates: &[Option<NaiveDate>],
+        activity_default: Option<&CurrencyCode>,
+        target: &CurrencyCode,
+    ) -> Vec<Result<Money, FxError>> {
+        assert_eq!(values.len(), dates.len(), "convert_many: values and dates differ in length");
+        let target_id = self.id(target);
+        let default_id = activity_default.map(|c| self.id(c));
+        // Cross rates already computed, by (source, month).
+        let mut cache: HashMap<(CurrencyId, i64), Decimal> = HashMap::new();
+
+        values
+            .iter()
+            .zip(dates)
+            .map(|(money, date)| {
+                let source_id = match &money.currency {
+                    Some(code) => Some(self.id(code)),
+                    None => default_id,
+                };
+                let Some(source_id) = source_id else {
+                    return Err(FxError::MissingSourceCurrency);
+                };
+                let date = date.ok_or(FxError::MissingDate)?;
+                let rate = match (source_id, target_id) {
+                    (Some(s), Some(t)) => {
+                        let key = (s, month_index(date.year(), date.month()));
+                        match cache.get(&key) {
+                            Some(rate) => *rate,
+                            None => *cache.entry(key).or_insert(self.rate_by_id(s, t, date)?),
+                        }
+                    }
+                    // Unknown currencies: take the slow path for identical codes and errors.
+                    _ => {
+                        let source = resolve_source_currency(money, activity_default).expect("source resolved above");
+                        self.get_rate(&source, target, date)?
+                    }
+                };
+                Ok(Money {
+                    amount: money.amount.checked_mul(rate).ok_or(FxError::Overflow)?,
+                    currency: Some(target.clone()),
+                    value_date: money.value_date,
+                })
+            })
+            .collect()
+    }
+}
+
+impl TryFrom<&FxTable> for IndexedFxTable {
+    type Error = IndexError;
+
+    fn try_from(table: &FxTable) -> Result<Self, IndexError> {
+        IndexedFxTable::from_table(table)
+    }
+}
+
+impl FxProvider for IndexedFxTable {
+    fn get_rate(
+        &self,
+        source_currency: &CurrencyCode,
+        target_currency: &CurrencyCode,
+        date: NaiveDate,
+    ) -> Result<Decimal, FxError> {
+        if source_currency == target_currency {
+            return Ok(Decimal::ONE);
+        }
+        let r_from = match self.id(source_currency) {
+            Some(id) => self.usd_rate(id, date)?,
+            None => return Err(FxError::UnsupportedCurrency(source_currency.clone())),
+        };
+        let r_to = match self.id(target_currency) {
+            Some(id) => self.usd_rate(id, date).map_err(FxError::on_target_leg)?,
+            None => return Err(FxError::UnsupportedTarget(target_currency.clone())),
+        };
+        cross_rate(r_to, r_from)
+    }
+}
@@ -1,8 +1,22 @@
 pub mod provider;
 pub mod table;
 pub mod convert;
+pub mod annual;
+pub mod daily;
+pub mod date_policy;
+pub mod load;
+pub mod check;
+pub mod snapshot;
+pub mod indexed;
 
-pub use crate::provider::{FxProvider, FxError};
+pub use crate::provider::{FxProvider, FxError, RateLeg};
 pub use crate::table::{FxTable, YearMonth};
-pub use crate::convert::{resolve_source_currency, convert_money, convert_activity};
+pub use crate::check::{CheckOptions, Coverage, CurrencyCoverage, RateIssue};
+pub use crate::indexed::{CurrencyId, IndexError, IndexedFxTable};
+pub use crate::annual::{AnnualFxTable, MissingMonths};
+pub use crate::daily::DailyFxTable;
+pub use crate::date_policy::{DateContext, DateFn, DatePolicy};
+pub use crate::snapshot::{FxSnapshot, SnapshotError, SnapshotMeta};
+pub use crate::load::{load_annual_csv, load_ecb_csv, load_monthly_csv, LoadError};
+pub use crate::convert::{resolve_source_currency, convert_money, convert_money_with, convert_activity, convert_activity_with, convert_activity_partial, convert_bag, ConvertOptions, OnFailure, PartialConversion, ValueRef};
 
@@ -0,0 +1,144 @@
+//! Loaders for published rate files.
+
+use std::io::BufRead;
+use std::str::FromStr;
+
+use chrono::NaiveDate;
+use iati_types::CurrencyCode;
+use rust_decimal::Decimal;
+use thiserror::Error;
+
+use crate::annual::AnnualFxTable;
+use crate::daily::DailyFxTable;
+use crate::table::{FxTable, YearMonth};
+
+#[derive(Debug, Error)]
+pub enum LoadError {
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("line {line}: {message}")]
+    Parse { line: usize, message: String },
+}
+
+fn parse_err(line: usize, message: impl Into<String>) -> LoadError {
+    LoadError::Parse {
+        line,
+        message: message.into(),
+    }
+}
+
+/// Load the ECB `eurofxref-hist.csv` file (or the single-day `eurofxref.csv`).
+///
+/// The header is `Date,USD,JPY,...` and each row holds units of currency per
+/// 1 EUR for one date; `N/A` and empty cells (discontinued currencies, the
+/// trailing comma) are skipped, and zero or negative rates are rejected.
+pub fn load_ecb_csv(reader: impl BufRead) -> Result<DailyFxTable, LoadError> {
+    let mut table = DailyFxTable::new(CurrencyCode::from("EUR"));
+    let mut lines = reader.lines().enumerate();
+
+    let header = loop {
+        match lines.next() {
+            Some((_, line)) if line.as_ref().is_ok_and(|l| l.trim().is_empty()) => continue,
+            Some((_, line)) => break line?,
+            None => return Ok(table),
+        }
+    };
+    let mut cols = header.split(',').map(str::trim);
+    if !cols.next().is_some_and(|c| c.eq_ignore_ascii_case("date")) {
+        return Err(parse_err(1, "expected a `Date` header column"));
+    }
+    let currencies: Vec<Option<CurrencyCode>> = cols
+        .map(|c| (!c.is_empty()).then(|| CurrencyCode::from(c)))
+        .collect();
+
+    for (i, line) in lines {
+        let line = line?;
+        let line_no = i + 1;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let mut cells = line.split(',').map(str::trim);
+        let date_cell = cells.next().unwrap_or_default();
+        let date = NaiveDate::parse_from_str(date_cell, "%Y-%m-%d")
+            .or_else(|_| NaiveDate::parse_from_str(date_cell, "%d %B %Y"))
+            .map_err(|e| parse_err(line_no, format!("invalid date {date_cell:?}: {e}")))?;
+
+        for (currency, cell) in currencies.iter().zip(cells) {
+            let Some(currency) = currency else {
+                continue;
+            };
+            if cell.is_empty() || cell.eq_ignore_ascii_case("N/A") {
+                continue;
+            }
+            let rate = Decimal::from_str(cell)
+                .map_err(|e| parse_err(line_no, format!("invalid rate {cell:?} for {}: {e}", currency.0)))?;
+            table
+                .insert(currency.clone(), date, rate)
+                .map_err(|e| parse_err(line_no, e.to_string()))?;
+        }
+    }
+    Ok(table)
+}
+
+/// Load annual average rates from a `currency,year,rate` CSV, with rates in
+/// units of currency per USD. A header row (non-numeric year) is skipped, and
+/// zero or negative rates are rejected.
+pub fn load_annual_csv(reader: impl BufRead) -> Result<AnnualFxTable, LoadError> {
+    let mut table = AnnualFxTable::new();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line_no = i + 1;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let cells: Vec<&str> = line.split(',').map(str::trim).collect();
+        let [currency, year, rate] = cells[..] else {
+            return Err(parse_err(line_no, format!("expected 3 columns, found {}", cells.len())));
+        };
+        let Ok(year) = year.parse::<i32>() else {
+            if line_no == 1 {
+                continue;
+            }
+            return Err(parse_err(line_no, format!("invalid year {year:?}")));
+        };
+        let rate = Decimal::from_str(rate).map_err(|e| parse_err(line_no, format!("invalid rate {rate:?}: {e}")))?;
+        table
+            .insert(CurrencyCode::from(currency), year, rate)
+            .map_err(|e| parse_err(line_no, e.to_string()))?;
+    }
+    Ok(table)
+}
+
+/// Load monthly rates from a `currency,year,month,rate` CSV, with rates in
+/// units of currency per USD (the IMF IFS layout). A header row is skipped, and
+/// zero or negative rates are rejected.
+pub fn load_monthly_csv(reader: impl BufRead) -> Result<FxTable, LoadError> {
+    let mut table = FxTable::new();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line_no = i + 1;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let cells: Vec<&str> = line.split(',').map(str::trim).collect();
+        let [currency, year, month, rate] = cells[..] else {
+            return Err(parse_err(line_no, format!("expected 4 columns, found {}", cells.len())));
+        };
+        let Ok(year) = year.parse::<i32>() else {
+            if line_no == 1 {
+                continue;
+            }
+            return Err(parse_err(line_no, format!("invalid year {year:?}")));
+        };
+        let month = match month.parse::<u32>() {
+            Ok(m @ 1..=12) => m,
+            _ => return Err(parse_err(line_no, format!("invalid month {month:?}"))),
+        };
+        let rate = Decimal::from_str(rate).map_err(|e| parse_err(line_no, format!("invalid rate {rate:?}: {e}")))?;
+        table
+            .insert(CurrencyCode::from(currency), YearMonth { year, month }, rate)
+            .map_err(|e| parse_err(line_no, e.to_string()))?;
+    }
+    Ok(table)
+}
@@ -1,22 +1,127 @@
+use std::fmt;
+
 use chrono::NaiveDate;
 use iati_types::CurrencyCode;
 use rust_decimal::Decimal;
 use thiserror::Error;
 
+/// Which side of a conversion a rate lookup was for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RateLeg {
+    Source,
+    Target,
+}
+
+impl fmt::Display for RateLeg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            RateLeg::Source => "source",
+            RateLeg::Target => "target",
+        })
+    }
+}
 
 #[derive(Debug, Error)]
 pub enum FxError {
-    #[error("No FX rate available for {0:?} on {1}")]
-    MissingRate(CurrencyCode, NaiveDate),
+    /// The table has rates for `currency`, but not for `date`. `nearest_before`
+    /// and `nearest_after` are the closest dates that do have a rate (the first
+    /// day of the period for monthly and annual tables).
+    #[error("No FX rate for {currency} ({leg} currency) on {date}{}", nearest_hint(.nearest_before, .nearest_after))]
+    MissingRate {
+        currency: CurrencyCode,
+        leg: RateLeg,
+        date: NaiveDate,
+        nearest_before: Option<NaiveDate>,
+        nearest_after: Option<NaiveDate>,
+    },
 
-    #[error("Unsupported currency: {0:?}")]
+    #[error("FX rate for {currency} on {date} is not positive: {rate}")]
+    NonPositiveRate {
+        currency: CurrencyCode,
+        date: NaiveDate,
+        rate: Decimal,
+    },
+
+    #[error("Unsupported currency: {0} has no FX rates")]
     UnsupportedCurrency(CurrencyCode),
 
-    #[error("Target currency {0:?} not found in FX table")]
+    #[error("Target currency {0} not found in FX table")]
     UnsupportedTarget(CurrencyCode),
 
+    #[error("Source currency missing (set value/@currency or the activity default-currency)")]
+    MissingSourceCurrency,
+
     #[error("Date missing for conversion (need activity or transaction value_date)")]
     MissingDate,
+
+    /// A converted amount or cross rate does not fit in a `Decimal`.
+    #[error("FX conversion overflowed")]
+    Overflow,
+}
+
+fn nearest_hint(before: &Option<NaiveDate>, after: &Option<NaiveDate>) -> String {
+    match (before, after) {
+        (Some(b), Some(a)) => format!("; nearest rates are on {b} and {a}"),
+        (Some(b), None) => format!("; latest rate is on {b}"),
+        (None, Some(a)) => format!("; earliest rate is on {a}"),
+        (None, None) => String::new(),
+    }
+}
+
+impl FxError {
+    /// Error for a source-leg lookup of `currency` on `date` that found nothing;
+    /// with no rate on either side the currency is unsupported altogether.
+    pub(crate) fn missing(
+        currency: &CurrencyCode,
+        date: NaiveDate,
+        nearest_before: Option<NaiveDate>,
+        nearest_after: Option<NaiveDate>,
+    ) -> Self {
+        if nearest_before.is_none() && nearest_after.is_none() {
+            return FxError::UnsupportedCurrency(currency.clone());
+        }
+        FxError::MissingRate {
+            currency: currency.clone(),
+            leg: RateLeg::Source,
+            date,
+            nearest_before,
+            nearest_after,
+        }
+    }
+
+    /// Re-attribute a lookup error to the target leg.
+    pub(crate) fn on_target_leg(self) -> Self {
+        match self {
+            FxError::MissingRate { currency, date, nearest_before, nearest_after, .. } => FxError::MissingRate {
+                currency,
+                leg: RateLeg::Target,
+                date,
+                nearest_before,
+                nearest_after,
+            },
+            FxError::UnsupportedCurrency(currency) => FxError::UnsupportedTarget(currency),
+            other => other,
+        }
+    }
+}
+
+/// Reject zero and negative rates, which would otherwise divide by zero or flip signs.
+/// `1 source = (r_to / r_from) target`, for rates quoted against a common base.
+/// Both rates have passed [`check_rate`], so only overflow can fail here.
+pub(crate) fn cross_rate(r_to: Decimal, r_from: Decimal) -> Result<Decimal, FxError> {
+    r_to.checked_div(r_from).ok_or(FxError::Overflow)
+}
+
+pub(crate) fn check_rate(currency: &CurrencyCode, date: NaiveDate, rate: Decimal) -> Result<Decimal, FxError> {
+    if rate > Decimal::ZERO {
+        Ok(rate)
+    } else {
+        Err(FxError::NonPositiveRate {
+            currency: currency.clone(),
+            date,
+            rate,
+        })
+    }
 }
 
 pub trait FxProvider {
@@ -26,4 +131,26 @@ pub trait FxProvider {
         target_currency: &CurrencyCode,
         date: NaiveDate,
     ) -> Result<Decimal, FxError>;
-}
\ No newline at end of file
+}
+
+impl<P: FxProvider + ?Sized> FxProvider for &P {
+    fn get_rate(
+        &self,
+        source_currency: &CurrencyCode,
+        target_currency: &CurrencyCode,
+        date: NaiveDate,
+    ) -> Result<Decimal, FxError> {
+        (**self).get_rate(source_currency, target_currency, date)
+    }
+}
+
+impl<P: FxProvider + ?Sized> FxProvider for Box<P> {
+    fn get_rate(
+        &self,
+        source_currency: &CurrencyCode,
+        target_currency: &CurrencyCode,
+        date: NaiveDate,
+    ) -> Result<Decimal, FxError> {
+        (**self).get_rate(source_currency, target_currency, date)
+    }
+}
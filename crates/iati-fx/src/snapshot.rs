@@ -0,0 +1,287 @@
+//! Versioned on-disk snapshots of monthly rate tables.
+//!
+//! A snapshot is an [`FxTable`] plus [`SnapshotMeta`] describing where the rates
+//! came from. It can be stored as JSON (with the `serde` feature) or in a
+//! compact binary form, and [`FxSnapshot::id`] gives a stable identifier that
+//! converted outputs can record.
+//!
+//! Binary layout (little-endian), format version 1:
+//!
+//! ```text
+//! "IATIFX" u8:format
+//! str:source str:retrieved(YYYY-MM-DD) str:version   (str = u32 length + UTF-8)
+//! u32:count, then per rate: u8 length + currency, i32 year, u8 month, 16-byte decimal
+//! ```
+
+use std::io::{self, Read, Write};
+
+use chrono::NaiveDate;
+use iati_types::CurrencyCode;
+use rust_decimal::Decimal;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::provider::{FxError, FxProvider};
+use crate::table::{FxTable, YearMonth};
+
+const MAGIC: &[u8; 6] = b"IATIFX";
+
+#[derive(Debug, Error)]
+pub enum SnapshotError {
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+
+    #[cfg(feature = "serde")]
+    #[error("json error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("unsupported snapshot format {0} (expected {FORMAT})", FORMAT = FxSnapshot::FORMAT)]
+    UnsupportedFormat(u32),
+
+    #[error("invalid snapshot: {0}")]
+    Invalid(String),
+}
+
+/// Provenance of a snapshot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SnapshotMeta {
+    /// Where the rates came from, e.g. "IMF IFS ENDA_XDC_USD_RATE".
+    pub source: String,
+    /// When the rates were downloaded.
+    pub retrieved: NaiveDate,
+    /// Publisher's or caller's table version, e.g. "2024-04".
+    pub version: String,
+}
+
+/// A rate table with its provenance.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FxSnapshot {
+    /// On-disk format version; see [`FxSnapshot::FORMAT`].
+    pub format: u32,
+    pub meta: SnapshotMeta,
+    pub table: FxTable,
+}
+
+impl FxSnapshot {
+    /// Current format version, for both JSON and binary snapshots.
+    pub const FORMAT: u32 = 1;
+
+    pub fn new(table: FxTable, meta: SnapshotMeta) -> Self {
+        FxSnapshot {
+            format: Self::FORMAT,
+            meta,
+            table,
+        }
+    }
+
+    /// Stable identifier: the 64-bit FNV-1a hash of the binary encoding, in hex.
+    /// Rates are normalized before hashing, so snapshots that compare equal
+    /// (`1.50` == `1.5`) have equal ids on every platform.
+    pub fn id(&self) -> Result<String, SnapshotError> {
+        let mut hash = Fnv1a(0xcbf2_9ce4_8422_2325);
+        self.encode(&mut hash, Decimal::normalize)?;
+        Ok(format!("{:016x}", hash.0))
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> Result<String, SnapshotError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn from_json(json: &str) -> Result<Self, SnapshotError> {
+        let snapshot: FxSnapshot = serde_json::from_str(json)?;
+        if snapshot.format != Self::FORMAT {
+            return Err(SnapshotError::UnsupportedFormat(snapshot.format));
+        }
+        Ok(snapshot)
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, SnapshotError> {
+        let mut out = Vec::new();
+        self.write_binary(&mut out)?;
+        Ok(out)
+    }
+
+    /// Write the binary form. Fails if a currency code is longer than 255 bytes
+    /// or a month is outside 1..=12.
+    pub fn write_binary(&self, w: impl Write) -> Result<(), SnapshotError> {
+        self.encode(w, |rate| *rate)
+    }
+
+    fn encode(&self, mut w: impl Write, rate_of: impl Fn(&Decimal) -> Decimal) -> Result<(), SnapshotError> {
+        w.write_all(MAGIC)?;
+        w.write_all(&[Self::FORMAT as u8])?;
+        write_str(&mut w, &self.meta.source)?;
+        write_str(&mut w, &self.meta.retrieved.format("%Y-%m-%d").to_string())?;
+        write_str(&mut w, &self.meta.version)?;
+
+        let count = u32::try_from(self.table.ncu_per_usd.len())
+            .map_err(|_| SnapshotError::Invalid("too many rates".into()))?;
+        w.write_all(&count.to_le_bytes())?;
+        for ((currency, ym), rate) in &self.table.ncu_per_usd {
+            let code = currency.0.as_bytes();
+            let len = u8::try_from(code.len())
+                .map_err(|_| SnapshotError::Invalid(format!("currency code too long: {}", currency.0)))?;
+            w.write_all(&[len])?;
+            w.write_all(code)?;
+            let month = u8::try_from(ym.month)
+                .ok()
+                .filter(|m| (1..=12).contains(m))
+                .ok_or_else(|| SnapshotError::Invalid(format!("{currency}: invalid month {}", ym.month)))?;
+            w.write_all(&ym.year.to_le_bytes())?;
+            w.write_all(&[month])?;
+            w.write_all(&rate_of(rate).serialize())?;
+        }
+        Ok(())
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SnapshotError> {
+        Self::read_binary(bytes)
+    }
+
+    /// Read the binary form, rejecting months outside 1..=12, non-positive rates
+    /// and anything after the last rate.
+    pub fn read_binary(mut r: impl Read) -> Result<Self, SnapshotError> {
+        let magic: [u8; 6] = read_array(&mut r)?;
+        if &magic != MAGIC {
+            return Err(SnapshotError::Invalid("not an FX snapshot".into()));
+        }
+        let [format] = read_array(&mut r)?;
+        if u32::from(format) != Self::FORMAT {
+            return Err(SnapshotError::UnsupportedFormat(format.into()));
+        }
+        let source = read_str(&mut r)?;
+        let retrieved = read_str(&mut r)?;
+        let retrieved = NaiveDate::parse_from_str(&retrieved, "%Y-%m-%d")
+            .map_err(|e| SnapshotError::Invalid(format!("retrieved date {retrieved:?}: {e}")))?;
+        let version = read_str(&mut r)?;
+
+        let mut table = FxTable::new();
+        let count = u32::from_le_bytes(read_array(&mut r)?);
+        for _ in 0..count {
+            let [len] = read_array(&mut r)?;
+            let mut code = vec![0; usize::from(len)];
+            r.read_exact(&mut code)?;
+            let code = String::from_utf8(code).map_err(|e| SnapshotError::Invalid(e.to_string()))?;
+            let year = i32::from_le_bytes(read_array(&mut r)?);
+            let [month] = read_array(&mut r)?;
+            let rate = Decimal::deserialize(read_array(&mut r)?);
+            let ym = period(year, month.into()).map_err(SnapshotError::Invalid)?;
+            table
+                .insert(CurrencyCode::from(code.as_str()), ym, rate)
+                .map_err(|e| SnapshotError::Invalid(e.to_string()))?;
+        }
+        if r.read(&mut [0; 1])? != 0 {
+            return Err(SnapshotError::Invalid("trailing bytes after the last rate".into()));
+        }
+
+        Ok(FxSnapshot::new(table, SnapshotMeta { source, retrieved, version }))
+    }
+}
+
+impl FxProvider for FxSnapshot {
+    fn get_rate(
+        &self,
+        source_currency: &CurrencyCode,
+        target_currency: &CurrencyCode,
+        date: NaiveDate,
+    ) -> Result<Decimal, FxError> {
+        self.table.get_rate(source_currency, target_currency, date)
+    }
+}
+
+/// Write sink that hashes the bytes with 64-bit FNV-1a.
+struct Fnv1a(u64);
+
+impl Write for Fnv1a {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for &byte in buf {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn period(year: i32, month: u32) -> Result<YearMonth, String> {
+    if (1..=12).contains(&month) {
+        Ok(YearMonth { year, month })
+    } else {
+        Err(format!("month {month} of {year} is not in 1..=12"))
+    }
+}
+
+fn write_str(w: &mut impl Write, s: &str) -> Result<(), SnapshotError> {
+    let len = u32::try_from(s.len()).map_err(|_| SnapshotError::Invalid("string too long".into()))?;
+    w.write_all(&len.to_le_bytes())?;
+    w.write_all(s.as_bytes())?;
+    Ok(())
+}
+
+fn read_array<const N: usize>(r: &mut impl Read) -> Result<[u8; N], SnapshotError> {
+    let mut buf = [0; N];
+    r.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+fn read_str(r: &mut impl Read) -> Result<String, SnapshotError> {
+    let len = u32::from_le_bytes(read_array(r)?);
+    let mut buf = Vec::new();
+    r.take(u64::from(len)).read_to_end(&mut buf)?;
+    if buf.len() != len as usize {
+        return Err(SnapshotError::Io(io::ErrorKind::UnexpectedEof.into()));
+    }
+    String::from_utf8(buf).map_err(|e| SnapshotError::Invalid(e.to_string()))
+}
+
+/// Serde adapter for `FxTable::ncu_per_usd` as a list of rows.
+#[cfg(feature = "serde")]
+pub(crate) mod rate_rows {
+    use std::collections::BTreeMap;
+
+    use iati_types::CurrencyCode;
+    use rust_decimal::Decimal;
+    use serde::de::Error as _;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use crate::table::{FxTable, YearMonth};
+
+    #[derive(Serialize, Deserialize)]
+    struct Row {
+        currency: CurrencyCode,
+        year: i32,
+        month: u32,
+        rate: Decimal,
+    }
+
+    pub fn serialize<S: Serializer>(
+        map: &BTreeMap<(CurrencyCode, YearMonth), Decimal>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(map.iter().map(|((currency, ym), rate)| Row {
+            currency: currency.clone(),
+            year: ym.year,
+            month: ym.month,
+            rate: *rate,
+        }))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<BTreeMap<(CurrencyCode, YearMonth), Decimal>, D::Error> {
+        let mut table = FxTable::new();
+        for r in Vec::<Row>::deserialize(deserializer)? {
+            let ym = super::period(r.year, r.month).map_err(D::Error::custom)?;
+            table.insert(r.currency, ym, r.rate).map_err(D::Error::custom)?;
+        }
+        Ok(table.ncu_per_usd)
+    }
+}
@@ -1,14 +1,19 @@
 use std::collections::BTreeMap;
+use std::fmt;
 
 use chrono::NaiveDate;
 use chrono::Datelike;
 use iati_types::CurrencyCode;
 use rust_decimal::Decimal;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
-use crate::provider::{FxError, FxProvider};
+use crate::check::RateIssue;
+use crate::provider::{check_rate, cross_rate, FxError, FxProvider};
 
 /// Year + month (IMF data is monthly).
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct YearMonth {
     pub year: i32,
     pub month: u32,
@@ -21,13 +26,50 @@ impl YearMonth {
             month: date.month(),
         }
     }
+
+    /// The following month.
+    pub fn next(self) -> Self {
+        if self.month >= 12 {
+            YearMonth { year: self.year + 1, month: 1 }
+        } else {
+            YearMonth { year: self.year, month: self.month + 1 }
+        }
+    }
+
+    /// The preceding month.
+    pub fn prev(self) -> Self {
+        if self.month <= 1 {
+            YearMonth { year: self.year - 1, month: 12 }
+        } else {
+            YearMonth { year: self.year, month: self.month - 1 }
+        }
+    }
+
+    /// The first day of the month, if it is a valid date.
+    pub fn first_day(&self) -> Option<NaiveDate> {
+        NaiveDate::from_ymd_opt(self.year, self.month, 1)
+    }
+}
+
+impl fmt::Display for YearMonth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}", self.year, self.month)
+    }
 }
 
 /// Structure holding IMF monthly "domestic currency per USD" exchange rates.
-#[derive(Debug, Clone)]
+///
+/// Rates are added with [`FxTable::insert`], which rejects zero and negative
+/// values; run [`FxTable::check`] on tables loaded from external files.
+///
+/// With the `serde` feature the table serialises as a list of
+/// `{currency, year, month, rate}` rows, since JSON has no tuple keys.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct FxTable {
     /// Map ((currency, YearMonth) -> rate)
-    pub ncu_per_usd: BTreeMap<(CurrencyCode, YearMonth), Decimal>, 
+    #[cfg_attr(feature = "serde", serde(rename = "rates", with = "crate::snapshot::rate_rows"))]
+    pub(crate) ncu_per_usd: BTreeMap<(CurrencyCode, YearMonth), Decimal>,
 }
 
 impl FxTable {
@@ -37,6 +79,20 @@ impl FxTable {
         }
     }
 
+    /// Add one rate, rejecting zero and negative values.
+    pub fn insert(&mut self, currency: CurrencyCode, period: YearMonth, rate: Decimal) -> Result<(), RateIssue> {
+        if rate <= Decimal::ZERO {
+            return Err(RateIssue::NonPositive { currency, period, rate });
+        }
+        self.ncu_per_usd.insert((currency, period), rate);
+        Ok(())
+    }
+
+    /// All rates, keyed by (currency, month).
+    pub fn rates(&self) -> &BTreeMap<(CurrencyCode, YearMonth), Decimal> {
+        &self.ncu_per_usd
+    }
+
     /// Get the exchange rate for currency to USD for the given year and month.
     fn get_monthly_usd_rate(
         &self,
@@ -44,10 +100,15 @@ impl FxTable {
         date: NaiveDate,
     ) -> Result<Decimal, FxError> {
         let ym: YearMonth = YearMonth::from_date(date);
-        self.ncu_per_usd
-            .get(&(code.clone(), ym))
-            .cloned()
-            .ok_or_else(|| FxError::MissingRate(code.clone(), date))
+        if let Some(rate) = self.ncu_per_usd.get(&(code.clone(), ym)) {
+            return check_rate(code, date, *rate);
+        }
+        let first = (code.clone(), YearMonth { year: i32::MIN, month: 1 });
+        let last = (code.clone(), YearMonth { year: i32::MAX, month: 12 });
+        let before = self.ncu_per_usd.range(first..(code.clone(), ym)).next_back();
+        let after = self.ncu_per_usd.range((code.clone(), ym)..=last).next();
+        let first_day = |((_, ym), _): (&(CurrencyCode, YearMonth), _)| ym.first_day();
+        Err(FxError::missing(code, date, before.and_then(first_day), after.and_then(first_day)))
     }
 }
 
@@ -64,10 +125,10 @@ impl FxProvider for FxTable {
 
         // IMF: rate = NCU per USD
         let r_from: Decimal = self.get_monthly_usd_rate(source_currency, date)?;
-        let r_to: Decimal = self.get_monthly_usd_rate(target_currency, date)?;
+        let r_to: Decimal = self.get_monthly_usd_rate(target_currency, date).map_err(FxError::on_target_leg)?;
 
         // Cross rate:
         //   1 from = (r_to / r_from) to 
-        Ok(r_to / r_from)
+        cross_rate(r_to, r_from)
     }
 }
\ No newline at end of file
@@ -0,0 +1,63 @@
+use chrono::NaiveDate;
+use iati_fx::{convert_activity, load_annual_csv, AnnualFxTable, FxProvider, FxTable, MissingMonths, RateIssue, YearMonth};
+use iati_types::{tx::Transaction, Activity, CurrencyCode, Money, TxType};
+use rust_decimal::Decimal;
+
+fn d(y: i32, m: u32, day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(y, m, day).unwrap()
+}
+
+#[test]
+fn test_annual_csv_converts_activity() {
+    let csv = "currency,year,rate\nEUR,2023,0.925\nGBP,2023,0.804\n";
+    let table = load_annual_csv(csv.as_bytes()).unwrap();
+    assert_eq!(table.rate_for(&CurrencyCode::from("EUR"), 2023), Some(Decimal::new(925, 3)));
+    // USD is implicit
+    assert_eq!(table.rate_for(&CurrencyCode::from("USD"), 1999), Some(Decimal::ONE));
+
+    let mut act = Activity::new("A1");
+    act.default_currency = Some(CurrencyCode::from("EUR"));
+    act.transactions.push(Transaction::new(TxType::Disbursement, d(2023, 2, 1), Money::new(Decimal::new(925, 0))));
+    act.transactions.push(Transaction::new(TxType::Disbursement, d(2023, 11, 30), Money::new(Decimal::new(185, 0))));
+
+    // same rate for every date in the year
+    let out = convert_activity(&act, &CurrencyCode::from("USD"), &table).unwrap();
+    assert_eq!(out.transactions[0].value.amount, Decimal::new(1000, 0));
+    assert_eq!(out.transactions[1].value.amount, Decimal::new(200, 0));
+
+    assert!(table.get_rate(&CurrencyCode::from("EUR"), &CurrencyCode::from("USD"), d(2024, 1, 1)).is_err());
+    assert!(load_annual_csv("EUR,2023\n".as_bytes()).is_err());
+    let err = load_annual_csv("EUR,2023,-1\n".as_bytes()).unwrap_err();
+    assert_eq!(err.to_string(), "line 1: EUR 2023: rate -1 is not positive");
+
+    let mut table = AnnualFxTable::new();
+    assert!(matches!(
+        table.insert(CurrencyCode::from("EUR"), 2023, Decimal::ZERO),
+        Err(RateIssue::NonPositiveAnnual { .. })
+    ));
+    assert!(table.rates().is_empty());
+}
+
+#[test]
+fn test_annual_from_monthly_missing_months() {
+    let eur = CurrencyCode::from("EUR");
+    let mut monthly = FxTable::new();
+    for month in 1..=12 {
+        monthly.insert(eur.clone(), YearMonth { year: 2023, month }, Decimal::new(80 + month as i64, 2)).unwrap();
+    }
+    for month in 1..=6 {
+        monthly.insert(eur.clone(), YearMonth { year: 2024, month }, Decimal::new(90, 2)).unwrap();
+    }
+
+    let strict = AnnualFxTable::from_monthly(&monthly, MissingMonths::Strict);
+    // mean of 0.81 .. 0.92
+    assert_eq!(strict.rate_for(&eur, 2023), Some(Decimal::new(865, 3)));
+    assert_eq!(strict.rate_for(&eur, 2024), None);
+
+    let lenient = AnnualFxTable::from_monthly(&monthly, MissingMonths::AverageAvailable);
+    assert_eq!(lenient.rate_for(&eur, 2024), Some(Decimal::new(90, 2)));
+
+    let min9 = AnnualFxTable::from_monthly(&monthly, MissingMonths::MinMonths(9));
+    assert_eq!(min9.rate_for(&eur, 2024), None);
+    assert!(min9.rate_for(&eur, 2023).is_some());
+}
@@ -1,6 +1,6 @@
 use iati_fx::{FxTable, FxProvider};
 use iati_types::{CurrencyCode};
-use chrono::NaiveDate;
+use chrono::{Datelike, NaiveDate};
 use rust_decimal::{Decimal, prelude::FromPrimitive};
 
 #[test]
@@ -14,8 +14,8 @@ fn test_cross_rate() {
     let ym = iati_fx::YearMonth { year: 2024, month: 3 };
 
 
-    table.ncu_per_usd.insert((CurrencyCode::from("DKK"), ym), Decimal::new(70,1)); // 7.0
-    table.ncu_per_usd.insert((CurrencyCode::from("EUR"), ym), Decimal::new(9,1));  // 0.9
+    table.insert(CurrencyCode::from("DKK"), ym, Decimal::new(70,1)).unwrap(); // 7.0
+    table.insert(CurrencyCode::from("EUR"), ym, Decimal::new(9,1)).unwrap();  // 0.9
 
     let date = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap();
 
@@ -28,3 +28,195 @@ fn test_cross_rate() {
     assert_eq!(rate.round_dp(10), expected_rate.round_dp(10));  // this tests up to 10 decimal places in equality
 }
 
+
+#[test]
+fn test_convert_activity_converts_budgets() {
+    use iati_fx::convert_activity;
+    use iati_types::{Activity, Budget, Money};
+
+    let mut table = FxTable::new();
+    let ym = iati_fx::YearMonth { year: 2024, month: 1 };
+    table.insert(CurrencyCode::from("EUR"), ym, Decimal::new(5, 1)).unwrap(); // 0.5
+    table.insert(CurrencyCode::from("USD"), ym, Decimal::ONE).unwrap();
+
+    let mut activity = Activity::new("ACT-1");
+    activity.default_currency = Some(CurrencyCode::from("EUR"));
+    activity.budgets.push(Budget::new(
+        NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+        NaiveDate::from_ymd_opt(2024, 12, 31).unwrap(),
+        Money::new(Decimal::new(100, 0)),
+    ));
+
+    let out = convert_activity(&activity, &CurrencyCode::from("USD"), &table).unwrap();
+
+    // no value-date: converted at the January period-start rate
+    assert_eq!(out.budgets[0].value.amount, Decimal::new(200, 0));
+    assert_eq!(out.budgets[0].value.currency, Some(CurrencyCode::from("USD")));
+}
+
+#[test]
+fn test_convert_bag_sums_in_target_currency() {
+    use iati_fx::convert_bag;
+    use iati_types::MoneyBag;
+
+    let mut table = FxTable::new();
+    let ym = iati_fx::YearMonth { year: 2024, month: 1 };
+    table.insert(CurrencyCode::from("EUR"), ym, Decimal::new(5, 1)).unwrap(); // 0.5
+    table.insert(CurrencyCode::from("USD"), ym, Decimal::ONE).unwrap();
+
+    let mut bag = MoneyBag::new();
+    bag.add(CurrencyCode::from("EUR"), Decimal::new(10, 0)).unwrap();
+    bag.add(CurrencyCode::from("USD"), Decimal::new(3, 0)).unwrap();
+
+    let date = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+    let total = convert_bag(&bag, &CurrencyCode::from("USD"), date, &table).unwrap();
+    assert_eq!(total.amount, Decimal::new(23, 0));
+    assert_eq!(total.currency, Some(CurrencyCode::from("USD")));
+}
+
+#[test]
+fn test_convert_bag_overflow_is_an_error() {
+    use iati_fx::{convert_bag, convert_money, FxError};
+    use iati_types::{Money, MoneyBag};
+
+    let mut table = FxTable::new();
+    let ym = iati_fx::YearMonth { year: 2024, month: 1 };
+    table.insert(CurrencyCode::from("EUR"), ym, Decimal::ONE).unwrap();
+    table.insert(CurrencyCode::from("USD"), ym, Decimal::ONE).unwrap();
+
+    let mut bag = MoneyBag::new();
+    bag.add(CurrencyCode::from("EUR"), Decimal::MAX).unwrap();
+    bag.add(CurrencyCode::from("USD"), Decimal::MAX).unwrap();
+
+    let usd = CurrencyCode::from("USD");
+    let date = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+    assert!(matches!(convert_bag(&bag, &usd, date, &table), Err(FxError::Overflow)));
+
+    table.insert(usd.clone(), ym, Decimal::MAX).unwrap();
+    let money = Money::new(Decimal::new(2, 0)).with_currency(CurrencyCode::from("EUR"));
+    assert!(matches!(convert_money(&money, None, &usd, Some(date), &table), Err(FxError::Overflow)));
+}
+
+#[test]
+fn test_convert_activity_with_rounding() {
+    use iati_fx::{convert_activity_with, ConvertOptions};
+    use iati_types::{tx::Transaction, Activity, Money, RoundingPolicy, TxType};
+
+    let mut table = FxTable::new();
+    let ym = iati_fx::YearMonth { year: 2024, month: 3 };
+    table.insert(CurrencyCode::from("DKK"), ym, Decimal::new(70, 1)).unwrap(); // 7.0
+    table.insert(CurrencyCode::from("JPY"), ym, Decimal::new(150, 0)).unwrap();
+
+    let mut activity = Activity::new("ACT-1");
+    activity.default_currency = Some(CurrencyCode::from("DKK"));
+    let date = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap();
+    activity.transactions.push(Transaction::new(TxType::Disbursement, date, Money::new(Decimal::new(100, 0))));
+
+    // 100 DKK = 100 / 7 * 150 JPY = 2142.857... JPY
+    let options = ConvertOptions::default().with_rounding(RoundingPolicy::default());
+    let out = convert_activity_with(&activity, &CurrencyCode::from("JPY"), &table, &options).unwrap();
+    assert_eq!(out.transactions[0].value.amount, Decimal::new(2143, 0));
+    assert_eq!(out.transactions[0].value.to_string(), "2,143 JPY");
+}
+
+#[test]
+fn test_date_policies() {
+    use iati_fx::{convert_activity_with, ConvertOptions, DatePolicy, FxError};
+    use iati_types::{tx::Transaction, Activity, Money, TxType};
+
+    let mut table = FxTable::new();
+    for (month, eur) in [(1, 5), (2, 8)] {
+        let ym = iati_fx::YearMonth { year: 2024, month };
+        table.insert(CurrencyCode::from("EUR"), ym, Decimal::new(eur, 1)).unwrap();
+        table.insert(CurrencyCode::from("USD"), ym, Decimal::ONE).unwrap();
+    }
+
+    let mut activity = Activity::new("ACT-1");
+    activity.default_currency = Some(CurrencyCode::from("EUR"));
+    let mut money = Money::new(Decimal::new(40, 0));
+    money.value_date = NaiveDate::from_ymd_opt(2024, 1, 31);
+    activity.transactions.push(Transaction::new(TxType::Disbursement, NaiveDate::from_ymd_opt(2024, 2, 2).unwrap(), money));
+    let usd = CurrencyCode::from("USD");
+
+    let convert = |policy: DatePolicy| {
+        let options = ConvertOptions::default().with_date_policy(policy);
+        convert_activity_with(&activity, &usd, &table, &options).map(|a| a.transactions[0].value.amount)
+    };
+
+    // value-date (January, 0.5 EUR per USD) vs transaction date (February, 0.8)
+    assert_eq!(convert(DatePolicy::ValueDateFirst).unwrap(), Decimal::new(80, 0));
+    assert_eq!(convert(DatePolicy::TransactionDate).unwrap(), Decimal::new(50, 0));
+    assert_eq!(convert(DatePolicy::Fixed(NaiveDate::from_ymd_opt(2024, 1, 1).unwrap())).unwrap(), Decimal::new(80, 0));
+    assert!(matches!(convert(DatePolicy::ActivityStart), Err(FxError::MissingDate)));
+    let first_of_month = DatePolicy::custom(|ctx| ctx.transaction_date.map(|d| d.with_day(1).unwrap()));
+    assert_eq!(convert(first_of_month).unwrap(), Decimal::new(50, 0));
+}
+
+#[test]
+fn test_partial_conversion_reports_failures() {
+    use iati_fx::{convert_activity, convert_activity_partial, ConvertOptions, FxError, OnFailure, ValueRef};
+    use iati_types::{tx::Transaction, Activity, Money, TxType};
+
+    let mut table = FxTable::new();
+    let ym = iati_fx::YearMonth { year: 2024, month: 1 };
+    table.insert(CurrencyCode::from("EUR"), ym, Decimal::new(5, 1)).unwrap();
+    table.insert(CurrencyCode::from("USD"), ym, Decimal::ONE).unwrap();
+
+    let date = NaiveDate::from_ymd_opt(2024, 1, 10).unwrap();
+    let mut activity = Activity::new("ACT-1");
+    activity.default_currency = Some(CurrencyCode::from("EUR"));
+    for (amount, currency) in [(10, None), (20, Some("XYZ")), (30, None)] {
+        let mut money = Money::new(Decimal::new(amount, 0));
+        money.currency = currency.map(CurrencyCode::from);
+        activity.transactions.push(Transaction::new(TxType::Disbursement, date, money));
+    }
+    let usd = CurrencyCode::from("USD");
+
+    assert!(matches!(convert_activity(&activity, &usd, &table), Err(FxError::UnsupportedCurrency(_))));
+
+    let kept = convert_activity_partial(&activity, &usd, &table, &ConvertOptions::default());
+    assert!(!kept.is_complete());
+    assert_eq!(kept.failures.len(), 1);
+    assert_eq!(kept.failures[0].0, ValueRef::Transaction(1));
+    let amounts: Vec<_> = kept.activity.transactions.iter().map(|t| t.value.to_string()).collect();
+    assert_eq!(amounts, vec!["20.00 USD", "20.00 XYZ", "60.00 USD"]);
+
+    let options = ConvertOptions::default().with_on_failure(OnFailure::Drop);
+    let dropped = convert_activity_partial(&activity, &usd, &table, &options);
+    assert_eq!(dropped.activity.transactions.len(), 2);
+    assert_eq!(dropped.activity.transactions[1].value.amount, Decimal::new(60, 0));
+}
+
+#[test]
+fn test_fx_errors_name_the_leg_and_nearest_rates() {
+    use iati_fx::{convert_money, FxError, RateLeg};
+    use iati_types::Money;
+
+    let mut table = FxTable::new();
+    let ym = |month| iati_fx::YearMonth { year: 2024, month };
+    let (eur, dkk, gbp) = (CurrencyCode::from("EUR"), CurrencyCode::from("DKK"), CurrencyCode::from("GBP"));
+    table.insert(eur.clone(), ym(1), Decimal::new(9, 1)).unwrap();
+    table.insert(eur.clone(), ym(4), Decimal::new(9, 1)).unwrap();
+    table.insert(dkk.clone(), ym(2), Decimal::new(70, 1)).unwrap();
+    assert!(table.insert(gbp.clone(), ym(2), Decimal::ZERO).is_err());
+    let feb = NaiveDate::from_ymd_opt(2024, 2, 15).unwrap();
+    let d = |m| NaiveDate::from_ymd_opt(2024, m, 1);
+
+    let err = table.get_rate(&dkk, &eur, feb).unwrap_err();
+    assert!(matches!(
+        &err,
+        FxError::MissingRate { currency, leg: RateLeg::Target, nearest_before, nearest_after, .. }
+            if *currency == eur && *nearest_before == d(1) && *nearest_after == d(4)
+    ));
+    assert_eq!(
+        err.to_string(),
+        "No FX rate for EUR (target currency) on 2024-02-15; nearest rates are on 2024-01-01 and 2024-04-01"
+    );
+
+    assert!(matches!(table.get_rate(&CurrencyCode::from("XYZ"), &dkk, feb), Err(FxError::UnsupportedCurrency(_))));
+    assert!(matches!(table.get_rate(&dkk, &CurrencyCode::from("XYZ"), feb), Err(FxError::UnsupportedTarget(_))));
+    assert!(matches!(table.get_rate(&gbp, &dkk, feb), Err(FxError::UnsupportedCurrency(_))));
+
+    let money = Money::new(Decimal::ONE);
+    assert!(matches!(convert_money(&money, None, &dkk, Some(feb), &table), Err(FxError::MissingSourceCurrency)));
+}
@@ -0,0 +1,76 @@
+use iati_fx::{load_monthly_csv, CheckOptions, FxTable, LoadError, RateIssue, YearMonth};
+use iati_types::CurrencyCode;
+use rust_decimal::Decimal;
+
+fn ym(year: i32, month: u32) -> YearMonth {
+    YearMonth { year, month }
+}
+
+#[test]
+fn test_insert_rejects_non_positive_rates() {
+    let mut table = FxTable::new();
+    let eur = CurrencyCode::from("EUR");
+    assert!(table.insert(eur.clone(), ym(2024, 1), Decimal::new(9, 1)).is_ok());
+    assert!(matches!(
+        table.insert(eur.clone(), ym(2024, 2), Decimal::ZERO),
+        Err(RateIssue::NonPositive { .. })
+    ));
+    assert!(table.insert(eur, ym(2024, 3), Decimal::new(-1, 0)).is_err());
+    assert_eq!(table.rates().len(), 1);
+}
+
+#[test]
+fn test_check_flags_jumps_and_gaps() {
+    let mut table = FxTable::new();
+    let (eur, xof) = (CurrencyCode::from("EUR"), CurrencyCode::from("XOF"));
+    for (period, rate) in [(ym(2023, 11), 92), (ym(2023, 12), 91), (ym(2024, 3), 90)] {
+        table.insert(eur.clone(), period, Decimal::new(rate, 2)).unwrap();
+    }
+    table.insert(xof.clone(), ym(2024, 1), Decimal::new(600, 0)).unwrap();
+    table.insert(xof.clone(), ym(2024, 2), Decimal::new(600_000, 0)).unwrap(); // off by 1000
+    table.insert(xof.clone(), ym(2024, 3), Decimal::new(600_000, 0)).unwrap();
+
+    let issues = table.check(&CheckOptions::default());
+    assert_eq!(issues.len(), 2);
+    assert_eq!(issues[0], RateIssue::Gap { currency: eur.clone(), from: ym(2024, 1), to: ym(2024, 2) });
+    assert!(matches!(&issues[1], RateIssue::Jump { from, to, .. } if *from == ym(2024, 1) && *to == ym(2024, 2)));
+    assert_eq!(issues[0].to_string(), "EUR: no rates from 2024-01 to 2024-02");
+
+    let lenient = CheckOptions { max_jump: Decimal::from(10_000) };
+    assert_eq!(table.check(&lenient).len(), 1);
+
+    let coverage = table.coverage();
+    let c = coverage.get(&eur).unwrap();
+    assert_eq!((c.first, c.last, c.months), (ym(2023, 11), ym(2024, 3), 3));
+    assert_eq!(c.gaps, vec![(ym(2024, 1), ym(2024, 2))]);
+    assert!(coverage.get(&xof).unwrap().is_complete());
+    assert_eq!(
+        coverage.to_string(),
+        "EUR: 2023-11 to 2024-03 (3 months, missing 2024-01..2024-02)\nXOF: 2024-01 to 2024-03 (3 months)\n"
+    );
+}
+
+#[test]
+fn test_load_monthly_csv() {
+    let csv = "currency,year,month,rate\nEUR,2024,1,0.92\nEUR,2024,2,0.93\n\nDKK,2024,1,6.85\n";
+    let table = load_monthly_csv(csv.as_bytes()).unwrap();
+    assert_eq!(table.rates().len(), 3);
+    assert_eq!(table.rates()[&(CurrencyCode::from("DKK"), ym(2024, 1))], Decimal::new(685, 2));
+
+    let zero = "EUR,2024,1,0.92\nEUR,2024,2,0\n";
+    assert!(matches!(load_monthly_csv(zero.as_bytes()), Err(LoadError::Parse { line: 2, .. })));
+    assert!(load_monthly_csv("EUR,2024,13,0.9\n".as_bytes()).is_err());
+}
+
+#[test]
+fn test_ecb_and_annual_loaders_reject_non_positive_rates() {
+    use iati_fx::{load_annual_csv, load_ecb_csv};
+
+    let ecb = "Date,USD,JPY,\n2024-03-15,1.09,0,\n";
+    assert!(matches!(load_ecb_csv(ecb.as_bytes()), Err(LoadError::Parse { line: 2, .. })));
+    assert!(load_ecb_csv("Date,USD,\n2024-03-15,-1.09,\n".as_bytes()).is_err());
+    assert!(load_ecb_csv("Date,USD,JPY,\n2024-03-15,1.09,N/A,\n".as_bytes()).is_ok());
+
+    let annual = "currency,year,rate\nEUR,2023,0.92\nGBP,2023,-0.8\n";
+    assert!(matches!(load_annual_csv(annual.as_bytes()), Err(LoadError::Parse { line: 3, .. })));
+}
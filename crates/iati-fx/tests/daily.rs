@@ -0,0 +1,68 @@
+use chrono::NaiveDate;
+use iati_fx::{load_ecb_csv, DailyFxTable, FxError, FxProvider, LoadError, RateIssue};
+use iati_types::CurrencyCode;
+use rust_decimal::Decimal;
+
+const ECB_HIST: &str = "\
+Date,USD,JPY,CYP,GBP,
+2024-03-15,1.0887,162.16,N/A,0.8556,
+2024-03-14,1.0925,161.81,N/A,0.8548,
+2024-03-13,1.0939,161.72,N/A,0.8547,
+";
+
+fn d(y: i32, m: u32, day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(y, m, day).unwrap()
+}
+
+#[test]
+fn test_load_ecb_hist_csv() {
+    let table = load_ecb_csv(ECB_HIST.as_bytes()).unwrap();
+    assert_eq!(table.base(), &CurrencyCode::from("EUR"));
+    // N/A cells (discontinued CYP) are skipped
+    assert_eq!(table.len(), 9);
+    assert_eq!(table.currencies().count(), 3);
+
+    let usd = CurrencyCode::from("USD");
+    let eur = CurrencyCode::from("EUR");
+    assert_eq!(table.get_rate(&eur, &usd, d(2024, 3, 14)).unwrap(), Decimal::new(10925, 4));
+    // cross rate through the base: 1 USD = 0.8556 / 1.0887 GBP
+    let gbp = table.get_rate(&usd, &CurrencyCode::from("GBP"), d(2024, 3, 15)).unwrap();
+    assert_eq!(gbp, Decimal::new(8556, 4) / Decimal::new(10887, 4));
+}
+
+#[test]
+fn test_weekend_falls_back_to_previous_business_day() {
+    let table = load_ecb_csv(ECB_HIST.as_bytes()).unwrap();
+    let usd = CurrencyCode::from("USD");
+
+    // Sunday 17 March -> Friday 15 March
+    assert_eq!(table.rate_on(&usd, d(2024, 3, 17)).unwrap(), (d(2024, 3, 15), Decimal::new(10887, 4)));
+
+    // outside the lookback window, or before the first publication
+    assert!(matches!(table.rate_on(&usd, d(2024, 3, 30)), Err(FxError::MissingRate { .. })));
+    assert!(table.rate_on(&usd, d(2024, 3, 1)).is_err());
+    let strict = table.clone().with_max_lookback(0);
+    assert!(strict.rate_on(&usd, d(2024, 3, 16)).is_err());
+}
+
+#[test]
+fn test_configurable_base_and_load_errors() {
+    let mut table = DailyFxTable::new(CurrencyCode::from("USD"));
+    table.insert(CurrencyCode::from("KES"), d(2024, 1, 2), Decimal::new(160, 0)).unwrap();
+    let rate = table
+        .get_rate(&CurrencyCode::from("KES"), &CurrencyCode::from("USD"), d(2024, 1, 2))
+        .unwrap();
+    assert_eq!(rate, Decimal::ONE / Decimal::new(160, 0));
+
+    let err = load_ecb_csv("Date,USD\n2024-03-15,abc\n".as_bytes()).unwrap_err();
+    assert!(matches!(err, LoadError::Parse { line: 2, .. }));
+    assert!(load_ecb_csv("Currency,USD\n".as_bytes()).is_err());
+
+    assert!(matches!(
+        table.insert(CurrencyCode::from("KES"), d(2024, 1, 3), Decimal::ZERO),
+        Err(RateIssue::NonPositiveDaily { .. })
+    ));
+    assert_eq!(table.len(), 1);
+    let err = load_ecb_csv("Date,USD\n2024-03-15,0\n".as_bytes()).unwrap_err();
+    assert_eq!(err.to_string(), "line 2: USD 2024-03-15: rate 0 is not positive");
+}
@@ -0,0 +1,83 @@
+use chrono::NaiveDate;
+use iati_fx::{convert_money, load_monthly_csv, FxError, FxProvider, FxTable, IndexError, IndexedFxTable, YearMonth};
+use iati_types::{CurrencyCode, Money};
+use rust_decimal::Decimal;
+
+fn ym(year: i32, month: u32) -> YearMonth {
+    YearMonth { year, month }
+}
+
+fn d(y: i32, m: u32, day: u32) -> Option<NaiveDate> {
+    NaiveDate::from_ymd_opt(y, m, day)
+}
+
+fn money(amount: i64, currency: Option<&str>) -> Money {
+    let mut m = Money::new(Decimal::new(amount, 0));
+    m.currency = currency.map(CurrencyCode::from);
+    m
+}
+
+#[test]
+fn test_indexed_table_matches_fx_table() {
+    let mut table = FxTable::new();
+    table.insert(CurrencyCode::from("EUR"), ym(2023, 12), Decimal::new(92, 2)).unwrap();
+    table.insert(CurrencyCode::from("EUR"), ym(2024, 3), Decimal::new(9, 1)).unwrap();
+    table.insert(CurrencyCode::from("DKK"), ym(2023, 12), Decimal::new(68, 1)).unwrap();
+    table.insert(CurrencyCode::from("DKK"), ym(2024, 3), Decimal::new(7, 0)).unwrap();
+    table.insert(CurrencyCode::from("GBP"), ym(2024, 3), Decimal::new(1, 28)).unwrap(); // 100 GBP overflows in EUR
+    let indexed = IndexedFxTable::from_table(&table).unwrap();
+    assert_eq!(indexed.currencies().len(), 3);
+
+    let values = vec![
+        money(100, Some("DKK")),
+        money(100, None),
+        money(100, Some("DKK")),
+        money(100, Some("XYZ")),
+        money(100, Some("GBP")),
+        money(100, Some("DKK")),
+    ];
+    let dates = vec![d(2024, 3, 5), d(2023, 12, 31), d(2024, 2, 1), d(2024, 3, 5), d(2024, 3, 5), None];
+    let eur = CurrencyCode::from("EUR");
+    let default = CurrencyCode::from("DKK");
+
+    let many = indexed.convert_many(&values, &dates, Some(&default), &eur);
+    for ((value, date), got) in values.iter().zip(&dates).zip(&many) {
+        let expected = convert_money(value, Some(&default), &eur, *date, &table);
+        assert_eq!(format!("{got:?}"), format!("{expected:?}"));
+    }
+    assert_eq!(many[0].as_ref().unwrap().amount, Decimal::new(100, 0) * (Decimal::new(9, 1) / Decimal::new(7, 0)));
+    assert!(matches!(
+        &many[2],
+        Err(FxError::MissingRate { nearest_before, nearest_after, .. })
+            if *nearest_before == d(2023, 12, 1) && *nearest_after == d(2024, 3, 1)
+    ));
+    assert!(matches!(many[3], Err(FxError::UnsupportedCurrency(_))));
+    assert!(matches!(many[4], Err(FxError::Overflow)));
+    assert!(matches!(many[5], Err(FxError::MissingDate)));
+
+    let usd = CurrencyCode::from("USD");
+    assert!(matches!(indexed.get_rate(&eur, &usd, d(2024, 3, 1).unwrap()), Err(FxError::UnsupportedTarget(_))));
+    let mut empty = money(1, None);
+    empty.currency = None;
+    assert!(matches!(
+        indexed.convert_many(&[empty], &[d(2024, 3, 1)], None, &eur)[0],
+        Err(FxError::MissingSourceCurrency)
+    ));
+}
+
+#[test]
+fn test_indexing_rejects_bad_months_and_huge_spans() {
+    let typo = load_monthly_csv("EUR,2024,1,0.9\nEUR,20240,1,0.9\nUSD,2024,1,1\n".as_bytes()).unwrap();
+    let err = IndexedFxTable::from_table(&typo).unwrap_err();
+    assert_eq!(err, IndexError::SpanTooLarge { currency: CurrencyCode::from("EUR"), first: ym(2024, 1), last: ym(20240, 1) });
+    assert_eq!(err.to_string(), "EUR: rates from 2024-01 to 20240-01 span too many months to index");
+
+    let mut far = FxTable::new();
+    far.insert(CurrencyCode::from("EUR"), ym(i32::MIN, 1), Decimal::ONE).unwrap();
+    far.insert(CurrencyCode::from("EUR"), ym(i32::MAX, 12), Decimal::ONE).unwrap();
+    assert!(matches!(IndexedFxTable::from_table(&far), Err(IndexError::SpanTooLarge { .. })));
+
+    let mut month = FxTable::new();
+    month.insert(CurrencyCode::from("EUR"), ym(2024, 13), Decimal::ONE).unwrap();
+    assert!(matches!(IndexedFxTable::try_from(&month), Err(IndexError::InvalidMonth { .. })));
+}
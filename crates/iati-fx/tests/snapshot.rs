@@ -0,0 +1,103 @@
+use chrono::NaiveDate;
+use iati_fx::{FxProvider, FxSnapshot, FxTable, SnapshotError, SnapshotMeta, YearMonth};
+use iati_types::CurrencyCode;
+use rust_decimal::Decimal;
+
+fn snapshot() -> FxSnapshot {
+    let mut table = FxTable::new();
+    let ym = YearMonth { year: 2024, month: 3 };
+    table.insert(CurrencyCode::from("DKK"), ym, Decimal::new(6_8512, 4)).unwrap();
+    table.insert(CurrencyCode::from("EUR"), ym, Decimal::new(9187, 4)).unwrap();
+    let meta = SnapshotMeta {
+        source: "IMF IFS".into(),
+        retrieved: NaiveDate::from_ymd_opt(2024, 4, 15).unwrap(),
+        version: "2024-04".into(),
+    };
+    FxSnapshot::new(table, meta)
+}
+
+#[test]
+fn test_binary_round_trip_and_stable_id() {
+    let snap = snapshot();
+    let bytes = snap.to_bytes().unwrap();
+    let back = FxSnapshot::from_bytes(&bytes).unwrap();
+    assert_eq!(back, snap);
+    assert_eq!(back.id().unwrap(), snap.id().unwrap());
+
+    let date = NaiveDate::from_ymd_opt(2024, 3, 10).unwrap();
+    let rate = back.get_rate(&CurrencyCode::from("DKK"), &CurrencyCode::from("EUR"), date).unwrap();
+    assert_eq!(rate, Decimal::new(9187, 4) / Decimal::new(6_8512, 4));
+
+    let mut changed = snap.clone();
+    changed.meta.version = "2024-05".into();
+    assert_ne!(changed.id().unwrap(), snap.id().unwrap());
+
+    assert!(matches!(FxSnapshot::from_bytes(b"nope"), Err(SnapshotError::Io(_))));
+    let mut future = bytes.clone();
+    future[6] = 2;
+    assert!(matches!(FxSnapshot::from_bytes(&future), Err(SnapshotError::UnsupportedFormat(2))));
+    assert!(FxSnapshot::from_bytes(&bytes[..bytes.len() - 3]).is_err());
+}
+
+#[test]
+fn test_id_ignores_rate_scale() {
+    let snap = snapshot();
+    let mut rescaled = snap.clone();
+    let ((currency, ym), mut rate) = snap.table.rates().first_key_value().map(|(k, r)| (k.clone(), *r)).unwrap();
+    rate.rescale(8);
+    rescaled.table.insert(currency, ym, rate).unwrap();
+    assert_eq!(rescaled, snap);
+    assert_ne!(rescaled.to_bytes().unwrap(), snap.to_bytes().unwrap());
+    assert_eq!(rescaled.id().unwrap(), snap.id().unwrap());
+}
+
+#[test]
+fn test_binary_rejects_bad_codes_months_and_rates() {
+    let mut long = snapshot();
+    let ym = YearMonth { year: 2024, month: 3 };
+    long.table.insert(CurrencyCode::from("X".repeat(256).as_str()), ym, Decimal::ONE).unwrap();
+    assert!(matches!(long.to_bytes(), Err(SnapshotError::Invalid(_))));
+    assert!(matches!(long.id(), Err(SnapshotError::Invalid(_))));
+
+    // last record: "EUR" (1 + 3 bytes), year (4), month (1), rate (16)
+    let bytes = snapshot().to_bytes().unwrap();
+    let month_at = bytes.len() - 17;
+    let mut bad_month = bytes.clone();
+    bad_month[month_at] = 13;
+    assert!(matches!(FxSnapshot::from_bytes(&bad_month), Err(SnapshotError::Invalid(_))));
+
+    let mut trailing = bytes.clone();
+    trailing.push(0);
+    assert!(matches!(FxSnapshot::from_bytes(&trailing), Err(SnapshotError::Invalid(_))));
+
+    let mut month = snapshot();
+    month.table.insert(CurrencyCode::from("EUR"), YearMonth { year: 2024, month: 256 + 3 }, Decimal::ONE).unwrap();
+    assert!(matches!(month.to_bytes(), Err(SnapshotError::Invalid(_))));
+
+    // sign bit of the last rate's flags
+    let mut negative = bytes.clone();
+    negative[bytes.len() - 13] |= 0x80;
+    assert!(matches!(FxSnapshot::from_bytes(&negative), Err(SnapshotError::Invalid(_))));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_json_round_trip() {
+    let snap = snapshot();
+    let json = snap.to_json().unwrap();
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(value["format"], 1);
+    assert_eq!(value["meta"]["retrieved"], "2024-04-15");
+    assert_eq!(value["table"]["rates"][1]["currency"], "EUR");
+    assert_eq!(value["table"]["rates"][1]["month"], 3);
+
+    assert_eq!(FxSnapshot::from_json(&json).unwrap(), snap);
+    let future = json.replace("\"format\": 1", "\"format\": 9");
+    assert!(matches!(FxSnapshot::from_json(&future), Err(SnapshotError::UnsupportedFormat(9))));
+
+    let bad_month = json.replace("\"month\": 3", "\"month\": 0");
+    assert!(matches!(FxSnapshot::from_json(&bad_month), Err(SnapshotError::Json(_))));
+    let negative = json.replace("\"0.9187\"", "\"-0.9187\"");
+    assert_ne!(negative, json);
+    assert!(matches!(FxSnapshot::from_json(&negative), Err(SnapshotError::Json(_))));
+}
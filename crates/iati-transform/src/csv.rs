@@ -0,0 +1,186 @@
+//! CSV export of flattened transactions and activities, with a fixed column
+//! order so spreadsheets and scripts can rely on it.
+
+use std::io::{self, Write};
+
+use crate::flatten::{FlatActivity, FlatTransaction};
+
+/// A record with a fixed set of CSV columns.
+pub trait CsvRow {
+    /// Header names, in output order.
+    const COLUMNS: &'static [&'static str];
+
+    /// One cell per column, unescaped; empty for missing values.
+    fn cells(&self) -> Vec<String>;
+}
+
+fn opt<T: ToString>(v: &Option<T>) -> String {
+    v.as_ref().map(T::to_string).unwrap_or_default()
+}
+
+impl CsvRow for FlatTransaction {
+    const COLUMNS: &'static [&'static str] = &[
+        "iati_identifier",
+        "reporting_org_ref",
+        "reporting_org_name",
+        "activity_start",
+        "activity_end",
+        "transaction_index",
+        "transaction_ref",
+        "tx_type",
+        "tx_type_name",
+        "date",
+        "amount",
+        "currency",
+        "value_date",
+        "provider_org_ref",
+        "provider_org_name",
+        "provider_activity_id",
+        "receiver_org_ref",
+        "receiver_org_name",
+        "receiver_activity_id",
+        "converted_amount",
+        "converted_currency",
+    ];
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.iati_identifier.clone(),
+            opt(&self.reporting_org_ref),
+            opt(&self.reporting_org_name),
+            opt(&self.activity_start),
+            opt(&self.activity_end),
+            self.transaction_index.to_string(),
+            opt(&self.transaction_ref),
+            self.tx_type.to_string(),
+            self.tx_type_name.clone(),
+            self.date.to_string(),
+            self.amount.to_string(),
+            opt(&self.currency),
+            opt(&self.value_date),
+            opt(&self.provider_org_ref),
+            opt(&self.provider_org_name),
+            opt(&self.provider_activity_id),
+            opt(&self.receiver_org_ref),
+            opt(&self.receiver_org_name),
+            opt(&self.receiver_activity_id),
+            opt(&self.converted_amount),
+            opt(&self.converted_currency),
+        ]
+    }
+}
+
+impl CsvRow for FlatActivity {
+    const COLUMNS: &'static [&'static str] = &[
+        "iati_identifier",
+        "reporting_org_ref",
+        "reporting_org_name",
+        "default_currency",
+        "activity_start",
+        "activity_end",
+        "transactions",
+        "budgets",
+        "sectors",
+    ];
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.iati_identifier.clone(),
+            opt(&self.reporting_org_ref),
+            opt(&self.reporting_org_name),
+            opt(&self.default_currency),
+            opt(&self.activity_start),
+            opt(&self.activity_end),
+            self.transactions.to_string(),
+            self.budgets.to_string(),
+            self.sectors.clone(),
+        ]
+    }
+}
+
+/// Quote a cell if it contains a comma, quote or line break (RFC 4180).
+pub fn escape(cell: &str) -> std::borrow::Cow<'_, str> {
+    if cell.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", cell.replace('"', "\"\"")).into()
+    } else {
+        cell.into()
+    }
+}
+
+/// Writes rows of one type, with the header before the first row.
+pub struct CsvWriter<W: Write> {
+    out: W,
+    header_written: bool,
+}
+
+impl<W: Write> CsvWriter<W> {
+    pub fn new(out: W) -> Self {
+        CsvWriter { out, header_written: false }
+    }
+
+    fn write_line<'a>(&mut self, cells: impl IntoIterator<Item = &'a str>) -> io::Result<()> {
+        let line: Vec<_> = cells.into_iter().map(escape).collect();
+        self.out.write_all(line.join(",").as_bytes())?;
+        self.out.write_all(b"\r\n")
+    }
+
+    /// Write the header now (e.g. for an empty table); later calls do nothing.
+    pub fn write_header<T: CsvRow>(&mut self) -> io::Result<()> {
+        if !self.header_written {
+            self.header_written = true;
+            self.write_line(T::COLUMNS.iter().copied())?;
+        }
+        Ok(())
+    }
+
+    pub fn write<T: CsvRow>(&mut self, row: &T) -> io::Result<()> {
+        self.write_header::<T>()?;
+        let cells = row.cells();
+        self.write_line(cells.iter().map(String::as_str))
+    }
+
+    /// Flush and return the underlying writer.
+    pub fn into_inner(mut self) -> io::Result<W> {
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flatten::{flatten_activity, flatten_transactions};
+    use chrono::NaiveDate;
+    use iati_types::{money::CurrencyCode, money::Money, tx::Transaction, Activity, OrgRef, TxType};
+    use rust_decimal::Decimal;
+
+    #[test]
+    fn writes_header_once_and_escapes_cells() {
+        let mut a = Activity::new("XM-1");
+        a.default_currency = Some(CurrencyCode::from("EUR"));
+        a.reporting_org = Some(OrgRef { ref_id: Some("XM".into()), name: Some("Aid, \"Inc\"".into()), activity_id: None });
+        let date = NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+        a.activity_start = Some(date);
+        a.transactions.push(Transaction::new(TxType::Disbursement, date, Money::new(Decimal::new(1050, 2))));
+        a.transactions.push(Transaction::new(TxType::Expenditure, date, Money::new(Decimal::ONE)));
+
+        let mut w = CsvWriter::new(Vec::new());
+        for row in flatten_transactions(&a) {
+            w.write(&row).unwrap();
+        }
+        let text = String::from_utf8(w.into_inner().unwrap()).unwrap();
+        let lines: Vec<&str> = text.split("\r\n").collect();
+        assert_eq!(lines.len(), 4); // header, 2 rows, trailing empty
+        assert_eq!(lines[0], FlatTransaction::COLUMNS.join(","));
+        assert_eq!(
+            lines[1],
+            "XM-1,XM,\"Aid, \"\"Inc\"\"\",2024-01-02,,0,,3,Disbursement,2024-01-02,10.50,EUR,,,,,,,,,"
+        );
+
+        let mut w = CsvWriter::new(Vec::new());
+        w.write_header::<FlatActivity>().unwrap();
+        w.write(&flatten_activity(&a)).unwrap();
+        let text = String::from_utf8(w.into_inner().unwrap()).unwrap();
+        assert!(text.ends_with("\r\nXM-1,XM,\"Aid, \"\"Inc\"\"\",EUR,2024-01-02,,2,0,\r\n"));
+    }
+}
@@ -0,0 +1,386 @@
+//! Double-counting detection across publishers.
+//!
+//! When a donor's outgoing transaction (e.g. a disbursement, type 3) and the
+//! recipient's matching incoming transaction (incoming funds, type 1) are both
+//! aggregated, the same money is counted twice. This pass pairs them up and
+//! either flags the pairs or excludes one side.
+
+use iati_types::{money::CurrencyCode, tx::TxType, Activity};
+use rust_decimal::Decimal;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// What to do with matched pairs.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DedupPolicy {
+    /// Report the pairs, keep both transactions.
+    #[default]
+    Flag,
+    /// Keep the funder's outgoing transaction, exclude the recipient's incoming one.
+    KeepOutgoing,
+    /// Keep the recipient's incoming transaction, exclude the funder's outgoing one.
+    KeepIncoming,
+}
+
+/// Matching thresholds and the policy to apply.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DedupOptions {
+    /// Relative amount tolerance, e.g. `0.01` accepts a 1% difference.
+    pub relative_tolerance: Decimal,
+    /// Absolute amount tolerance in the transactions' currency.
+    pub absolute_tolerance: Decimal,
+    /// Maximum number of days between the two transaction dates.
+    pub date_window_days: i64,
+    /// Also match on organisation refs when neither side links the other activity.
+    pub match_org_refs: bool,
+    pub policy: DedupPolicy,
+}
+
+impl Default for DedupOptions {
+    fn default() -> Self {
+        DedupOptions {
+            relative_tolerance: Decimal::new(1, 2),
+            absolute_tolerance: Decimal::ZERO,
+            date_window_days: 90,
+            match_org_refs: true,
+            policy: DedupPolicy::Flag,
+        }
+    }
+}
+
+/// Position of a transaction in the input slice.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxLocation {
+    pub iati_identifier: String,
+    pub activity_index: usize,
+    pub tx_index: usize,
+    pub tx_type: TxType,
+}
+
+/// Why two transactions were considered the same flow.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MatchBasis {
+    /// One side names the other activity via provider/receiver activity id.
+    ActivityLink,
+    /// Provider/receiver org refs agree with the other side's reporting org.
+    OrgRefs,
+}
+
+/// Decision taken for a matched pair.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DedupAction {
+    Flagged,
+    ExcludedIncoming,
+    ExcludedOutgoing,
+}
+
+/// One matched outgoing/incoming pair.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicatePair {
+    pub outgoing: TxLocation,
+    pub incoming: TxLocation,
+    pub basis: MatchBasis,
+    pub currency: CurrencyCode,
+    /// `outgoing - incoming` amount.
+    pub amount_difference: Decimal,
+    /// `incoming.date - outgoing.date` in days.
+    pub days_apart: i64,
+    pub action: DedupAction,
+}
+
+/// Report of the decisions made by the pass.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct DedupReport {
+    pub pairs: Vec<DuplicatePair>,
+}
+
+impl DedupReport {
+    /// Locations excluded by the policy, as `(activity_index, tx_index)`.
+    pub fn excluded(&self) -> BTreeSet<(usize, usize)> {
+        self.pairs
+            .iter()
+            .filter_map(|p| match p.action {
+                DedupAction::Flagged => None,
+                DedupAction::ExcludedIncoming => Some((p.incoming.activity_index, p.incoming.tx_index)),
+                DedupAction::ExcludedOutgoing => Some((p.outgoing.activity_index, p.outgoing.tx_index)),
+            })
+            .collect()
+    }
+}
+
+/// Incoming counterpart for each outgoing type.
+fn counterpart(tx_type: TxType) -> Option<TxType> {
+    match tx_type {
+        TxType::Disbursement => Some(TxType::IncomingFunds),
+        TxType::OutgoingCommitment => Some(TxType::IncomingCommitment),
+        _ => None,
+    }
+}
+
+fn org_ref(act: &Activity) -> Option<&str> {
+    act.reporting_org.as_ref()?.ref_id.as_deref()
+}
+
+fn link_basis(out_act: &Activity, out_tx: usize, in_act: &Activity, in_tx: usize, opts: &DedupOptions) -> Option<MatchBasis> {
+    let o = &out_act.transactions[out_tx];
+    let i = &in_act.transactions[in_tx];
+
+    let names_recipient = o
+        .receiver_org
+        .as_ref()
+        .and_then(|r| r.activity_id.as_deref())
+        == Some(in_act.iati_identifier.as_str());
+    let names_funder = i
+        .provider_org
+        .as_ref()
+        .and_then(|p| p.activity_id.as_deref())
+        == Some(out_act.iati_identifier.as_str());
+    if names_recipient || names_funder {
+        return Some(MatchBasis::ActivityLink);
+    }
+
+    if opts.match_org_refs {
+        let receiver_ok = matches!(
+            (o.receiver_org.as_ref().and_then(|r| r.ref_id.as_deref()), org_ref(in_act)),
+            (Some(a), Some(b)) if a == b
+        );
+        let provider_ok = matches!(
+            (i.provider_org.as_ref().and_then(|p| p.ref_id.as_deref()), org_ref(out_act)),
+            (Some(a), Some(b)) if a == b
+        );
+        if receiver_ok && provider_ok {
+            return Some(MatchBasis::OrgRefs);
+        }
+    }
+    None
+}
+
+fn within_tolerance(a: Decimal, b: Decimal, opts: &DedupOptions) -> bool {
+    let diff = (a - b).abs();
+    let scale = a.abs().max(b.abs());
+    diff <= opts.absolute_tolerance || diff <= scale * opts.relative_tolerance
+}
+
+/// Find matching outgoing/incoming pairs across different activities.
+///
+/// Both sides must resolve to the same currency (value.currency or
+/// activity.default_currency); convert activities first to match across
+/// currencies. Each transaction is used in at most one pair. Among the
+/// candidates, activity-id links win over org-ref matches, then the closest
+/// date, then the closest amount.
+pub fn find_double_counting(activities: &[Activity], opts: &DedupOptions) -> DedupReport {
+    // Candidate incoming transactions: (activity_index, tx_index, currency)
+    let mut incoming: Vec<(usize, usize, CurrencyCode)> = Vec::new();
+    for (ai, act) in activities.iter().enumerate() {
+        for (ti, tx) in act.transactions.iter().enumerate() {
+            if !matches!(tx.tx_type, TxType::IncomingFunds | TxType::IncomingCommitment) {
+                continue;
+            }
+            if let Some(c) = tx.value.currency.clone().or_else(|| act.default_currency.clone()) {
+                incoming.push((ai, ti, c));
+            }
+        }
+    }
+
+    let mut used: BTreeSet<(usize, usize)> = BTreeSet::new();
+    let mut report = DedupReport::default();
+
+    for (oa, out_act) in activities.iter().enumerate() {
+        for (ot, out_tx) in out_act.transactions.iter().enumerate() {
+            let Some(wanted) = counterpart(out_tx.tx_type) else {
+                continue;
+            };
+            let Some(out_cur) = out_tx.value.currency.clone().or_else(|| out_act.default_currency.clone()) else {
+                continue;
+            };
+
+            // (basis, |days|, |amount diff|, ai, ti)
+            let mut best: Option<(MatchBasis, i64, Decimal, usize, usize)> = None;
+            for (ia, it, in_cur) in &incoming {
+                let (ia, it) = (*ia, *it);
+                if ia == oa || *in_cur != out_cur || used.contains(&(ia, it)) {
+                    continue;
+                }
+                let in_act = &activities[ia];
+                let in_tx = &in_act.transactions[it];
+                if in_tx.tx_type != wanted {
+                    continue;
+                }
+                let days = (in_tx.date - out_tx.date).num_days();
+                if days.abs() > opts.date_window_days
+                    || !within_tolerance(out_tx.value.amount, in_tx.value.amount, opts)
+                {
+                    continue;
+                }
+                let Some(basis) = link_basis(out_act, ot, in_act, it, opts) else {
+                    continue;
+                };
+                let key = (basis, days.abs(), (out_tx.value.amount - in_tx.value.amount).abs(), ia, it);
+                if best.as_ref().is_none_or(|b| key < *b) {
+                    best = Some(key);
+                }
+            }
+
+            if let Some((basis, _, _, ia, it)) = best {
+                used.insert((ia, it));
+                let in_act = &activities[ia];
+                let in_tx = &in_act.transactions[it];
+                report.pairs.push(DuplicatePair {
+                    outgoing: TxLocation {
+                        iati_identifier: out_act.iati_identifier.clone(),
+                        activity_index: oa,
+                        tx_index: ot,
+                        tx_type: out_tx.tx_type,
+                    },
+                    incoming: TxLocation {
+                        iati_identifier: in_act.iati_identifier.clone(),
+                        activity_index: ia,
+                        tx_index: it,
+                        tx_type: in_tx.tx_type,
+                    },
+                    basis,
+                    currency: out_cur.clone(),
+                    amount_difference: out_tx.value.amount - in_tx.value.amount,
+                    days_apart: (in_tx.date - out_tx.date).num_days(),
+                    action: match opts.policy {
+                        DedupPolicy::Flag => DedupAction::Flagged,
+                        DedupPolicy::KeepOutgoing => DedupAction::ExcludedIncoming,
+                        DedupPolicy::KeepIncoming => DedupAction::ExcludedOutgoing,
+                    },
+                });
+            }
+        }
+    }
+
+    report
+}
+
+/// Run `find_double_counting` and return copies of the activities with the
+/// excluded transactions removed. Indices in the report refer to the input.
+pub fn deduplicate(activities: &[Activity], opts: &DedupOptions) -> (Vec<Activity>, DedupReport) {
+    let report = find_double_counting(activities, opts);
+    let excluded = report.excluded();
+
+    let out = activities
+        .iter()
+        .enumerate()
+        .map(|(ai, act)| {
+            let mut act = act.clone();
+            act.transactions = act
+                .transactions
+                .into_iter()
+                .enumerate()
+                .filter(|(ti, _)| !excluded.contains(&(ai, *ti)))
+                .map(|(_, tx)| tx)
+                .collect();
+            act
+        })
+        .collect();
+
+    (out, report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use iati_types::{money::Money, tx::Transaction, OrgRef};
+
+    fn tx(tx_type: TxType, day: u32, amount: i64) -> Transaction {
+        Transaction::new(
+            tx_type,
+            NaiveDate::from_ymd_opt(2023, 3, day).unwrap(),
+            Money::new(Decimal::new(amount, 0)),
+        )
+    }
+
+    fn org(ref_id: Option<&str>, activity_id: Option<&str>) -> OrgRef {
+        OrgRef {
+            ref_id: ref_id.map(String::from),
+            name: None,
+            activity_id: activity_id.map(String::from),
+        }
+    }
+
+    fn activity(id: &str, reporting: &str, txs: Vec<Transaction>) -> Activity {
+        let mut a = Activity::new(id);
+        a.default_currency = Some(CurrencyCode::from("USD"));
+        a.reporting_org = Some(org(Some(reporting), None));
+        a.transactions = txs;
+        a
+    }
+
+    #[test]
+    fn matches_linked_disbursement_and_incoming_funds() {
+        let donor = activity("DONOR-1", "DONOR", vec![
+            tx(TxType::Disbursement, 1, 1000).with_receiver(org(None, Some("NGO-1"))),
+            tx(TxType::Disbursement, 2, 5000), // unrelated
+        ]);
+        let ngo = activity("NGO-1", "NGO", vec![
+            tx(TxType::IncomingFunds, 20, 995), // within 1%
+            tx(TxType::Expenditure, 25, 900),
+        ]);
+        let acts = [donor, ngo];
+
+        let report = find_double_counting(&acts, &DedupOptions::default());
+        assert_eq!(report.pairs.len(), 1);
+        let p = &report.pairs[0];
+        assert_eq!(p.basis, MatchBasis::ActivityLink);
+        assert_eq!((p.outgoing.activity_index, p.outgoing.tx_index), (0, 0));
+        assert_eq!((p.incoming.activity_index, p.incoming.tx_index), (1, 0));
+        assert_eq!(p.amount_difference, Decimal::new(5, 0));
+        assert_eq!(p.days_apart, 19);
+        assert_eq!(p.action, DedupAction::Flagged);
+    }
+
+    #[test]
+    fn org_ref_match_and_exclusion_policy() {
+        let donor = activity("DONOR-1", "DONOR", vec![
+            tx(TxType::Disbursement, 1, 1000).with_receiver(org(Some("NGO"), None)),
+        ]);
+        let ngo = activity("NGO-1", "NGO", vec![
+            tx(TxType::IncomingFunds, 3, 1000).with_provider(org(Some("DONOR"), None)),
+        ]);
+        let acts = [donor, ngo];
+        let opts = DedupOptions {
+            policy: DedupPolicy::KeepOutgoing,
+            ..Default::default()
+        };
+
+        let (out, report) = deduplicate(&acts, &opts);
+        assert_eq!(report.pairs[0].basis, MatchBasis::OrgRefs);
+        assert_eq!(report.pairs[0].action, DedupAction::ExcludedIncoming);
+        assert_eq!(out[0].transactions.len(), 1);
+        assert!(out[1].transactions.is_empty());
+
+        let strict = DedupOptions {
+            match_org_refs: false,
+            ..Default::default()
+        };
+        assert!(find_double_counting(&acts, &strict).pairs.is_empty());
+    }
+
+    #[test]
+    fn outside_window_or_tolerance_is_not_matched() {
+        let link = || org(None, Some("NGO-1"));
+        let donor = activity("DONOR-1", "DONOR", vec![
+            tx(TxType::Disbursement, 1, 1000).with_receiver(link()),
+        ]);
+        let mut ngo = activity("NGO-1", "NGO", vec![tx(TxType::IncomingFunds, 2, 900)]);
+        ngo.transactions.push(Transaction::new(
+            TxType::IncomingFunds,
+            NaiveDate::from_ymd_opt(2024, 3, 1).unwrap(),
+            Money::new(Decimal::new(1000, 0)),
+        ));
+
+        assert!(find_double_counting(&[donor, ngo], &DedupOptions::default()).pairs.is_empty());
+    }
+}
@@ -0,0 +1,331 @@
+//! Budget-vs-actual execution analysis.
+//!
+//! Compares each activity's budgets with its commitments (type 2) and spend
+//! (disbursements + expenditures, types 3 and 4), per budget period and in total.
+
+use chrono::NaiveDate;
+use iati_types::{budget::BudgetType, money::{CurrencyCode, MoneyError}, tx::TxType, Activity, RoundingPolicy};
+use rust_decimal::Decimal;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::{FxStrategy, TransformError};
+use iati_fx::DateContext;
+
+/// Budget, commitment and spend figures in a single currency.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ExecutionFigures {
+    pub budget: Decimal,
+    /// Sum of outgoing commitments (type 2).
+    pub commitments: Decimal,
+    /// Sum of disbursements and expenditures (types 3 + 4).
+    pub spend: Decimal,
+    /// `spend / budget`, or `None` when there is no budget to execute against.
+    pub execution_rate: Option<Decimal>,
+    /// Committed but not yet spent: `commitments - spend`.
+    pub remaining_pipeline: Decimal,
+}
+
+impl ExecutionFigures {
+    fn finish(&mut self) -> Result<(), MoneyError> {
+        self.execution_rate = if self.budget.is_zero() {
+            None
+        } else {
+            Some(self.spend.checked_div(self.budget).ok_or(MoneyError::Overflow)?)
+        };
+        self.remaining_pipeline = self.commitments.checked_sub(self.spend).ok_or(MoneyError::Overflow)?;
+        Ok(())
+    }
+
+    fn round(&mut self, policy: &RoundingPolicy, currency: &CurrencyCode) {
+        self.budget = policy.round(self.budget, Some(currency));
+        self.commitments = policy.round(self.commitments, Some(currency));
+        self.spend = policy.round(self.spend, Some(currency));
+        self.remaining_pipeline = self.commitments - self.spend;
+    }
+}
+
+/// Execution figures for one budget period, keyed by currency.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeriodExecution {
+    pub period_start: NaiveDate,
+    pub period_end: NaiveDate,
+    pub figures: BTreeMap<CurrencyCode, ExecutionFigures>,
+}
+
+/// Execution report for a single activity.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActivityExecution {
+    pub iati_identifier: String,
+    /// One entry per distinct budget period, ordered by period start.
+    pub periods: Vec<PeriodExecution>,
+    /// Transactions dated outside every budget period.
+    pub unbudgeted: BTreeMap<CurrencyCode, ExecutionFigures>,
+    /// Whole-activity figures (all periods plus unbudgeted transactions).
+    pub total: BTreeMap<CurrencyCode, ExecutionFigures>,
+}
+
+impl ActivityExecution {
+    /// Round every amount with `policy`; `execution_rate` keeps full precision.
+    pub fn round(&mut self, policy: &RoundingPolicy) {
+        let periods = self.periods.iter_mut().map(|p| &mut p.figures);
+        for by_cur in periods.chain([&mut self.unbudgeted, &mut self.total]) {
+            by_cur.iter_mut().for_each(|(cur, f)| f.round(policy, cur));
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Column {
+    Budget,
+    Commitment,
+    Spend,
+}
+
+fn bump(
+    map: &mut BTreeMap<CurrencyCode, ExecutionFigures>,
+    currency: CurrencyCode,
+    column: Column,
+    amount: Decimal,
+) -> Result<(), MoneyError> {
+    let f = map.entry(currency).or_default();
+    let total = match column {
+        Column::Budget => &mut f.budget,
+        Column::Commitment => &mut f.commitments,
+        Column::Spend => &mut f.spend,
+    };
+    *total = total.checked_add(amount).ok_or(MoneyError::Overflow)?;
+    Ok(())
+}
+
+/// `finish` every figure in `map`.
+fn finish_all(map: &mut BTreeMap<CurrencyCode, ExecutionFigures>) -> Result<(), MoneyError> {
+    map.values_mut().try_for_each(ExecutionFigures::finish)
+}
+
+/// Build a budget-vs-actual report for one activity.
+///
+/// - Budget periods are taken from `activity.budgets`; when a period has a
+///   revised budget (type 2), it supersedes the original for that period.
+/// - Transactions are attributed to the first budget period covering their date.
+/// - Currency resolution: value.currency -> act.default_currency; values with
+///   neither are skipped, as in the aggregations.
+/// - Errors with `TransformError::Money` if a figure overflows.
+pub fn activity_execution(act: &Activity, fx: &FxStrategy<'_>) -> Result<ActivityExecution, TransformError> {
+    // (start, end) -> has revised budget
+    let mut revised: BTreeMap<(NaiveDate, NaiveDate), bool> = BTreeMap::new();
+    for b in &act.budgets {
+        let r = revised.entry((b.period_start, b.period_end)).or_default();
+        *r |= b.budget_type == BudgetType::Revised;
+    }
+
+    let mut periods: Vec<PeriodExecution> = revised
+        .keys()
+        .map(|&(period_start, period_end)| PeriodExecution {
+            period_start,
+            period_end,
+            figures: BTreeMap::new(),
+        })
+        .collect();
+    let mut unbudgeted = BTreeMap::new();
+    let mut total = BTreeMap::new();
+
+    for b in &act.budgets {
+        let key = (b.period_start, b.period_end);
+        if revised[&key] && b.budget_type != BudgetType::Revised {
+            continue;
+        }
+        let Some((amount, cur)) = fx.apply(act, &b.value, &DateContext::for_budget(act, b))? else {
+            continue;
+        };
+        let period = periods
+            .iter_mut()
+            .find(|p| (p.period_start, p.period_end) == key)
+            .expect("period created from the same budgets");
+        bump(&mut period.figures, cur.clone(), Column::Budget, amount)?;
+        bump(&mut total, cur, Column::Budget, amount)?;
+    }
+
+    for tx in &act.transactions {
+        let column = match tx.tx_type {
+            TxType::OutgoingCommitment => Column::Commitment,
+            TxType::Disbursement | TxType::Expenditure => Column::Spend,
+            _ => continue,
+        };
+        let Some((amount, cur)) = fx.apply(act, &tx.value, &DateContext::for_transaction(act, tx))? else {
+            continue;
+        };
+        let target = match periods
+            .iter_mut()
+            .find(|p| p.period_start <= tx.date && tx.date <= p.period_end)
+        {
+            Some(p) => &mut p.figures,
+            None => &mut unbudgeted,
+        };
+        bump(target, cur.clone(), column, amount)?;
+        bump(&mut total, cur, column, amount)?;
+    }
+
+    for p in &mut periods {
+        finish_all(&mut p.figures)?;
+    }
+    finish_all(&mut unbudgeted)?;
+    finish_all(&mut total)?;
+
+    Ok(ActivityExecution {
+        iati_identifier: act.iati_identifier.clone(),
+        periods,
+        unbudgeted,
+        total,
+    })
+}
+
+/// Build a budget-vs-actual report for each activity, in input order.
+pub fn budget_execution(activities: &[Activity], fx: &FxStrategy<'_>) -> Result<Vec<ActivityExecution>, TransformError> {
+    activities.iter().map(|act| activity_execution(act, fx)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use iati_fx::{FxTable, YearMonth};
+    use iati_types::{budget::Budget, money::Money, tx::{Transaction, TxType}};
+
+    fn d(y: i32, m: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, day).unwrap()
+    }
+
+    fn mk_activity() -> Activity {
+        let mut a = Activity::new("A1");
+        a.default_currency = Some(CurrencyCode::from("EUR"));
+        a.budgets.push(Budget::new(d(2023, 1, 1), d(2023, 12, 31), Money::new(Decimal::new(1000, 0))));
+        a.budgets.push(
+            Budget::new(d(2023, 1, 1), d(2023, 12, 31), Money::new(Decimal::new(800, 0)))
+                .with_type(BudgetType::Revised),
+        );
+        a.budgets.push(Budget::new(d(2024, 1, 1), d(2024, 12, 31), Money::new(Decimal::new(500, 0))));
+        let txs = [
+            (TxType::OutgoingCommitment, d(2023, 2, 1), 600),
+            (TxType::Disbursement, d(2023, 3, 1), 300),
+            (TxType::Expenditure, d(2023, 4, 1), 100),
+            (TxType::IncomingFunds, d(2023, 4, 1), 999),
+            (TxType::Reimbursement, d(2023, 4, 1), 77), // not spend for execution
+            (TxType::Disbursement, d(2025, 1, 1), 50),
+        ];
+        for (t, date, amount) in txs {
+            a.transactions.push(Transaction::new(t, date, Money::new(Decimal::new(amount, 0))));
+        }
+        a
+    }
+
+    #[test]
+    fn revised_budget_supersedes_original() {
+        let report = activity_execution(&mk_activity(), &FxStrategy::Native).unwrap();
+        let eur = CurrencyCode::from("EUR");
+
+        assert_eq!(report.periods.len(), 2);
+        let p2023 = &report.periods[0].figures[&eur];
+        assert_eq!(p2023.budget, Decimal::new(800, 0));
+        assert_eq!(p2023.commitments, Decimal::new(600, 0));
+        assert_eq!(p2023.spend, Decimal::new(400, 0));
+        assert_eq!(p2023.execution_rate, Some(Decimal::new(5, 1)));
+        assert_eq!(p2023.remaining_pipeline, Decimal::new(200, 0));
+
+        let p2024 = &report.periods[1].figures[&eur];
+        assert_eq!(p2024.spend, Decimal::ZERO);
+        assert_eq!(p2024.execution_rate, Some(Decimal::ZERO));
+
+        let unbudgeted = &report.unbudgeted[&eur];
+        assert_eq!(unbudgeted.spend, Decimal::new(50, 0));
+        assert_eq!(unbudgeted.execution_rate, None);
+
+        let total = &report.total[&eur];
+        assert_eq!(total.budget, Decimal::new(1300, 0));
+        assert_eq!(total.spend, Decimal::new(450, 0));
+    }
+
+    #[test]
+    fn converts_through_fx_provider() {
+        let mut table = FxTable::new();
+        for year in 2023..=2025 {
+            for month in 1..=12 {
+                let ym = YearMonth { year, month };
+                table.insert(CurrencyCode::from("EUR"), ym, Decimal::new(5, 1)).unwrap();
+                table.insert(CurrencyCode::from("USD"), ym, Decimal::ONE).unwrap();
+            }
+        }
+        let usd = CurrencyCode::from("USD");
+        let fx = FxStrategy::convert(&usd, &table);
+
+        let reports = budget_execution(&[mk_activity()], &fx).unwrap();
+        let total = &reports[0].total[&usd];
+        assert_eq!(total.budget, Decimal::new(2600, 0));
+        assert_eq!(total.spend, Decimal::new(900, 0));
+        assert!(!reports[0].total.contains_key(&CurrencyCode::from("EUR")));
+    }
+
+    #[test]
+    fn date_policy_picks_the_fx_date() {
+        use iati_fx::DatePolicy;
+
+        // only January 2023 rates: everything converts at a fixed date in that month
+        let mut table = FxTable::new();
+        let ym = YearMonth { year: 2023, month: 1 };
+        table.insert(CurrencyCode::from("EUR"), ym, Decimal::new(5, 1)).unwrap();
+        table.insert(CurrencyCode::from("USD"), ym, Decimal::ONE).unwrap();
+        let usd = CurrencyCode::from("USD");
+
+        let fixed = DatePolicy::Fixed(d(2023, 1, 15));
+        let fx = FxStrategy::Convert { target: &usd, provider: &table, dates: &fixed };
+        let report = activity_execution(&mk_activity(), &fx).unwrap();
+        assert_eq!(report.total[&usd].budget, Decimal::new(2600, 0));
+
+        // no activity start date -> MissingDate
+        let start = DatePolicy::ActivityStart;
+        let fx = FxStrategy::Convert { target: &usd, provider: &table, dates: &start };
+        assert!(matches!(
+            activity_execution(&mk_activity(), &fx),
+            Err(TransformError::Fx(iati_fx::FxError::MissingDate))
+        ));
+    }
+
+    #[test]
+    fn overflow_is_an_error() {
+        let mut a = mk_activity();
+        a.transactions.push(Transaction::new(TxType::Disbursement, d(2023, 5, 1), Money::new(Decimal::MAX)));
+        assert!(matches!(
+            activity_execution(&a, &FxStrategy::Native),
+            Err(TransformError::Money(MoneyError::Overflow))
+        ));
+
+        // A huge rate overflows the conversion itself.
+        let mut table = FxTable::new();
+        let usd = CurrencyCode::from("USD");
+        for month in 1..=12 {
+            table.insert(CurrencyCode::from("EUR"), YearMonth { year: 2023, month }, Decimal::ONE).unwrap();
+            table.insert(usd.clone(), YearMonth { year: 2023, month }, Decimal::MAX).unwrap();
+        }
+        let mut a = Activity::new("A2");
+        a.default_currency = Some(CurrencyCode::from("EUR"));
+        a.transactions.push(Transaction::new(TxType::Disbursement, d(2023, 5, 1), Money::new(Decimal::new(2, 0))));
+        assert!(matches!(
+            activity_execution(&a, &FxStrategy::convert(&usd, &table)),
+            Err(TransformError::Money(MoneyError::Overflow))
+        ));
+    }
+
+    #[test]
+    fn missing_rate_is_an_error() {
+        let table = FxTable::new();
+        let usd = CurrencyCode::from("USD");
+        let fx = FxStrategy::convert(&usd, &table);
+        assert!(matches!(
+            activity_execution(&mk_activity(), &fx),
+            Err(TransformError::Fx(_))
+        ));
+    }
+}
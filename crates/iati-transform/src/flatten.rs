@@ -0,0 +1,206 @@
+//! One row per transaction, with the parent activity's fields denormalised,
+//! and one row per activity, for tabular outputs (NDJSON, CSV, dataframes).
+
+use chrono::NaiveDate;
+use iati_fx::DateContext;
+use iati_types::{money::CurrencyCode, Activity, OrgRef};
+use rust_decimal::Decimal;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{FxStrategy, TransformError};
+
+/// A transaction together with the activity fields needed to analyse it alone.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlatTransaction {
+    pub iati_identifier: String,
+    pub reporting_org_ref: Option<String>,
+    pub reporting_org_name: Option<String>,
+    pub activity_start: Option<NaiveDate>,
+    pub activity_end: Option<NaiveDate>,
+    /// Position of the transaction within the activity (0-based).
+    pub transaction_index: usize,
+    pub transaction_ref: Option<String>,
+    /// 'transaction-type/@code'
+    pub tx_type: u16,
+    pub tx_type_name: String,
+    pub date: NaiveDate,
+    pub amount: Decimal,
+    /// value/@currency, else the activity default-currency.
+    pub currency: Option<CurrencyCode>,
+    pub value_date: Option<NaiveDate>,
+    pub provider_org_ref: Option<String>,
+    pub provider_org_name: Option<String>,
+    pub provider_activity_id: Option<String>,
+    pub receiver_org_ref: Option<String>,
+    pub receiver_org_name: Option<String>,
+    pub receiver_activity_id: Option<String>,
+    /// Filled by [`convert_flat`]; `None` until converted.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub converted_amount: Option<Decimal>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub converted_currency: Option<CurrencyCode>,
+}
+
+/// Fill the converted columns of `row`, a row flattened from `activity`, using `fx`.
+/// On error the columns stay empty; a row whose `transaction_index` is not in
+/// `activity` is [`TransformError::NoSuchTransaction`].
+pub fn convert_flat(row: &mut FlatTransaction, activity: &Activity, fx: &FxStrategy) -> Result<(), TransformError> {
+    let tx = activity.transactions.get(row.transaction_index).ok_or_else(|| TransformError::NoSuchTransaction {
+        iati_identifier: activity.iati_identifier.clone(),
+        index: row.transaction_index,
+    })?;
+    let ctx = DateContext::for_transaction(activity, tx);
+    match fx.apply(activity, &tx.value, &ctx)? {
+        Some((amount, currency)) => {
+            row.converted_amount = Some(amount);
+            row.converted_currency = Some(currency);
+            Ok(())
+        }
+        None => Err(TransformError::MissingCurrency),
+    }
+}
+
+/// Activity-level fields, one row per activity.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlatActivity {
+    pub iati_identifier: String,
+    pub reporting_org_ref: Option<String>,
+    pub reporting_org_name: Option<String>,
+    pub default_currency: Option<CurrencyCode>,
+    pub activity_start: Option<NaiveDate>,
+    pub activity_end: Option<NaiveDate>,
+    pub transactions: usize,
+    pub budgets: usize,
+    /// Sector codes, `;`-separated, with percentages as `code:pct`.
+    pub sectors: String,
+}
+
+pub fn flatten_activity(activity: &Activity) -> FlatActivity {
+    let org = activity.reporting_org.as_ref();
+    let sectors: Vec<String> = activity
+        .sectors
+        .iter()
+        .map(|s| match s.percentage {
+            Some(pct) => format!("{}:{}", s.code, pct.normalize()),
+            None => s.code.clone(),
+        })
+        .collect();
+    FlatActivity {
+        iati_identifier: activity.iati_identifier.clone(),
+        reporting_org_ref: org.and_then(|o| o.ref_id.clone()),
+        reporting_org_name: org.and_then(|o| o.name.clone()),
+        default_currency: activity.default_currency.clone(),
+        activity_start: activity.activity_start,
+        activity_end: activity.activity_end,
+        transactions: activity.transactions.len(),
+        budgets: activity.budgets.len(),
+        sectors: sectors.join(";"),
+    }
+}
+
+/// Flatten every transaction of `activity`, in order.
+pub fn flatten_transactions(activity: &Activity) -> impl Iterator<Item = FlatTransaction> + '_ {
+    let org = |o: &Option<OrgRef>| {
+        let o = o.as_ref();
+        (
+            o.and_then(|o| o.ref_id.clone()),
+            o.and_then(|o| o.name.clone()),
+            o.and_then(|o| o.activity_id.clone()),
+        )
+    };
+    let (reporting_org_ref, reporting_org_name, _) = org(&activity.reporting_org);
+
+    activity.transactions.iter().enumerate().map(move |(i, tx)| {
+        let (provider_org_ref, provider_org_name, provider_activity_id) = org(&tx.provider_org);
+        let (receiver_org_ref, receiver_org_name, receiver_activity_id) = org(&tx.receiver_org);
+        FlatTransaction {
+            iati_identifier: activity.iati_identifier.clone(),
+            reporting_org_ref: reporting_org_ref.clone(),
+            reporting_org_name: reporting_org_name.clone(),
+            activity_start: activity.activity_start,
+            activity_end: activity.activity_end,
+            transaction_index: i,
+            transaction_ref: tx.ref_id.clone(),
+            tx_type: tx.tx_type.code(),
+            tx_type_name: tx.tx_type.name().to_string(),
+            date: tx.date,
+            amount: tx.value.amount,
+            currency: tx.value.currency.clone().or_else(|| activity.default_currency.clone()),
+            value_date: tx.value.value_date,
+            provider_org_ref,
+            provider_org_name,
+            provider_activity_id,
+            receiver_org_ref,
+            receiver_org_name,
+            receiver_activity_id,
+            converted_amount: None,
+            converted_currency: None,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use iati_types::{money::Money, tx::Transaction, TxType};
+
+    #[test]
+    fn flattens_with_activity_fields_and_currency_fallback() {
+        let mut a = Activity::new("XM-1");
+        a.default_currency = Some(CurrencyCode::from("EUR"));
+        a.reporting_org = Some(OrgRef { ref_id: Some("XM".into()), name: Some("Org".into()), activity_id: None });
+        let date = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        a.transactions.push(Transaction::new(TxType::Disbursement, date, Money::new(Decimal::new(5, 0))));
+        let tx = Transaction::new(TxType::IncomingFunds, date, Money::new(Decimal::ONE))
+            .with_provider(OrgRef { ref_id: Some("GB".into()), name: None, activity_id: Some("GB-1".into()) })
+            .with_ref("T2");
+        a.transactions.push(tx);
+        a.transactions[1].value.currency = Some(CurrencyCode::from("GBP"));
+
+        let rows: Vec<FlatTransaction> = flatten_transactions(&a).collect();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].currency, Some(CurrencyCode::from("EUR")));
+        assert_eq!(rows[0].reporting_org_ref.as_deref(), Some("XM"));
+        assert_eq!((rows[0].tx_type, rows[0].tx_type_name.as_str()), (3, "Disbursement"));
+        assert_eq!(rows[1].transaction_index, 1);
+        assert_eq!(rows[1].transaction_ref.as_deref(), Some("T2"));
+        assert_eq!(rows[1].currency, Some(CurrencyCode::from("GBP")));
+        assert_eq!(rows[1].provider_activity_id.as_deref(), Some("GB-1"));
+    }
+
+    #[test]
+    fn convert_flat_fills_converted_columns() {
+        let mut table = iati_fx::FxTable::new();
+        let ym = iati_fx::YearMonth { year: 2024, month: 1 };
+        table.insert(CurrencyCode::from("EUR"), ym, Decimal::new(5, 1)).unwrap();
+        table.insert(CurrencyCode::from("USD"), ym, Decimal::ONE).unwrap();
+        let usd = CurrencyCode::from("USD");
+        let fx = FxStrategy::convert(&usd, &table);
+
+        let mut a = Activity::new("XM-1");
+        a.default_currency = Some(CurrencyCode::from("EUR"));
+        for month in [1, 2] {
+            let date = NaiveDate::from_ymd_opt(2024, month, 1).unwrap();
+            a.transactions.push(Transaction::new(TxType::Disbursement, date, Money::new(Decimal::new(10, 0))));
+        }
+
+        let mut rows: Vec<FlatTransaction> = flatten_transactions(&a).collect();
+        assert!(convert_flat(&mut rows[0], &a, &fx).is_ok());
+        assert_eq!(rows[0].converted_amount, Some(Decimal::new(20, 0)));
+        assert_eq!(rows[0].converted_currency, Some(usd.clone()));
+        assert!(matches!(convert_flat(&mut rows[1], &a, &fx), Err(TransformError::Fx(_))));
+        assert_eq!(rows[1].converted_amount, None);
+
+        rows[1].transaction_index = 2;
+        assert!(matches!(
+            convert_flat(&mut rows[1], &a, &fx),
+            Err(TransformError::NoSuchTransaction { index: 2, .. })
+        ));
+
+        let flat = flatten_activity(&a);
+        assert_eq!((flat.transactions, flat.budgets), (2, 0));
+    }
+}
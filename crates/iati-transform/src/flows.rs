@@ -0,0 +1,249 @@
+//! Flow views built on `TxType` metadata: net flow (inflows minus outflows)
+//! and commitments vs spend.
+
+use chrono::Datelike;
+use iati_types::{money::{CurrencyCode, MoneyError}, tx::FlowDirection, tx::TxType, Activity, RoundingPolicy};
+use rust_decimal::Decimal;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::{FxStrategy, TransformError};
+use iati_fx::DateContext;
+
+/// Funds received and paid out in a single currency.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct NetFlow {
+    /// Sum of `TxType::is_inflow` transactions (types 1, 5, 6, 9).
+    pub inflows: Decimal,
+    /// Sum of `TxType::is_outflow` transactions (types 3, 4, 7, 8).
+    pub outflows: Decimal,
+    /// `inflows - outflows`
+    pub net: Decimal,
+}
+
+impl NetFlow {
+    fn add(&mut self, tx_type: TxType, amount: Decimal) -> Result<(), MoneyError> {
+        let (inflows, outflows, net) = if tx_type.is_inflow() {
+            (self.inflows.checked_add(amount), Some(self.outflows), self.net.checked_add(amount))
+        } else if tx_type.is_outflow() {
+            (Some(self.inflows), self.outflows.checked_add(amount), self.net.checked_sub(amount))
+        } else {
+            return Ok(());
+        };
+        // Update nothing unless every figure fits.
+        let (Some(inflows), Some(outflows), Some(net)) = (inflows, outflows, net) else {
+            return Err(MoneyError::Overflow);
+        };
+        (self.inflows, self.outflows, self.net) = (inflows, outflows, net);
+        Ok(())
+    }
+
+    fn round(&mut self, policy: &RoundingPolicy, currency: &CurrencyCode) {
+        self.inflows = policy.round(self.inflows, Some(currency));
+        self.outflows = policy.round(self.outflows, Some(currency));
+        self.net = self.inflows - self.outflows;
+    }
+}
+
+/// Net flow in total and per calendar year, keyed by currency.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct NetFlows {
+    pub total: BTreeMap<CurrencyCode, NetFlow>,
+    /// year (of `transaction.date`) -> currency -> flow
+    pub by_year: BTreeMap<i32, BTreeMap<CurrencyCode, NetFlow>>,
+}
+
+impl NetFlows {
+    /// Round every figure with `policy`; `net` is recomputed from the rounded parts.
+    pub fn round(&mut self, policy: &RoundingPolicy) {
+        for by_cur in std::iter::once(&mut self.total).chain(self.by_year.values_mut()) {
+            by_cur.iter_mut().for_each(|(cur, flow)| flow.round(policy, cur));
+        }
+    }
+}
+
+/// Net flow across many activities. Commitments, pledges, guarantees and
+/// unknown transaction types are ignored; values without any currency are skipped.
+/// Errors with `TransformError::Money` if a figure overflows.
+pub fn net_flow(activities: &[Activity], fx: &FxStrategy<'_>) -> Result<NetFlows, TransformError> {
+    let mut out = NetFlows::default();
+    for act in activities {
+        for tx in act.transactions.iter().filter(|tx| tx.tx_type.is_flow()) {
+            let Some((amount, cur)) = fx.apply(act, &tx.value, &DateContext::for_transaction(act, tx))? else {
+                continue;
+            };
+            out.total.entry(cur.clone()).or_default().add(tx.tx_type, amount)?;
+            out.by_year
+                .entry(tx.date.year())
+                .or_default()
+                .entry(cur)
+                .or_default()
+                .add(tx.tx_type, amount)?;
+        }
+    }
+    Ok(out)
+}
+
+/// Outgoing commitments against what has been spent, in a single currency.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CommitmentsVsSpend {
+    /// Sum of outgoing commitments (type 2).
+    pub commitments: Decimal,
+    /// Sum of `TxType::is_spend` transactions (types 3, 4 and 7).
+    pub spend: Decimal,
+    /// `commitments - spend`
+    pub undisbursed: Decimal,
+    /// `spend / commitments`, or `None` when nothing was committed.
+    pub spend_rate: Option<Decimal>,
+}
+
+impl CommitmentsVsSpend {
+    fn finish(&mut self) -> Result<(), MoneyError> {
+        self.undisbursed = self.commitments.checked_sub(self.spend).ok_or(MoneyError::Overflow)?;
+        self.spend_rate = if self.commitments.is_zero() {
+            None
+        } else {
+            Some(self.spend.checked_div(self.commitments).ok_or(MoneyError::Overflow)?)
+        };
+        Ok(())
+    }
+
+    fn round(&mut self, policy: &RoundingPolicy, currency: &CurrencyCode) {
+        self.commitments = policy.round(self.commitments, Some(currency));
+        self.spend = policy.round(self.spend, Some(currency));
+        self.undisbursed = self.commitments - self.spend;
+    }
+}
+
+/// Commitments vs spend per activity and in total.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CommitmentsReport {
+    pub total: BTreeMap<CurrencyCode, CommitmentsVsSpend>,
+    /// iati-identifier -> currency -> figures
+    pub by_activity: BTreeMap<String, BTreeMap<CurrencyCode, CommitmentsVsSpend>>,
+}
+
+impl CommitmentsReport {
+    /// Round every amount with `policy`; `spend_rate` keeps full precision.
+    pub fn round(&mut self, policy: &RoundingPolicy) {
+        for by_cur in std::iter::once(&mut self.total).chain(self.by_activity.values_mut()) {
+            by_cur.iter_mut().for_each(|(cur, f)| f.round(policy, cur));
+        }
+    }
+}
+
+/// Compare outgoing commitments with spend across many activities.
+/// Errors with `TransformError::Money` if a figure overflows.
+pub fn commitments_vs_spend(activities: &[Activity], fx: &FxStrategy<'_>) -> Result<CommitmentsReport, TransformError> {
+    let mut out = CommitmentsReport::default();
+    for act in activities {
+        let per_activity = out.by_activity.entry(act.iati_identifier.clone()).or_default();
+        for tx in &act.transactions {
+            let t = tx.tx_type;
+            let is_commitment = t.is_commitment() && t.direction() == Some(FlowDirection::Outgoing);
+            if !is_commitment && !t.is_spend() {
+                continue;
+            }
+            let Some((amount, cur)) = fx.apply(act, &tx.value, &DateContext::for_transaction(act, tx))? else {
+                continue;
+            };
+            for figures in [out.total.entry(cur.clone()).or_default(), per_activity.entry(cur).or_default()] {
+                let total = if is_commitment { &mut figures.commitments } else { &mut figures.spend };
+                *total = total.checked_add(amount).ok_or(MoneyError::Overflow)?;
+            }
+        }
+    }
+    for figures in std::iter::once(&mut out.total).chain(out.by_activity.values_mut()) {
+        figures.values_mut().try_for_each(CommitmentsVsSpend::finish)?;
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use iati_types::{money::Money, tx::Transaction};
+
+    fn mk_activity(id: &str, txs: &[(TxType, i32, i64)]) -> Activity {
+        let mut a = Activity::new(id);
+        a.default_currency = Some(CurrencyCode::from("EUR"));
+        for &(t, year, amount) in txs {
+            let date = NaiveDate::from_ymd_opt(year, 6, 1).unwrap();
+            a.transactions.push(Transaction::new(t, date, Money::new(Decimal::new(amount, 0))));
+        }
+        a
+    }
+
+    #[test]
+    fn net_flow_by_year() {
+        let a = mk_activity("A1", &[
+            (TxType::IncomingFunds, 2023, 1000),
+            (TxType::OutgoingCommitment, 2023, 900),
+            (TxType::Disbursement, 2023, 400),
+            (TxType::Expenditure, 2024, 100),
+            (TxType::LoanRepayment, 2024, 50),
+            (TxType::OutgoingPledge, 2024, 5000),
+        ]);
+        let flows = net_flow(&[a], &FxStrategy::Native).unwrap();
+        let eur = CurrencyCode::from("EUR");
+
+        let total = &flows.total[&eur];
+        assert_eq!(total.inflows, Decimal::new(1050, 0));
+        assert_eq!(total.outflows, Decimal::new(500, 0));
+        assert_eq!(total.net, Decimal::new(550, 0));
+        assert_eq!(flows.by_year[&2023][&eur].net, Decimal::new(600, 0));
+        assert_eq!(flows.by_year[&2024][&eur].net, Decimal::new(-50, 0));
+
+        let a = mk_activity("A2", &[(TxType::IncomingFunds, 2023, 0)]);
+        let mut flows = net_flow(&[a], &FxStrategy::Native).unwrap();
+        flows.total.get_mut(&eur).unwrap().inflows = Decimal::new(10005, 3);
+        flows.round(&RoundingPolicy::default());
+        assert_eq!(flows.total[&eur].inflows, Decimal::new(1001, 2));
+        assert_eq!(flows.total[&eur].net, Decimal::new(1001, 2));
+    }
+
+    #[test]
+    fn commitments_vs_spend_per_activity() {
+        let a = mk_activity("A1", &[
+            (TxType::OutgoingCommitment, 2023, 800),
+            (TxType::Disbursement, 2023, 300),
+            (TxType::Reimbursement, 2023, 100),
+            (TxType::IncomingCommitment, 2023, 9999),
+        ]);
+        let b = mk_activity("B1", &[(TxType::Expenditure, 2023, 50)]);
+        let report = commitments_vs_spend(&[a, b], &FxStrategy::Native).unwrap();
+        let eur = CurrencyCode::from("EUR");
+
+        let a1 = &report.by_activity["A1"][&eur];
+        assert_eq!(a1.commitments, Decimal::new(800, 0));
+        assert_eq!(a1.spend, Decimal::new(400, 0));
+        assert_eq!(a1.undisbursed, Decimal::new(400, 0));
+        assert_eq!(a1.spend_rate, Some(Decimal::new(5, 1)));
+        assert_eq!(report.by_activity["B1"][&eur].spend_rate, None);
+        assert_eq!(report.total[&eur].spend, Decimal::new(450, 0));
+    }
+
+    #[test]
+    fn overflowing_totals_are_an_error() {
+        let mut a = mk_activity("A1", &[]);
+        for t in [TxType::Disbursement, TxType::Disbursement] {
+            let date = NaiveDate::from_ymd_opt(2023, 6, 1).unwrap();
+            a.transactions.push(Transaction::new(t, date, Money::new(Decimal::MAX)));
+        }
+        let acts = [a];
+        assert!(matches!(
+            net_flow(&acts, &FxStrategy::Native),
+            Err(TransformError::Money(MoneyError::Overflow))
+        ));
+        assert!(matches!(
+            commitments_vs_spend(&acts, &FxStrategy::Native),
+            Err(TransformError::Money(MoneyError::Overflow))
+        ));
+    }
+}
@@ -0,0 +1,248 @@
+//! Traceability graph across publishers.
+//!
+//! Funding chains are linked through `provider-org/@provider-activity-id` and
+//! `receiver-org/@receiver-activity-id` on transactions. A provider link on
+//! activity B pointing at A, or a receiver link on A pointing at B, both yield
+//! the directed edge `A -> B` (money flows from A to B).
+
+use iati_types::{tx::TxType, Activity};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+/// One declared funding link, as found on a transaction.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FundingLink {
+    /// Activity the money flows from.
+    pub funder: String,
+    /// Activity the money flows to.
+    pub recipient: String,
+    /// Activity whose transaction declared the link.
+    pub declared_by: String,
+    /// Index of the declaring transaction in `Activity::transactions`.
+    pub tx_index: usize,
+    pub tx_type: TxType,
+}
+
+/// A link pointing at an activity that is not part of the graph.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BrokenLink {
+    pub link: FundingLink,
+    /// The linked activity id that could not be found.
+    pub missing: String,
+}
+
+/// Directed funding graph over a set of activities, keyed by `iati_identifier`.
+#[derive(Debug, Clone, Default)]
+pub struct FundingGraph {
+    nodes: BTreeSet<String>,
+    links: Vec<FundingLink>,
+    broken: Vec<BrokenLink>,
+    /// funder -> recipients
+    out: BTreeMap<String, BTreeSet<String>>,
+    /// recipient -> funders
+    inc: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl FundingGraph {
+    /// Build the graph from the provider/receiver activity links of every transaction.
+    /// Links an activity makes to itself (e.g. its own id on a provider-org) are ignored.
+    pub fn build(activities: &[Activity]) -> Self {
+        let mut g = FundingGraph {
+            nodes: activities.iter().map(|a| a.iati_identifier.clone()).collect(),
+            ..Default::default()
+        };
+
+        for act in activities {
+            let own = &act.iati_identifier;
+            for (tx_index, tx) in act.transactions.iter().enumerate() {
+                let provider = tx.provider_org.as_ref().and_then(|o| o.activity_id.as_ref());
+                let receiver = tx.receiver_org.as_ref().and_then(|o| o.activity_id.as_ref());
+
+                if let Some(p) = provider.filter(|p| *p != own) {
+                    g.add(FundingLink {
+                        funder: p.clone(),
+                        recipient: own.clone(),
+                        declared_by: own.clone(),
+                        tx_index,
+                        tx_type: tx.tx_type,
+                    }, p);
+                }
+                if let Some(r) = receiver.filter(|r| *r != own) {
+                    g.add(FundingLink {
+                        funder: own.clone(),
+                        recipient: r.clone(),
+                        declared_by: own.clone(),
+                        tx_index,
+                        tx_type: tx.tx_type,
+                    }, r);
+                }
+            }
+        }
+        g
+    }
+
+    fn add(&mut self, link: FundingLink, other: &str) {
+        if !self.nodes.contains(other) {
+            self.broken.push(BrokenLink { link, missing: other.to_string() });
+            return;
+        }
+        self.out.entry(link.funder.clone()).or_default().insert(link.recipient.clone());
+        self.inc.entry(link.recipient.clone()).or_default().insert(link.funder.clone());
+        self.links.push(link);
+    }
+
+    /// Activity identifiers in the graph.
+    pub fn nodes(&self) -> impl Iterator<Item = &str> {
+        self.nodes.iter().map(String::as_str)
+    }
+
+    /// Links between activities that are both in the graph.
+    pub fn links(&self) -> &[FundingLink] {
+        &self.links
+    }
+
+    /// Links whose other end is not in the graph.
+    pub fn broken_links(&self) -> &[BrokenLink] {
+        &self.broken
+    }
+
+    /// Direct funders of `id`.
+    pub fn funders(&self, id: &str) -> BTreeSet<String> {
+        self.inc.get(id).cloned().unwrap_or_default()
+    }
+
+    /// Direct recipients of `id`.
+    pub fn recipients(&self, id: &str) -> BTreeSet<String> {
+        self.out.get(id).cloned().unwrap_or_default()
+    }
+
+    /// All activities `id` is (transitively) funded by, excluding `id` itself.
+    pub fn upstream(&self, id: &str) -> BTreeSet<String> {
+        let mut up = reach(&self.inc, id);
+        up.remove(id);
+        up
+    }
+
+    /// All activities (transitively) funded by `id`, excluding `id` itself.
+    pub fn downstream(&self, id: &str) -> BTreeSet<String> {
+        let mut down = reach(&self.out, id);
+        down.remove(id);
+        down
+    }
+
+    /// Funding cycles: groups of activities that (transitively) fund each other.
+    /// Each group is sorted; groups are returned in order of their first member.
+    pub fn cycles(&self) -> Vec<Vec<String>> {
+        let mut cycles = Vec::new();
+        let mut seen = BTreeSet::new();
+        for node in &self.nodes {
+            if seen.contains(node) {
+                continue;
+            }
+            // strongly connected component: nodes both reachable from and reaching `node`
+            let down = reach(&self.out, node);
+            if !down.contains(node) {
+                continue;
+            }
+            let up = reach(&self.inc, node);
+            let component: Vec<String> = down.intersection(&up).cloned().collect();
+            seen.extend(component.iter().cloned());
+            cycles.push(component);
+        }
+        cycles
+    }
+}
+
+/// Breadth-first reachability over an adjacency map. `start` is only included
+/// when it lies on a cycle.
+fn reach(adj: &BTreeMap<String, BTreeSet<String>>, start: &str) -> BTreeSet<String> {
+    let mut seen = BTreeSet::new();
+    let mut queue: VecDeque<&str> = VecDeque::from([start]);
+    while let Some(n) = queue.pop_front() {
+        for next in adj.get(n).into_iter().flatten() {
+            if seen.insert(next.clone()) {
+                queue.push_back(next);
+            }
+        }
+    }
+    seen
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use iati_types::{money::Money, tx::Transaction, OrgRef};
+    use rust_decimal::Decimal;
+
+    fn link(tx_type: TxType, provider: Option<&str>, receiver: Option<&str>) -> Transaction {
+        let org = |id: Option<&str>| OrgRef {
+            activity_id: id.map(String::from),
+            ..Default::default()
+        };
+        let mut tx = Transaction::new(
+            tx_type,
+            NaiveDate::from_ymd_opt(2023, 1, 1).unwrap(),
+            Money::new(Decimal::new(100, 0)),
+        );
+        tx.provider_org = Some(org(provider));
+        tx.receiver_org = Some(org(receiver));
+        tx
+    }
+
+    fn activity(id: &str, txs: Vec<Transaction>) -> Activity {
+        let mut a = Activity::new(id);
+        a.transactions = txs;
+        a
+    }
+
+    #[test]
+    fn traces_donor_to_delivery_partner() {
+        let acts = vec![
+            // donor declares its disbursement to the fund
+            activity("DONOR", vec![link(TxType::Disbursement, Some("DONOR"), Some("FUND"))]),
+            // fund declares incoming funds from donor and a disbursement to the NGO
+            activity("FUND", vec![
+                link(TxType::IncomingFunds, Some("DONOR"), None),
+                link(TxType::Disbursement, None, Some("NGO")),
+            ]),
+            activity("NGO", vec![link(TxType::Disbursement, None, Some("SUB-GRANTEE"))]),
+        ];
+        let g = FundingGraph::build(&acts);
+
+        assert_eq!(g.links().len(), 3);
+        assert_eq!(g.funders("FUND"), BTreeSet::from(["DONOR".to_string()]));
+        assert_eq!(
+            g.upstream("NGO"),
+            BTreeSet::from(["DONOR".to_string(), "FUND".to_string()])
+        );
+        assert_eq!(
+            g.downstream("DONOR"),
+            BTreeSet::from(["FUND".to_string(), "NGO".to_string()])
+        );
+        assert!(g.cycles().is_empty());
+
+        let broken = g.broken_links();
+        assert_eq!(broken.len(), 1);
+        assert_eq!(broken[0].missing, "SUB-GRANTEE");
+        assert_eq!(broken[0].link.declared_by, "NGO");
+    }
+
+    #[test]
+    fn detects_cycles() {
+        let acts = vec![
+            activity("A", vec![link(TxType::Disbursement, None, Some("B"))]),
+            activity("B", vec![link(TxType::Disbursement, None, Some("C"))]),
+            activity("C", vec![link(TxType::Disbursement, None, Some("A"))]),
+            activity("D", vec![link(TxType::IncomingFunds, Some("C"), None)]),
+        ];
+        let g = FundingGraph::build(&acts);
+
+        assert_eq!(g.cycles(), vec![vec!["A".to_string(), "B".to_string(), "C".to_string()]]);
+        assert!(!g.upstream("A").contains("A"));
+        assert!(g.downstream("A").contains("D"));
+    }
+}
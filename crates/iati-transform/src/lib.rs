@@ -1,46 +1,119 @@
 
 use chrono::Datelike;
-use iati_types::{money::CurrencyCode, tx::TxType, Activity};
+use iati_fx::{DateContext, DatePolicy, FxError, FxProvider};
+use iati_types::{money::{CurrencyCode, Money, MoneyBag, MoneyError}, tx::{Transaction, TxType}, Activity, RoundingPolicy};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 use thiserror::Error; 
 
+pub mod csv;
+pub mod dedup;
+pub mod execution;
+pub mod flatten;
+pub mod flows;
+pub mod graph;
+#[cfg(feature = "serde")]
+pub mod ndjson;
+
+pub use crate::csv::{CsvRow, CsvWriter};
+pub use crate::dedup::{deduplicate, find_double_counting, DedupAction, DedupOptions, DedupPolicy, DedupReport, DuplicatePair};
+pub use crate::execution::{activity_execution, budget_execution, ActivityExecution, ExecutionFigures, PeriodExecution};
+pub use crate::flatten::{convert_flat, flatten_activity, flatten_transactions, FlatActivity, FlatTransaction};
+pub use crate::flows::{commitments_vs_spend, net_flow, CommitmentsReport, CommitmentsVsSpend, NetFlow, NetFlows};
+pub use crate::graph::{BrokenLink, FundingGraph, FundingLink};
+#[cfg(feature = "serde")]
+pub use crate::ndjson::{write_activities, NdjsonError, NdjsonReader, NdjsonWriter};
+
 #[derive(Debug, Error)]
 pub enum TransformError {
     #[error("missing currency (no value currency and no activity default)")]
     MissingCurrency,
+    #[error("FX conversion failed: {0}")]
+    Fx(#[from] FxError),
+    #[error("money arithmetic failed: {0}")]
+    Money(#[from] MoneyError),
+    #[error("{iati_identifier} has no transaction {index}")]
+    NoSuchTransaction { iati_identifier: String, index: usize },
 }
 
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub enum FxCurrency {
-    /// Keep each transaction's native currency (value.currency or activity.default_currency).
+/// How values are brought into a common currency: kept native, or converted
+/// through an `FxProvider` (e.g. `iati_fx::FxTable`). Used by the aggregations,
+/// the execution report, the flow views and `convert_flat`.
+#[derive(Clone, Copy)]
+pub enum FxStrategy<'a> {
+    /// Keep each value's native currency (value.currency or activity.default_currency).
     Native,
-    /// Convert everything to target currency with a placeholder 1:1 rate for now.
-    /// (Real FX will be provided by a future iati-fx crate.)
-    Fixed { target: CurrencyCode },
+    /// Convert every value to `target` using `provider`, at the date chosen by `dates`.
+    Convert {
+        target: &'a CurrencyCode,
+        provider: &'a dyn FxProvider,
+        dates: &'a DatePolicy,
+    },
+}
+
+static VALUE_DATE_FIRST: DatePolicy = DatePolicy::ValueDateFirst;
+
+impl<'a> FxStrategy<'a> {
+    /// Convert to `target` with the default date policy: the value-date when
+    /// present, otherwise the transaction date (or the budget period start).
+    pub fn convert(target: &'a CurrencyCode, provider: &'a dyn FxProvider) -> Self {
+        FxStrategy::Convert {
+            target,
+            provider,
+            dates: &VALUE_DATE_FIRST,
+        }
+    }
+
+    /// Resolve and (optionally) convert a value.
+    /// Returns `None` when the value has no currency and the activity no default.
+    pub(crate) fn apply(
+        &self,
+        act: &Activity,
+        money: &Money,
+        ctx: &DateContext,
+    ) -> Result<Option<(Decimal, CurrencyCode)>, TransformError> {
+        let Ok(src_cur) = resolve_currency(act, money.currency.clone()) else {
+            return Ok(None);
+        };
+        match self {
+            FxStrategy::Native => Ok(Some((money.amount, src_cur))),
+            FxStrategy::Convert { target, provider, dates } => {
+                let date = dates.resolve(ctx).ok_or(FxError::MissingDate)?;
+                let rate = provider.get_rate(&src_cur, target, date)?;
+                let amount = money.amount.checked_mul(rate).ok_or(MoneyError::Overflow)?;
+                Ok(Some((amount, (*target).clone())))
+            }
+        }
+    }
 }
 
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub struct ByTypeAndCurrency {
-    // TxType -> Currency -> Decimal
-    pub sums: BTreeMap<TxType, BTreeMap<CurrencyCode, Decimal>>,
+    /// TxType -> per-currency totals
+    pub sums: BTreeMap<TxType, MoneyBag>,
 }
 
 impl ByTypeAndCurrency {
-    pub fn add(&mut self, tx_type: TxType, currency: CurrencyCode, amount: Decimal) {
-        self.sums
-            .entry(tx_type)
-            .or_default()
-            .entry(currency)
-            .and_modify(|x| *x += amount)
-            .or_insert(amount);
+    pub fn add(&mut self, tx_type: TxType, currency: CurrencyCode, amount: Decimal) -> Result<(), MoneyError> {
+        self.sums.entry(tx_type).or_default().add(currency, amount)
     }
 
     pub fn total_for(&self, tx_type: TxType, currency: &CurrencyCode) -> Option<Decimal> {
-        self.sums.get(&tx_type)?.get(currency).cloned()
+        self.sums.get(&tx_type)?.get(currency)
+    }
+
+    /// Round every total with `policy`, e.g. after converting to one currency.
+    pub fn round(&mut self, policy: &RoundingPolicy) {
+        for bag in self.sums.values_mut() {
+            *bag = bag.rounded(policy);
+        }
+    }
+
+    /// All currency totals for one type, e.g. for `iati_fx::convert_bag`.
+    pub fn totals(&self, tx_type: TxType) -> MoneyBag {
+        self.sums.get(&tx_type).cloned().unwrap_or_default()
     }
 }
 
@@ -52,79 +125,72 @@ fn resolve_currency(act: &Activity, currency: Option<CurrencyCode>) -> Result<Cu
     }
 }
 
-/// Apply FX strategy. For now only Native or Fixed{target} with a 1:1 rate.
-/// (A future iati-fx crate will supply actual FX conversions.)
-fn apply_fx(_value_date: Option<chrono::NaiveDate>, amount: Decimal, from: &CurrencyCode, fx: &FxCurrency)
-    -> (Decimal, CurrencyCode)
-{
-    match fx {
-        FxCurrency::Native => (amount, from.clone()),
-        FxCurrency::Fixed { target } => {
-            // placeholder: 1:1 rate; swap to target currency
-            (amount, target.clone())
-        }
+/// Add each transaction of `act` to `totals[key(tx)]`, in its resolved currency
+/// (value.currency -> act.default_currency) or converted by `fx`; transactions
+/// with neither currency are skipped. Stops at the first FX failure or overflow.
+pub fn accumulate<K: Ord>(
+    totals: &mut BTreeMap<K, MoneyBag>,
+    act: &Activity,
+    fx: &FxStrategy<'_>,
+    mut key: impl FnMut(&Transaction) -> K,
+) -> Result<(), TransformError> {
+    for tx in &act.transactions {
+        let Some((amt, cur)) = fx.apply(act, &tx.value, &DateContext::for_transaction(act, tx))? else {
+            continue; // skip transactions without any currency info
+        };
+        totals.entry(key(tx)).or_default().add(cur, amt)?;
     }
+    Ok(())
 }
 
 /// Aggregate sums by TxType and Currency across many activities.
-/// - Currency resolution: value.currency -> act.default_currency -> error.
-/// - FX: Native (no conversion) or Fixed{target} (placeholder 1:1).
-pub fn aggregate_by_type(activities: &[Activity], fx: FxCurrency) -> ByTypeAndCurrency {
+/// - Currency resolution: value.currency -> act.default_currency -> skipped.
+/// - FX: `FxStrategy::Native` (no conversion) or `FxStrategy::Convert`.
+/// - Errors with `TransformError::Fx` if a value cannot be converted and
+///   `TransformError::Money` if a total overflows.
+pub fn aggregate_by_type(activities: &[Activity], fx: &FxStrategy<'_>) -> Result<ByTypeAndCurrency, TransformError> {
     let mut out = ByTypeAndCurrency::default();
-
     for act in activities {
-        for tx in &act.transactions {
-            // resolve currency
-            let src_cur = match resolve_currency(act, tx.value.currency.clone()) {
-                Ok(c) => c,
-                Err(_) => continue, // skip transactions without any currency info
-            };
-
-            let (amt, cur) = apply_fx(tx.value.value_date, tx.value.amount, &src_cur, &fx);
-            out.add(tx.tx_type, cur, amt);
-        }
+        accumulate(&mut out.sums, act, fx, |tx| tx.tx_type)?;
     }
-
-    out
+    Ok(out)
 }
 
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub struct ByYearTypeAndCurrency {
-    /// year -> TxType -> Currency -> Decimal
-    pub sums: BTreeMap<i32, BTreeMap<TxType, BTreeMap<CurrencyCode, Decimal>>>,
+    /// year -> TxType -> per-currency totals
+    pub sums: BTreeMap<i32, BTreeMap<TxType, MoneyBag>>,
 }
 
 impl ByYearTypeAndCurrency {
-    pub fn add(&mut self, year: i32, tx_type: TxType, currency: CurrencyCode, amount: Decimal) {
-        self.sums
-            .entry(year)
-            .or_default()
-            .entry(tx_type)
-            .or_default()
-            .entry(currency)
-            .and_modify(|x| *x += amount)
-            .or_insert(amount);
+    pub fn add(&mut self, year: i32, tx_type: TxType, currency: CurrencyCode, amount: Decimal) -> Result<(), MoneyError> {
+        self.sums.entry(year).or_default().entry(tx_type).or_default().add(currency, amount)
     }
-}
 
-/// Aggregate by (year, type, currency). Uses `transaction.date.year()`.
-pub fn aggregate_by_year_and_type(activities: &[Activity], fx: FxCurrency) -> ByYearTypeAndCurrency {
-    let mut out = ByYearTypeAndCurrency::default();
+    pub fn total_for(&self, year: i32, tx_type: TxType, currency: &CurrencyCode) -> Option<Decimal> {
+        self.sums.get(&year)?.get(&tx_type)?.get(currency)
+    }
 
-    for act in activities {
-        for tx in &act.transactions {
-            let year = tx.date.year();
-            let src_cur = match resolve_currency(act, tx.value.currency.clone()) {
-                Ok(c) => c,
-                Err(_) => continue,
-            };
-            let (amt, cur) = apply_fx(tx.value.value_date, tx.value.amount, &src_cur, &fx);
-            out.add(year, tx.tx_type, cur, amt);
+    /// Round every total with `policy`.
+    pub fn round(&mut self, policy: &RoundingPolicy) {
+        for bag in self.sums.values_mut().flat_map(|by_type| by_type.values_mut()) {
+            *bag = bag.rounded(policy);
         }
     }
+}
 
-    out
+/// Aggregate by (year, type, currency). Uses `transaction.date.year()`.
+pub fn aggregate_by_year_and_type(activities: &[Activity], fx: &FxStrategy<'_>) -> Result<ByYearTypeAndCurrency, TransformError> {
+    let mut totals = BTreeMap::new();
+    for act in activities {
+        accumulate(&mut totals, act, fx, |tx| (tx.date.year(), tx.tx_type))?;
+    }
+    let mut out = ByYearTypeAndCurrency::default();
+    for ((year, tx_type), bag) in totals {
+        out.sums.entry(year).or_default().insert(tx_type, bag);
+    }
+    Ok(out)
 }
 
 #[cfg(test)]
@@ -137,7 +203,7 @@ mod tests {
     fn mk_money(amount_cents: i64, currency: Option<&str>) -> Money {
         Money {
             amount: Decimal::new(amount_cents, 2),
-            currency: currency.map(|c| CurrencyCode::from(c)),
+            currency: currency.map(CurrencyCode::from),
             value_date: None,
         }
     }
@@ -163,7 +229,7 @@ mod tests {
             mk_money(700, Some("USD")), // 7.00 USD
         ));
 
-        let sums = aggregate_by_type(&[a], FxCurrency::Native);
+        let sums = aggregate_by_type(&[a], &FxStrategy::Native).unwrap();
         assert_eq!(
             sums.total_for(TxType::Disbursement, &CurrencyCode::from("USD")).unwrap(),
             Decimal::new(1000, 2)
@@ -176,10 +242,12 @@ mod tests {
             sums.total_for(TxType::OutgoingCommitment, &CurrencyCode::from("USD")).unwrap(),
             Decimal::new(700, 2)
         );
+        assert_eq!(sums.totals(TxType::Disbursement).len(), 2);
+        assert!(sums.totals(TxType::Expenditure).is_empty());
     }
 
     #[test]
-    fn sum_by_year_and_type_fixed_target() {
+    fn sum_by_year_and_type_converted_target() {
         let mut a = Activity::new("A1");
         a.default_currency = Some(CurrencyCode::from("USD"));
 
@@ -191,10 +259,19 @@ mod tests {
         a.transactions.push(Transaction::new(
             TxType::Disbursement,
             NaiveDate::from_ymd_opt(2024, 3, 1).unwrap(),
-            mk_money(500, Some("EUR")), // 5.00 EUR -> Fixed target GBP (1:1)
+            mk_money(500, Some("EUR")), // 5.00 EUR -> GBP (1:1)
         ));
 
-        let sums = aggregate_by_year_and_type(&[a], FxCurrency::Fixed { target: CurrencyCode::from("GBP") });
+        // every currency at 1 per USD, so amounts carry over unchanged
+        let mut table = iati_fx::FxTable::new();
+        for (year, month) in [(2023, 1), (2024, 3)] {
+            for code in ["USD", "EUR", "GBP"] {
+                let ym = iati_fx::YearMonth { year, month };
+                table.insert(CurrencyCode::from(code), ym, Decimal::ONE).unwrap();
+            }
+        }
+        let gbp = CurrencyCode::from("GBP");
+        let sums = aggregate_by_year_and_type(&[a], &FxStrategy::convert(&gbp, &table)).unwrap();
         use rust_decimal::prelude::ToPrimitive;
         // 2023: 10.00 -> GBP
         assert_eq!(
@@ -211,4 +288,19 @@ mod tests {
             5.00_f64
         );
     }
+
+    #[test]
+    fn aggregate_reports_overflow() {
+        let mut a = Activity::new("A1");
+        a.default_currency = Some(CurrencyCode::from("USD"));
+        for _ in 0..2 {
+            a.transactions.push(Transaction::new(
+                TxType::Disbursement,
+                NaiveDate::from_ymd_opt(2023, 1, 10).unwrap(),
+                Money::new(Decimal::MAX),
+            ));
+        }
+        assert!(matches!(aggregate_by_type(&[a.clone()], &FxStrategy::Native), Err(TransformError::Money(_))));
+        assert!(matches!(aggregate_by_year_and_type(&[a], &FxStrategy::Native), Err(TransformError::Money(_))));
+    }
 }
@@ -0,0 +1,161 @@
+//! NDJSON (JSON Lines) output and input: one record per line, written and
+//! read incrementally, so large files never need to be held in memory.
+
+use std::io::{BufRead, Write};
+use std::marker::PhantomData;
+
+use iati_types::Activity;
+use serde::{de::DeserializeOwned, Serialize};
+use thiserror::Error;
+
+use crate::flatten::flatten_transactions;
+
+#[derive(Debug, Error)]
+pub enum NdjsonError {
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("line {line}: {source}")]
+    Json { line: usize, source: serde_json::Error },
+}
+
+/// Writes one JSON record per line.
+pub struct NdjsonWriter<W: Write> {
+    out: W,
+    written: usize,
+}
+
+impl<W: Write> NdjsonWriter<W> {
+    pub fn new(out: W) -> Self {
+        NdjsonWriter { out, written: 0 }
+    }
+
+    pub fn write<T: Serialize + ?Sized>(&mut self, record: &T) -> Result<(), NdjsonError> {
+        serde_json::to_writer(&mut self.out, record).map_err(|e| NdjsonError::Json { line: self.written + 1, source: e })?;
+        self.out.write_all(b"\n")?;
+        self.written += 1;
+        Ok(())
+    }
+
+    /// Write one line per transaction of `activity` (see [`FlatTransaction`](crate::FlatTransaction)).
+    pub fn write_flat_transactions(&mut self, activity: &Activity) -> Result<(), NdjsonError> {
+        for row in flatten_transactions(activity) {
+            self.write(&row)?;
+        }
+        Ok(())
+    }
+
+    /// Lines written so far.
+    pub fn written(&self) -> usize {
+        self.written
+    }
+
+    /// Flush and return the underlying writer.
+    pub fn into_inner(mut self) -> Result<W, NdjsonError> {
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+/// Reads one JSON record per line; blank lines are skipped.
+///
+/// ```
+/// use iati_transform::NdjsonReader;
+/// use iati_types::Activity;
+///
+/// let input = "{\"iati_identifier\":\"XM-1\",\"default_currency\":null,\"transactions\":[]}\n";
+/// let activities: Vec<Activity> = NdjsonReader::new(input.as_bytes()).collect::<Result<_, _>>().unwrap();
+/// assert_eq!(activities[0].iati_identifier, "XM-1");
+/// ```
+pub struct NdjsonReader<R, T = Activity> {
+    lines: std::io::Lines<R>,
+    line: usize,
+    _record: PhantomData<fn() -> T>,
+}
+
+impl<R: BufRead, T: DeserializeOwned> NdjsonReader<R, T> {
+    pub fn new(source: R) -> Self {
+        NdjsonReader {
+            lines: source.lines(),
+            line: 0,
+            _record: PhantomData,
+        }
+    }
+}
+
+impl<R: BufRead, T: DeserializeOwned> Iterator for NdjsonReader<R, T> {
+    type Item = Result<T, NdjsonError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = match self.lines.next()? {
+                Ok(l) => l,
+                Err(e) => return Some(Err(e.into())),
+            };
+            self.line += 1;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let line_no = self.line;
+            return Some(serde_json::from_str(&line).map_err(|e| NdjsonError::Json { line: line_no, source: e }));
+        }
+    }
+}
+
+/// Write `activities` as NDJSON, one activity per line. Returns the number written.
+pub fn write_activities<'a, W: Write>(
+    out: W,
+    activities: impl IntoIterator<Item = &'a Activity>,
+) -> Result<usize, NdjsonError> {
+    let mut writer = NdjsonWriter::new(out);
+    for activity in activities {
+        writer.write(activity)?;
+    }
+    let written = writer.written();
+    writer.into_inner()?;
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FlatTransaction;
+    use chrono::NaiveDate;
+    use iati_types::{money::Money, tx::Transaction, TxType};
+    use rust_decimal::Decimal;
+
+    fn activity(id: &str) -> Activity {
+        let mut a = Activity::new(id);
+        let date = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        a.transactions.push(Transaction::new(TxType::Disbursement, date, Money::new(Decimal::new(125, 1))));
+        a.transactions.push(Transaction::new(TxType::Expenditure, date, Money::new(Decimal::ONE)));
+        a
+    }
+
+    #[test]
+    fn activities_round_trip_one_per_line() {
+        let acts = vec![activity("A1"), activity("A2")];
+        let mut buf = Vec::new();
+        assert_eq!(write_activities(&mut buf, &acts).unwrap(), 2);
+        let text = String::from_utf8(buf).unwrap();
+        assert_eq!(text.lines().count(), 2);
+
+        let with_blank = text.replacen('\n', "\n\n", 1);
+        let back: Vec<Activity> = NdjsonReader::new(with_blank.as_bytes()).collect::<Result<_, _>>().unwrap();
+        assert_eq!(back, acts);
+
+        let bad = format!("{text}{{not json\n");
+        let results: Vec<Result<Activity, _>> = NdjsonReader::new(bad.as_bytes()).collect();
+        assert!(matches!(results[2], Err(NdjsonError::Json { line: 3, .. })));
+    }
+
+    #[test]
+    fn flat_transactions_one_per_line() {
+        let mut writer = NdjsonWriter::new(Vec::new());
+        writer.write_flat_transactions(&activity("A1")).unwrap();
+        let out = writer.into_inner().unwrap();
+        let rows: Vec<FlatTransaction> = NdjsonReader::new(out.as_slice()).collect::<Result<_, _>>().unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1].tx_type, 4);
+        assert_eq!(rows[0].iati_identifier, "A1");
+    }
+}
@@ -0,0 +1,109 @@
+use crate::money::Money;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+/// IATI Budget Type (from budget/@type).
+/// See https://iatistandard.org/en/iati-standard/203/codelists/budgettype/
+/// The standard treats a missing attribute as 'Original'.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub enum BudgetType {
+    #[default]
+    Original, // 1
+    Revised,  // 2
+    Unknown(u16),
+}
+
+impl BudgetType {
+    /// Return the IATI code for this BudgetType.
+    pub fn code(self) -> u16 {
+        match self {
+            BudgetType::Original => 1,
+            BudgetType::Revised => 2,
+            BudgetType::Unknown(c) => c,
+        }
+    }
+}
+
+impl From<u16> for BudgetType {
+    fn from(code: u16) -> Self {
+        match code {
+            1 => BudgetType::Original,
+            2 => BudgetType::Revised,
+            c => BudgetType::Unknown(c),
+        }
+    }
+}
+
+/// IATI Budget Status (from budget/@status).
+/// See https://iatistandard.org/en/iati-standard/203/codelists/budgetstatus/
+/// The standard treats a missing attribute as 'Indicative'.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub enum BudgetStatus {
+    #[default]
+    Indicative, // 1
+    Committed,  // 2
+    Unknown(u16),
+}
+
+impl BudgetStatus {
+    /// Return the IATI code for this BudgetStatus.
+    pub fn code(self) -> u16 {
+        match self {
+            BudgetStatus::Indicative => 1,
+            BudgetStatus::Committed => 2,
+            BudgetStatus::Unknown(c) => c,
+        }
+    }
+}
+
+impl From<u16> for BudgetStatus {
+    fn from(code: u16) -> Self {
+        match code {
+            1 => BudgetStatus::Indicative,
+            2 => BudgetStatus::Committed,
+            c => BudgetStatus::Unknown(c),
+        }
+    }
+}
+
+/// Activity budget for a single period (the 'budget' element).
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Budget {
+    pub budget_type: BudgetType, // from budget/@type
+    pub status: BudgetStatus,    // from budget/@status
+    pub period_start: NaiveDate, // from 'period-start/@iso-date'
+    pub period_end: NaiveDate,   // from 'period-end/@iso-date'
+    pub value: Money,            // from <value> body + attributes
+}
+
+impl Budget {
+    pub fn new(period_start: NaiveDate, period_end: NaiveDate, value: Money) -> Self {
+        Self {
+            budget_type: BudgetType::Original,
+            status: BudgetStatus::Indicative,
+            period_start,
+            period_end,
+            value,
+        }
+    }
+
+    /// Set the budget type (builder-style).
+    pub fn with_type(mut self, budget_type: BudgetType) -> Self {
+        self.budget_type = budget_type;
+        self
+    }
+
+    /// Set the budget status (builder-style).
+    pub fn with_status(mut self, status: BudgetStatus) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// True if `date` falls within the budget period (both ends inclusive).
+    pub fn covers(&self, date: NaiveDate) -> bool {
+        self.period_start <= date && date <= self.period_end
+    }
+}
@@ -0,0 +1,270 @@
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Characters the IATI rulesets forbid anywhere in an identifier.
+const FORBIDDEN: [char; 4] = ['/', '&', '|', '?'];
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum IdentifierError {
+    #[error("identifier is empty")]
+    Empty,
+    #[error("identifier contains whitespace at byte {0}")]
+    Whitespace(usize),
+    #[error("identifier contains forbidden character {ch:?} at byte {pos}")]
+    InvalidChar { ch: char, pos: usize },
+    #[error("identifier {0:?} has no activity part after the organisation identifier")]
+    MissingActivitySuffix(String),
+    #[error("identifier {identifier:?} has no '-' after the organisation identifier {org_identifier:?}")]
+    MissingSeparator {
+        identifier: String,
+        org_identifier: String,
+    },
+    #[error("identifier {identifier:?} does not start with any of {expected:?}")]
+    PrefixMismatch {
+        identifier: String,
+        /// The reporting-org ref and declared B1 other-identifiers that were tried.
+        expected: Vec<String>,
+    },
+}
+
+/// 'other-identifier' element of an Activity.
+/// See https://iatistandard.org/en/iati-standard/203/codelists/otheridentifiertype/
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct OtherIdentifier {
+    /// from '@ref'
+    pub ref_id: String,
+    /// OtherIdentifierType code from '@type', e.g. "A1" or "B1".
+    pub type_code: Option<String>,
+}
+
+impl OtherIdentifier {
+    /// Type B1: previous reporting organisation identifier, an allowed activity-identifier prefix.
+    pub fn is_previous_reporting_org(&self) -> bool {
+        self.type_code.as_deref() == Some("B1")
+    }
+}
+
+/// Validated `iati-identifier`: an organisation identifier (registration agency
+/// prefix + organisation number) followed by the publisher's activity suffix.
+///
+/// `GB-COH-123456-PROJ-1` splits into agency `GB-COH`, number `123456` and
+/// suffix `PROJ-1`. When the organisation identifier is known (e.g. from
+/// `reporting-org/@ref`), use [`IatiIdentifier::with_org_prefix`] for an exact split.
+/// Serialises as the plain string; deserialising re-splits it with [`IatiIdentifier::parse`].
+///
+/// Equality, hashing and ordering use the identifier string only, so a value
+/// still equals itself after a round trip that splits it differently.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "String", into = "String"))]
+#[derive(Debug, Clone)]
+pub struct IatiIdentifier {
+    raw: String,
+    /// End of the registration agency prefix, if one was recognised.
+    agency_end: Option<usize>,
+    /// End of the organisation identifier.
+    org_end: usize,
+    /// Start of the activity suffix.
+    suffix_start: usize,
+}
+
+impl PartialEq for IatiIdentifier {
+    fn eq(&self, other: &Self) -> bool {
+        self.raw == other.raw
+    }
+}
+
+impl Eq for IatiIdentifier {}
+
+impl Hash for IatiIdentifier {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.raw.hash(state);
+    }
+}
+
+impl PartialOrd for IatiIdentifier {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for IatiIdentifier {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.raw.cmp(&other.raw)
+    }
+}
+
+fn check_chars(s: &str) -> Result<(), IdentifierError> {
+    if s.is_empty() {
+        return Err(IdentifierError::Empty);
+    }
+    for (pos, ch) in s.char_indices() {
+        if ch.is_whitespace() {
+            return Err(IdentifierError::Whitespace(pos));
+        }
+        if FORBIDDEN.contains(&ch) || ch.is_control() {
+            return Err(IdentifierError::InvalidChar { ch, pos });
+        }
+    }
+    Ok(())
+}
+
+/// Agency prefixes are a country (or XM/XI) code plus a list code, e.g. `GB-COH`.
+/// Returns the end of the agency prefix when `org` has one followed by a number.
+fn agency_end(org: &str) -> Option<usize> {
+    let mut parts = org.splitn(3, '-');
+    let country = parts.next()?;
+    let list = parts.next()?;
+    let number = parts.next()?;
+    let is_country = country.len() == 2 && country.chars().all(|c| c.is_ascii_uppercase());
+    (is_country && !list.is_empty() && !number.is_empty()).then(|| country.len() + 1 + list.len())
+}
+
+impl IatiIdentifier {
+    /// Validate and split without knowing the organisation identifier.
+    /// Recognises `CC-LIST-NUMBER-suffix`, and `NUMBER-suffix` otherwise.
+    pub fn parse(s: &str) -> Result<Self, IdentifierError> {
+        check_chars(s)?;
+        let segments: Vec<&str> = s.split('-').collect();
+        let org_segments = match agency_end(s) {
+            Some(_) => 3,
+            None => 1,
+        };
+        if segments.len() <= org_segments || segments[org_segments..].concat().is_empty() {
+            return Err(IdentifierError::MissingActivitySuffix(s.to_string()));
+        }
+        let org_end = segments[..org_segments].join("-").len();
+        Ok(IatiIdentifier {
+            raw: s.to_string(),
+            agency_end: agency_end(&s[..org_end]),
+            org_end,
+            suffix_start: org_end + 1,
+        })
+    }
+
+    /// Validate and split at a known organisation identifier, which must be a
+    /// prefix of `s` followed by `-`.
+    pub fn with_org_prefix(s: &str, org_identifier: &str) -> Result<Self, IdentifierError> {
+        check_chars(s)?;
+        if org_identifier.is_empty() || !s.starts_with(org_identifier) {
+            return Err(IdentifierError::PrefixMismatch {
+                identifier: s.to_string(),
+                expected: vec![org_identifier.to_string()],
+            });
+        }
+        let org_end = org_identifier.len();
+        let suffix_start = org_end + 1;
+        if s.len() == org_end {
+            return Err(IdentifierError::MissingActivitySuffix(s.to_string()));
+        }
+        // `GB-COH-1234` must not match `GB-COH-12345-X` halfway through an element.
+        if s.as_bytes()[org_end] != b'-' {
+            return Err(IdentifierError::MissingSeparator {
+                identifier: s.to_string(),
+                org_identifier: org_identifier.to_string(),
+            });
+        }
+        if suffix_start == s.len() {
+            return Err(IdentifierError::MissingActivitySuffix(s.to_string()));
+        }
+        Ok(IatiIdentifier {
+            raw: s.to_string(),
+            agency_end: agency_end(org_identifier),
+            org_end,
+            suffix_start,
+        })
+    }
+
+    /// Validate against the reporting-org ref and any type B1 other-identifiers,
+    /// splitting at whichever one prefixes `s`.
+    pub fn for_activity(
+        s: &str,
+        reporting_org_ref: Option<&str>,
+        other_identifiers: &[OtherIdentifier],
+    ) -> Result<Self, IdentifierError> {
+        check_chars(s)?;
+        let expected: Vec<String> = reporting_org_ref
+            .into_iter()
+            .map(str::trim)
+            .chain(
+                other_identifiers
+                    .iter()
+                    .filter(|o| o.is_previous_reporting_org())
+                    .map(|o| o.ref_id.trim()),
+            )
+            .filter(|p| !p.is_empty())
+            .map(String::from)
+            .collect();
+
+        // Prefer a prefix ending at a '-', then the longest, so `GB-COH-1` does not
+        // shadow `GB-COH-12`; a prefix without the separator is reported as such.
+        let best = expected
+            .iter()
+            .filter(|p| s.starts_with(p.as_str()))
+            .max_by_key(|p| (s.as_bytes().get(p.len()) == Some(&b'-'), p.len()));
+        match best {
+            Some(prefix) => Self::with_org_prefix(s, prefix),
+            None => Err(IdentifierError::PrefixMismatch {
+                identifier: s.to_string(),
+                expected,
+            }),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.raw
+    }
+
+    /// Organisation identifier prefix, e.g. `GB-COH-123456`.
+    pub fn org_identifier(&self) -> &str {
+        &self.raw[..self.org_end]
+    }
+
+    /// Registration agency prefix, e.g. `GB-COH`, if recognised.
+    pub fn registration_agency(&self) -> Option<&str> {
+        self.agency_end.map(|end| &self.raw[..end])
+    }
+
+    /// Organisation number within the registration agency, e.g. `123456`.
+    pub fn org_number(&self) -> &str {
+        match self.agency_end {
+            Some(end) => &self.raw[end + 1..self.org_end],
+            None => self.org_identifier(),
+        }
+    }
+
+    /// Publisher-assigned activity part after the organisation identifier.
+    pub fn activity_suffix(&self) -> &str {
+        &self.raw[self.suffix_start..]
+    }
+}
+
+impl fmt::Display for IatiIdentifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.raw)
+    }
+}
+
+impl FromStr for IatiIdentifier {
+    type Err = IdentifierError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl TryFrom<String> for IatiIdentifier {
+    type Error = IdentifierError;
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        Self::parse(&s)
+    }
+}
+
+impl From<IatiIdentifier> for String {
+    fn from(id: IatiIdentifier) -> Self {
+        id.raw
+    }
+}
@@ -1,15 +1,24 @@
-//! iati-types: strongly-typed, IO-free core models for IATI Activity v2.03.
+//! iati-types: strongly-typed, IO-free core models for IATI Activity and Organisation v2.03.
 //!
 //! Other downstream crates (e.g. 'iati-xml', 'iati-transform') can provide parsing, serialization,
 //! validation, and codelist lookups.
 
+pub mod budget;
+pub mod identifier;
 pub mod money;
+pub mod organisation;
+pub mod rounding;
 pub mod tx;
 
-pub use money::{CurrencyCode, Money};
-pub use tx::{Transaction, TxType};
+pub use budget::{Budget, BudgetStatus, BudgetType};
+pub use identifier::{IatiIdentifier, IdentifierError, OtherIdentifier};
+pub use money::{CurrencyCode, Money, MoneyBag, MoneyError};
+pub use rounding::{RoundingMode, RoundingPolicy, RoundingScale};
+pub use organisation::{DocumentLink, OrgBudget, Organisation, RecipientCountryBudget, RecipientOrgBudget};
+pub use tx::{FlowDirection, Transaction, TxType};
 
 use chrono::NaiveDate;
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 
 /// Lightweight organisation reference used here through the Activity tree.
@@ -20,6 +29,22 @@ pub struct OrgRef {
     pub ref_id: Option<String>,
     /// Display name (narrative text, typically first/default language).
     pub name: Option<String>,
+    /// Linked activity identifier, from 'provider-org/@provider-activity-id'
+    /// or 'receiver-org/@receiver-activity-id' on transactions.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub activity_id: Option<String>,
+}
+
+/// Activity-level 'sector' element.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Sector {
+    /// from '@code'
+    pub code: String,
+    /// SectorVocabulary code from '@vocabulary'; 'None' means the default (1, OECD DAC 5-digit).
+    pub vocabulary: Option<String>,
+    /// from '@percentage'
+    pub percentage: Option<Decimal>,
 }
 
 /// IATI Activity (trimmed to basic fields for foundational fields of the Activity struct here).
@@ -33,11 +58,20 @@ pub struct Activity {
     pub default_currency: Option<CurrencyCode>,
     /// Transactions recorded for this activity.
     pub transactions: Vec<Transaction>,
+    /// Budgets recorded for this activity, one per 'budget' element.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub budgets: Vec<Budget>,
     /// Reporting organisation publishing this Activity.
     pub reporting_org: Option<OrgRef>,
     /// Activity start/end dates from 'activity-date' element.
     pub activity_start: Option<NaiveDate>,
     pub activity_end: Option<NaiveDate>,
+    /// 'other-identifier' elements; type B1 entries may prefix the iati-identifier.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub other_identifiers: Vec<OtherIdentifier>,
+    /// Activity-level 'sector' elements.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub sectors: Vec<Sector>,
 }
 
 impl Activity {
@@ -46,11 +80,21 @@ impl Activity {
             iati_identifier: iati_identifier.into(),
             default_currency: None,
             transactions: Vec::new(),
+            budgets: Vec::new(),
             reporting_org: None,
             activity_start: None,
             activity_end: None,
+            other_identifiers: Vec::new(),
+            sectors: Vec::new(),
         }
     }
+
+    /// Parse and validate `iati_identifier`: allowed characters, and a prefix of
+    /// either 'reporting-org/@ref' or a declared type B1 'other-identifier'.
+    pub fn identifier(&self) -> Result<IatiIdentifier, IdentifierError> {
+        let reporting = self.reporting_org.as_ref().and_then(|o| o.ref_id.as_deref());
+        IatiIdentifier::for_activity(&self.iati_identifier, reporting, &self.other_identifiers)
+    }
 }
 
 #[cfg(test)]
@@ -67,6 +111,49 @@ mod tests {
         assert_eq!("3".parse::<TxType>().unwrap().code(), 3);
     }
 
+    #[test]
+    fn tx_type_from_v1_code() {
+        let codes = [
+            ("IF", TxType::IncomingFunds),
+            ("C", TxType::OutgoingCommitment),
+            ("D", TxType::Disbursement),
+            ("E", TxType::Expenditure),
+            ("IR", TxType::InterestPayment),
+            ("LR", TxType::LoanRepayment),
+            ("R", TxType::Reimbursement),
+            ("QP", TxType::PurchaseOfEquity),
+            ("QS", TxType::SaleOfEquity),
+            ("CG", TxType::CreditGuarantee),
+        ];
+        for (code, tx_type) in codes {
+            assert_eq!(TxType::from_v1_code(code), Some(tx_type));
+        }
+        assert_eq!(TxType::from_v1_code(" D "), Some(TxType::Disbursement));
+        assert_eq!(TxType::from_v1_code("d"), None);
+        assert_eq!(TxType::from_v1_code("3"), None);
+    }
+
+    #[test]
+    fn tx_type_metadata() {
+        use crate::tx::FlowDirection;
+
+        let outflows: Vec<u16> = (1..=13).filter(|c| TxType::from(*c).is_outflow()).collect();
+        let inflows: Vec<u16> = (1..=13).filter(|c| TxType::from(*c).is_inflow()).collect();
+        assert_eq!(outflows, vec![3, 4, 7, 8]);
+        assert_eq!(inflows, vec![1, 5, 6, 9]);
+
+        assert!(TxType::IncomingCommitment.is_commitment());
+        assert_eq!(TxType::IncomingCommitment.direction(), Some(FlowDirection::Incoming));
+        assert!(TxType::OutgoingPledge.is_pledge() && !TxType::OutgoingPledge.is_flow());
+        assert!(TxType::Reimbursement.is_disbursement());
+        assert!(TxType::Expenditure.is_spend() && !TxType::Expenditure.is_disbursement());
+        assert_eq!(TxType::IncomingPledge.name(), "Incoming Pledge");
+
+        let unknown = TxType::Unknown(42);
+        assert_eq!(unknown.direction(), None);
+        assert!(!unknown.is_flow() && !unknown.is_commitment());
+    }
+
     #[test]
     fn money_uppercases_currency() {
         use crate::money::{CurrencyCode, Money};
@@ -78,6 +165,75 @@ mod tests {
         assert_eq!(m.currency.unwrap().0, "USD");
     }
 
+    #[test]
+    fn money_checked_arithmetic() {
+        use crate::money::{CurrencyCode, Money, MoneyError};
+
+        let eur = |cents: i64| Money::new(Decimal::new(cents, 2)).with_currency(CurrencyCode::from("EUR"));
+        let usd = Money::new(Decimal::ONE).with_currency(CurrencyCode::from("USD"));
+
+        assert_eq!(eur(150).checked_add(&eur(50)).unwrap().amount, Decimal::new(200, 2));
+        assert_eq!(eur(150).checked_sub(&eur(200)).unwrap().amount, Decimal::new(-50, 2));
+        assert_eq!(eur(1000).scale(Decimal::new(25, 2)).unwrap(), eur(250));
+        assert!(matches!(eur(1).checked_add(&usd), Err(MoneyError::CurrencyMismatch { .. })));
+        assert!(matches!(
+            eur(1).checked_add(&Money::new(Decimal::ONE)),
+            Err(MoneyError::CurrencyMismatch { right: None, .. })
+        ));
+        let max = Money::new(Decimal::MAX);
+        assert_eq!(max.checked_add(&max), Err(MoneyError::Overflow));
+
+        assert_eq!(Money::sum([&eur(100), &eur(200), &eur(300)]).unwrap(), Some(eur(600)));
+        assert_eq!(Money::sum(std::iter::empty()).unwrap(), None);
+        assert!(Money::sum([&eur(100), &usd]).is_err());
+    }
+
+    #[test]
+    fn rounding_policy_and_display() {
+        use crate::money::{CurrencyCode, Money};
+        use crate::rounding::{RoundingMode, RoundingPolicy, RoundingScale};
+
+        let m = |s: &str, c: &str| Money::new(s.parse().unwrap()).with_currency(CurrencyCode::from(c));
+        let half_up = RoundingPolicy::default();
+        let bankers = RoundingPolicy::new(RoundingMode::Bankers, RoundingScale::MinorUnit);
+
+        assert_eq!(m("2.345", "EUR").rounded(&half_up).amount, Decimal::new(235, 2));
+        assert_eq!(m("2.345", "EUR").rounded(&bankers).amount, Decimal::new(234, 2));
+        assert_eq!(m("1234.5", "JPY").rounded(&half_up).amount, Decimal::new(1235, 0));
+        assert_eq!(m("1.23456", "KWD").rounded(&half_up).amount, Decimal::new(1235, 3));
+        let scale1 = RoundingPolicy::new(RoundingMode::HalfUp, RoundingScale::Scale(1));
+        assert_eq!(scale1.round(Decimal::new(125, 2), None), Decimal::new(13, 1));
+
+        assert_eq!(m("1234.5", "EUR").to_string(), "1,234.50 EUR");
+        assert_eq!(m("-1234567", "JPY").to_string(), "-1,234,567 JPY");
+        assert_eq!(m("0.1234", "usd").to_string(), "0.1234 USD");
+        assert_eq!(format!("{:.1}", m("999.96", "EUR")), "1,000.0 EUR");
+        assert_eq!(Money::new(Decimal::new(12345, 1)).to_string(), "1,234.5");
+    }
+
+    #[test]
+    fn money_bag_accumulates_per_currency() {
+        use crate::money::{CurrencyCode, Money, MoneyBag, MoneyError};
+
+        let eur = CurrencyCode::from("EUR");
+        let mut bag = MoneyBag::new();
+        bag.add(eur.clone(), Decimal::new(10, 0)).unwrap();
+        bag.add_money(&Money::new(Decimal::new(5, 0)).with_currency(eur.clone())).unwrap();
+        assert_eq!(bag.single().unwrap().amount, Decimal::new(15, 0));
+        assert_eq!(bag.add_money(&Money::new(Decimal::ONE)), Err(MoneyError::MissingCurrency));
+
+        let other = MoneyBag::from(std::collections::BTreeMap::from([(CurrencyCode::from("usd"), Decimal::new(2, 0))]));
+        bag.merge(&other).unwrap();
+        assert_eq!(bag.len(), 2);
+        assert_eq!(bag.single(), None);
+        assert_eq!(bag.get(&CurrencyCode::from("USD")), Some(Decimal::new(2, 0)));
+        assert_eq!(serde_json::to_string(&bag).unwrap(), r#"{"EUR":"15","USD":"2"}"#);
+
+        // overflow is an error and leaves the total as it was
+        assert_eq!(bag.add(eur.clone(), Decimal::MAX), Err(MoneyError::Overflow));
+        assert_eq!(bag.get(&eur), Some(Decimal::new(15, 0)));
+    }
+
     #[test]
     fn transaction_new_and_builders() {
         use crate::money::{CurrencyCode, Money};
@@ -90,10 +246,12 @@ mod tests {
             .with_provider(OrgRef {
                 ref_id: Some("AAA-111".into()),
                 name: Some("Donor Org".into()),
+                activity_id: None,
             })
             .with_receiver(OrgRef {
                 ref_id: Some("BBB-222".into()),
                 name: None,
+                activity_id: Some("BBB-222-PROJ".into()),
             })
             .with_currency_hint(CurrencyCode::from("EUR"));
 
@@ -108,6 +266,124 @@ mod tests {
             tx.receiver_org.as_ref().unwrap().ref_id.as_deref(),
             Some("BBB-222")
         );
+        assert_eq!(
+            tx.receiver_org.as_ref().unwrap().activity_id.as_deref(),
+            Some("BBB-222-PROJ")
+        );
         assert_eq!(tx.currency_hint.as_ref().unwrap().0, "EUR");
     }
+
+    #[test]
+    fn budget_defaults_and_codes() {
+        use crate::budget::{Budget, BudgetStatus, BudgetType};
+        use crate::money::Money;
+        use chrono::NaiveDate;
+
+        let start = NaiveDate::from_ymd_opt(2023, 1, 1).unwrap();
+        let end = NaiveDate::from_ymd_opt(2023, 12, 31).unwrap();
+        let b = Budget::new(start, end, Money::new(Decimal::new(100, 0)));
+
+        assert_eq!(b.budget_type, BudgetType::Original);
+        assert_eq!(b.status, BudgetStatus::Indicative);
+        assert!(b.covers(NaiveDate::from_ymd_opt(2023, 12, 31).unwrap()));
+        assert!(!b.covers(NaiveDate::from_ymd_opt(2024, 1, 1).unwrap()));
+        assert_eq!(BudgetType::from(2), BudgetType::Revised);
+        assert!(matches!(BudgetStatus::from(9), BudgetStatus::Unknown(9)));
+    }
+
+    #[test]
+    fn identifier_split_and_validation() {
+        let id: IatiIdentifier = "GB-COH-123456-PROJ-1".parse().unwrap();
+        assert_eq!(id.registration_agency(), Some("GB-COH"));
+        assert_eq!(id.org_number(), "123456");
+        assert_eq!(id.org_identifier(), "GB-COH-123456");
+        assert_eq!(id.activity_suffix(), "PROJ-1");
+
+        let id = IatiIdentifier::parse("44000-P123456").unwrap();
+        assert_eq!(id.registration_agency(), None);
+        assert_eq!(id.org_number(), "44000");
+        assert_eq!(id.activity_suffix(), "P123456");
+
+        assert_eq!(IatiIdentifier::parse(""), Err(IdentifierError::Empty));
+        assert_eq!(IatiIdentifier::parse(" GB-COH-1-A"), Err(IdentifierError::Whitespace(0)));
+        assert_eq!(
+            IatiIdentifier::parse("GB-COH-1/A"),
+            Err(IdentifierError::InvalidChar { ch: '/', pos: 8 })
+        );
+        assert!(matches!(
+            IatiIdentifier::parse("GB-COH-123"),
+            Err(IdentifierError::MissingActivitySuffix(_))
+        ));
+    }
+
+    #[test]
+    fn identifier_prefix_from_reporting_org_or_b1() {
+        let mut act = Activity::new("XM-DAC-41114-PROJ");
+        act.reporting_org = Some(OrgRef {
+            ref_id: Some("XM-DAC-41114".into()),
+            ..Default::default()
+        });
+        let id = act.identifier().unwrap();
+        assert_eq!(id.registration_agency(), Some("XM-DAC"));
+        assert_eq!(id.activity_suffix(), "PROJ");
+
+        // identifier minted under a previous org id is fine when declared as B1
+        let mut act = Activity::new("GB-1-OLD-7");
+        act.reporting_org = Some(OrgRef {
+            ref_id: Some("GB-COH-999".into()),
+            ..Default::default()
+        });
+        assert!(matches!(act.identifier(), Err(IdentifierError::PrefixMismatch { .. })));
+        act.other_identifiers.push(OtherIdentifier {
+            ref_id: "GB-1".into(),
+            type_code: Some("B1".into()),
+        });
+        let id = act.identifier().unwrap();
+        assert_eq!(id.org_identifier(), "GB-1");
+        assert_eq!(id.activity_suffix(), "OLD-7");
+    }
+
+    #[test]
+    fn identifier_prefix_ends_at_an_element_boundary() {
+        assert!(matches!(
+            IatiIdentifier::with_org_prefix("GB-COH-12345-X", "GB-COH-1234"),
+            Err(IdentifierError::MissingSeparator { .. })
+        ));
+
+        let mut act = Activity::new("GB-COH-12345-X");
+        act.reporting_org = Some(OrgRef {
+            ref_id: Some("GB-COH-1234".into()),
+            ..Default::default()
+        });
+        assert!(matches!(act.identifier(), Err(IdentifierError::MissingSeparator { .. })));
+
+        // a boundary match wins over a longer prefix that stops mid-element
+        act.other_identifiers.push(OtherIdentifier {
+            ref_id: "GB-COH".into(),
+            type_code: Some("B1".into()),
+        });
+        let id = act.identifier().unwrap();
+        assert_eq!(id.org_identifier(), "GB-COH");
+        assert_eq!(id.activity_suffix(), "12345-X");
+    }
+
+    #[test]
+    fn identifier_equality_ignores_the_split() {
+        let known = IatiIdentifier::with_org_prefix("GB-1-OLD-7", "GB-1").unwrap();
+        let json = serde_json::to_string(&known).unwrap();
+        let back: IatiIdentifier = serde_json::from_str(&json).unwrap();
+        assert_ne!(back.org_identifier(), known.org_identifier());
+        assert_eq!(back, known);
+        assert_eq!(back.cmp(&known), std::cmp::Ordering::Equal);
+        let set: std::collections::HashSet<_> = [known, back].into_iter().collect();
+        assert_eq!(set.len(), 1);
+    }
+
+    #[test]
+    fn activity_deserialises_without_budgets() {
+        let json = r#"{"iati_identifier":"A1","default_currency":null,"transactions":[],
+            "reporting_org":null,"activity_start":null,"activity_end":null}"#;
+        let act: Activity = serde_json::from_str(json).unwrap();
+        assert!(act.budgets.is_empty());
+    }
 }
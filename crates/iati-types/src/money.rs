@@ -2,6 +2,8 @@
 use chrono::NaiveDate;
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use thiserror::Error;
 
 /// ISO 4217 currency code stored as uppercase string.
 /// Kept as a newtype to allow lightweight validation/normalisation later.
@@ -20,6 +22,39 @@ impl From<String> for CurrencyCode {
     }
 }
 
+impl CurrencyCode {
+    /// ISO 4217 minor unit: decimal places used for amounts in this currency.
+    /// Defaults to 2 for codes not listed as 0, 3 or 4.
+    pub fn minor_units(&self) -> u32 {
+        match self.0.as_str() {
+            "BIF" | "CLP" | "DJF" | "GNF" | "ISK" | "JPY" | "KMF" | "KRW" | "PYG" | "RWF" | "UGX" | "UYI" | "VND"
+            | "VUV" | "XAF" | "XOF" | "XPF" => 0,
+            "BHD" | "IQD" | "JOD" | "KWD" | "LYD" | "OMR" | "TND" => 3,
+            "CLF" | "UYW" => 4,
+            _ => 2,
+        }
+    }
+}
+
+impl std::fmt::Display for CurrencyCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum MoneyError {
+    #[error("currency mismatch: {left:?} vs {right:?}")]
+    CurrencyMismatch {
+        left: Option<CurrencyCode>,
+        right: Option<CurrencyCode>,
+    },
+    #[error("value has no currency")]
+    MissingCurrency,
+    #[error("decimal overflow")]
+    Overflow,
+}
+
 /// Monetary amount with currency and value-date.
 /// In IATI, '<value>' carries '@currency' and '@value-date' attributes.
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -41,4 +76,185 @@ impl Money {
             value_date: None,
         }
     }
+
+    /// Set the currency (builder-style).
+    pub fn with_currency(mut self, currency: CurrencyCode) -> Self {
+        self.currency = Some(currency);
+        self
+    }
+
+    /// Currencies must match exactly; `None` only matches `None`, so resolve
+    /// activity default currencies before doing arithmetic.
+    fn same_currency(&self, other: &Money) -> Result<(), MoneyError> {
+        if self.currency == other.currency {
+            Ok(())
+        } else {
+            Err(MoneyError::CurrencyMismatch {
+                left: self.currency.clone(),
+                right: other.currency.clone(),
+            })
+        }
+    }
+
+    /// The value-date survives only when both sides agree on it.
+    fn combine(&self, other: &Money, amount: Option<Decimal>) -> Result<Money, MoneyError> {
+        Ok(Money {
+            amount: amount.ok_or(MoneyError::Overflow)?,
+            currency: self.currency.clone(),
+            value_date: if self.value_date == other.value_date { self.value_date } else { None },
+        })
+    }
+
+    /// `self + other`, erroring when the currencies differ.
+    pub fn checked_add(&self, other: &Money) -> Result<Money, MoneyError> {
+        self.same_currency(other)?;
+        self.combine(other, self.amount.checked_add(other.amount))
+    }
+
+    /// `self - other`, erroring when the currencies differ.
+    pub fn checked_sub(&self, other: &Money) -> Result<Money, MoneyError> {
+        self.same_currency(other)?;
+        self.combine(other, self.amount.checked_sub(other.amount))
+    }
+
+    /// Multiply the amount by `factor` (e.g. a percentage share), keeping currency and value-date.
+    pub fn scale(&self, factor: Decimal) -> Result<Money, MoneyError> {
+        Ok(Money {
+            amount: self.amount.checked_mul(factor).ok_or(MoneyError::Overflow)?,
+            ..self.clone()
+        })
+    }
+
+    /// Sum values that all share one currency. Returns `None` for an empty iterator.
+    pub fn sum<'a, I>(values: I) -> Result<Option<Money>, MoneyError>
+    where
+        I: IntoIterator<Item = &'a Money>,
+    {
+        let mut iter = values.into_iter();
+        let Some(first) = iter.next() else {
+            return Ok(None);
+        };
+        iter.try_fold(first.clone(), |acc, m| acc.checked_add(m)).map(Some)
+    }
+}
+
+/// Locale-neutral formatting: `1,234.50 EUR`.
+///
+/// Amounts are not rounded, only padded to the currency's minor unit; round
+/// first with a `RoundingPolicy`, or pass a precision (`{:.2}`) to round half-up.
+impl std::fmt::Display for Money {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut amount = self.amount;
+        let mut min_dp = self.currency.as_ref().map_or(0, CurrencyCode::minor_units) as usize;
+        if let Some(p) = f.precision() {
+            amount = amount.round_dp_with_strategy(p as u32, rust_decimal::RoundingStrategy::MidpointAwayFromZero);
+            min_dp = p;
+        }
+
+        let digits = amount.abs().to_string();
+        let (int, frac) = digits.split_once('.').unwrap_or((&digits, ""));
+        let mut out = String::with_capacity(digits.len() + int.len() / 3 + 8);
+        if amount.is_sign_negative() && !amount.is_zero() {
+            out.push('-');
+        }
+        for (i, ch) in int.chars().enumerate() {
+            if i > 0 && (int.len() - i) % 3 == 0 {
+                out.push(',');
+            }
+            out.push(ch);
+        }
+        if !frac.is_empty() || min_dp > 0 {
+            out.push('.');
+            out.push_str(frac);
+            out.extend(std::iter::repeat_n('0', min_dp.saturating_sub(frac.len())));
+        }
+        if let Some(c) = &self.currency {
+            out.push(' ');
+            out.push_str(&c.0);
+        }
+        f.write_str(&out)
+    }
+}
+
+/// Multi-currency accumulator: one running total per currency.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct MoneyBag {
+    totals: BTreeMap<CurrencyCode, Decimal>,
+}
+
+impl MoneyBag {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `amount` to the running total for `currency`.
+    /// On overflow the total is left unchanged.
+    pub fn add(&mut self, currency: CurrencyCode, amount: Decimal) -> Result<(), MoneyError> {
+        let total = self.totals.entry(currency).or_default();
+        *total = total.checked_add(amount).ok_or(MoneyError::Overflow)?;
+        Ok(())
+    }
+
+    /// Add a value, which must carry a currency.
+    pub fn add_money(&mut self, money: &Money) -> Result<(), MoneyError> {
+        let currency = money.currency.clone().ok_or(MoneyError::MissingCurrency)?;
+        self.add(currency, money.amount)
+    }
+
+    /// Add every total from `other`, stopping at the first overflow.
+    pub fn merge(&mut self, other: &MoneyBag) -> Result<(), MoneyError> {
+        for (currency, amount) in other.iter() {
+            self.add(currency.clone(), amount)?;
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, currency: &CurrencyCode) -> Option<Decimal> {
+        self.totals.get(currency).copied()
+    }
+
+    /// Total in `currency` as a `Money`, if anything was added in it.
+    pub fn money(&self, currency: &CurrencyCode) -> Option<Money> {
+        self.get(currency).map(|amount| Money::new(amount).with_currency(currency.clone()))
+    }
+
+    pub fn currencies(&self) -> impl Iterator<Item = &CurrencyCode> {
+        self.totals.keys()
+    }
+
+    /// `(currency, total)` pairs, ordered by currency code.
+    pub fn iter(&self) -> impl Iterator<Item = (&CurrencyCode, Decimal)> {
+        self.totals.iter().map(|(c, a)| (c, *a))
+    }
+
+    pub fn len(&self) -> usize {
+        self.totals.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.totals.is_empty()
+    }
+
+    /// The single total when everything was in one currency.
+    pub fn single(&self) -> Option<Money> {
+        match self.totals.len() {
+            1 => self.currencies().next().and_then(|c| self.money(c)),
+            _ => None,
+        }
+    }
+}
+
+/// One total per currency; a map has no repeated currencies to add up.
+impl From<BTreeMap<CurrencyCode, Decimal>> for MoneyBag {
+    fn from(totals: BTreeMap<CurrencyCode, Decimal>) -> Self {
+        MoneyBag { totals }
+    }
+}
+
+impl From<MoneyBag> for BTreeMap<CurrencyCode, Decimal> {
+    fn from(bag: MoneyBag) -> Self {
+        bag.totals
+    }
 }
@@ -0,0 +1,94 @@
+use crate::budget::BudgetStatus;
+use crate::money::{CurrencyCode, Money};
+use crate::OrgRef;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+/// Budget or expenditure for a single period, as used by the Organisation Standard.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrgBudget {
+    /// from '@status'; always 'Indicative' for 'total-expenditure'.
+    pub status: BudgetStatus,
+    pub period_start: NaiveDate, // from 'period-start/@iso-date'
+    pub period_end: NaiveDate,   // from 'period-end/@iso-date'
+    pub value: Money,            // from <value> body + attributes
+}
+
+impl OrgBudget {
+    pub fn new(period_start: NaiveDate, period_end: NaiveDate, value: Money) -> Self {
+        Self {
+            status: BudgetStatus::Indicative,
+            period_start,
+            period_end,
+            value,
+        }
+    }
+}
+
+/// 'recipient-org-budget': budget allocated to another organisation.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecipientOrgBudget {
+    pub recipient_org: OrgRef,
+    pub budget: OrgBudget,
+}
+
+/// 'recipient-country-budget': budget allocated to a country.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecipientCountryBudget {
+    /// ISO 3166-1 alpha-2 code from 'recipient-country/@code'.
+    pub country: String,
+    pub budget: OrgBudget,
+}
+
+/// 'document-link': a published document about the organisation.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct DocumentLink {
+    pub url: String,
+    /// IANA media type from '@format'.
+    pub format: Option<String>,
+    /// First narrative of 'title'.
+    pub title: Option<String>,
+    /// DocumentCategory codes from 'category/@code'.
+    pub categories: Vec<String>,
+    /// Language codes from 'language/@code'.
+    pub languages: Vec<String>,
+    pub document_date: Option<NaiveDate>,
+}
+
+/// IATI Organisation (the 'iati-organisation' element), trimmed to its financial spine.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Organisation {
+    /// 'organisation-identifier' element.
+    pub organisation_identifier: String,
+    /// First narrative of 'name'.
+    pub name: Option<String>,
+    /// Default ISO 4217 currency for monetary values in this Organisation.
+    pub default_currency: Option<CurrencyCode>,
+    pub reporting_org: Option<OrgRef>,
+    pub total_budgets: Vec<OrgBudget>,
+    pub recipient_org_budgets: Vec<RecipientOrgBudget>,
+    pub recipient_country_budgets: Vec<RecipientCountryBudget>,
+    pub total_expenditures: Vec<OrgBudget>,
+    pub document_links: Vec<DocumentLink>,
+}
+
+impl Organisation {
+    pub fn new<S: Into<String>>(organisation_identifier: S) -> Self {
+        Self {
+            organisation_identifier: organisation_identifier.into(),
+            name: None,
+            default_currency: None,
+            reporting_org: None,
+            total_budgets: Vec::new(),
+            recipient_org_budgets: Vec::new(),
+            recipient_country_budgets: Vec::new(),
+            total_expenditures: Vec::new(),
+            document_links: Vec::new(),
+        }
+    }
+}
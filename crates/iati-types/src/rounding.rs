@@ -0,0 +1,84 @@
+use crate::money::{CurrencyCode, Money, MoneyBag};
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// How to break ties when rounding.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RoundingMode {
+    /// Round half to even (banker's rounding): 2.345 -> 2.34, 2.355 -> 2.36.
+    Bankers,
+    /// Round half away from zero: 2.345 -> 2.35.
+    #[default]
+    HalfUp,
+}
+
+impl RoundingMode {
+    fn strategy(self) -> RoundingStrategy {
+        match self {
+            RoundingMode::Bankers => RoundingStrategy::MidpointNearestEven,
+            RoundingMode::HalfUp => RoundingStrategy::MidpointAwayFromZero,
+        }
+    }
+}
+
+/// Number of decimal places to round to.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RoundingScale {
+    /// The currency's ISO 4217 minor unit (`CurrencyCode::minor_units`);
+    /// 2 when the value has no currency.
+    #[default]
+    MinorUnit,
+    /// A fixed number of decimal places, whatever the currency.
+    Scale(u32),
+}
+
+/// Rounding applied to monetary amounts, e.g. after FX conversion or aggregation.
+///
+/// The default rounds half-up to each currency's minor unit (JPY 0, EUR 2, KWD 3).
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RoundingPolicy {
+    pub mode: RoundingMode,
+    pub scale: RoundingScale,
+}
+
+impl RoundingPolicy {
+    pub fn new(mode: RoundingMode, scale: RoundingScale) -> Self {
+        RoundingPolicy { mode, scale }
+    }
+
+    /// Round `amount`, using `currency` to find the minor unit when needed.
+    pub fn round(&self, amount: Decimal, currency: Option<&CurrencyCode>) -> Decimal {
+        let dp = match self.scale {
+            RoundingScale::MinorUnit => currency.map_or(2, CurrencyCode::minor_units),
+            RoundingScale::Scale(dp) => dp,
+        };
+        amount.round_dp_with_strategy(dp, self.mode.strategy())
+    }
+
+    pub fn round_money(&self, money: &Money) -> Money {
+        Money {
+            amount: self.round(money.amount, money.currency.as_ref()),
+            ..money.clone()
+        }
+    }
+}
+
+impl Money {
+    /// Round the amount with `policy` (builder-style).
+    pub fn rounded(self, policy: &RoundingPolicy) -> Money {
+        policy.round_money(&self)
+    }
+}
+
+impl MoneyBag {
+    /// Round every total with `policy`.
+    pub fn rounded(&self, policy: &RoundingPolicy) -> MoneyBag {
+        let totals: BTreeMap<CurrencyCode, Decimal> =
+            self.iter().map(|(c, amount)| (c.clone(), policy.round(amount, Some(c)))).collect();
+        MoneyBag::from(totals)
+    }
+}
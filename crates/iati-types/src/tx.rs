@@ -4,11 +4,10 @@ use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
 use std::str::FromStr;  
 
-/// IATI Transaction Type (from Transaction/@type).
-/// See https://iatistandard.org/en/iati-standard/activities/transaction/#transaction-type
-/// Unknown/extension codes are preserved via 'Unknown(u16)' variant.
-/// Note that 'Transaction/@type' is distinct from 'Transaction/transaction-type'.
-/// The latter is a child element with its own codelist (see TxType enum).
+/// IATI TransactionType codelist, from 'transaction/transaction-type/@code'.
+/// See https://iatistandard.org/en/iati-standard/203/codelists/transactiontype/
+/// Codes outside the codelist are preserved via the 'Unknown(u16)' variant.
+/// [`TxType::direction`] and the `is_*` helpers classify codes for aggregation.
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)] 
 pub enum TxType {
@@ -49,6 +48,104 @@ impl TxType {
             Unknown(c) => c,
         }
     }
+
+    /// Codelist name, e.g. "Outgoing Commitment".
+    pub fn name(self) -> &'static str {
+        use TxType::*;
+        match self {
+            IncomingFunds => "Incoming Funds",
+            OutgoingCommitment => "Outgoing Commitment",
+            Disbursement => "Disbursement",
+            Expenditure => "Expenditure",
+            InterestPayment => "Interest Payment",
+            LoanRepayment => "Loan Repayment",
+            Reimbursement => "Reimbursement",
+            PurchaseOfEquity => "Purchase of Equity",
+            SaleOfEquity => "Sale of Equity",
+            CreditGuarantee => "Credit Guarantee",
+            IncomingCommitment => "Incoming Commitment",
+            OutgoingPledge => "Outgoing Pledge",
+            IncomingPledge => "Incoming Pledge",
+            Unknown(_) => "Unknown",
+        }
+    }
+
+    /// Direction of the funds relative to the reporting organisation.
+    /// Interest payments, loan repayments and equity sales are reflows, so incoming.
+    /// `None` for unknown codes.
+    pub fn direction(self) -> Option<FlowDirection> {
+        use TxType::*;
+        match self {
+            IncomingFunds | InterestPayment | LoanRepayment | SaleOfEquity | IncomingCommitment | IncomingPledge => {
+                Some(FlowDirection::Incoming)
+            }
+            OutgoingCommitment | Disbursement | Expenditure | Reimbursement | PurchaseOfEquity | CreditGuarantee
+            | OutgoingPledge => Some(FlowDirection::Outgoing),
+            Unknown(_) => None,
+        }
+    }
+
+    /// Firm commitment, outgoing (2) or incoming (11).
+    pub fn is_commitment(self) -> bool {
+        matches!(self, TxType::OutgoingCommitment | TxType::IncomingCommitment)
+    }
+
+    /// Non-binding pledge, outgoing (12) or incoming (13).
+    pub fn is_pledge(self) -> bool {
+        matches!(self, TxType::OutgoingPledge | TxType::IncomingPledge)
+    }
+
+    /// Disbursement (3), or reimbursement (7), which the codelist defines as a type of disbursement.
+    pub fn is_disbursement(self) -> bool {
+        matches!(self, TxType::Disbursement | TxType::Reimbursement)
+    }
+
+    /// Money spent by the reporting organisation: disbursements and expenditure (4).
+    pub fn is_spend(self) -> bool {
+        self.is_disbursement() || self == TxType::Expenditure
+    }
+
+    /// Actual movement of funds, as opposed to commitments, pledges and guarantees.
+    pub fn is_flow(self) -> bool {
+        !matches!(self, TxType::Unknown(_) | TxType::CreditGuarantee) && !self.is_commitment() && !self.is_pledge()
+    }
+
+    /// Funds actually received by the reporting organisation (1, 5, 6, 9).
+    pub fn is_inflow(self) -> bool {
+        self.is_flow() && self.direction() == Some(FlowDirection::Incoming)
+    }
+
+    /// Funds actually leaving the reporting organisation (3, 4, 7, 8).
+    pub fn is_outflow(self) -> bool {
+        self.is_flow() && self.direction() == Some(FlowDirection::Outgoing)
+    }
+
+    /// Map an IATI 1.0x letter code (e.g. "D", "IF") to its 2.x TxType.
+    /// See https://iatistandard.org/en/iati-standard/105/codelists/transactiontype/
+    pub fn from_v1_code(code: &str) -> Option<TxType> {
+        use TxType::*;
+        Some(match code.trim() {
+            "IF" => IncomingFunds,
+            "C" => OutgoingCommitment,
+            "D" => Disbursement,
+            "E" => Expenditure,
+            "IR" => InterestPayment,
+            "LR" => LoanRepayment,
+            "R" => Reimbursement,
+            "QP" => PurchaseOfEquity,
+            "QS" => SaleOfEquity,
+            "CG" => CreditGuarantee,
+            _ => return None,
+        })
+    }
+}
+
+/// Direction of a transaction relative to the reporting organisation.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum FlowDirection {
+    Incoming,
+    Outgoing,
 }
 
 impl std::fmt::Display for TxType { 
@@ -97,6 +194,9 @@ pub struct Transaction {
     pub receiver_org: Option<OrgRef>,
     /// optional hint for a resolved currency if caller has done FX lookup
     pub currency_hint: Option<CurrencyCode>,
+    /// Publisher's own reference, from 'transaction/@ref'
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub ref_id: Option<String>,
 }
 
 impl Transaction {
@@ -108,6 +208,7 @@ impl Transaction {
             provider_org: None, 
             receiver_org: None,
             currency_hint: None,
+            ref_id: None,
         }
     }
 
@@ -128,4 +229,10 @@ impl Transaction {
         self.currency_hint = Some(code);
         self
     }
+
+    /// Set the publisher's transaction reference (builder-style).
+    pub fn with_ref<S: Into<String>>(mut self, ref_id: S) -> Self {
+        self.ref_id = Some(ref_id.into());
+        self
+    }
 }
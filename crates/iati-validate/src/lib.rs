@@ -0,0 +1,165 @@
+//! iati-validate: rule-based data quality checks for IATI activities.
+//!
+//! A [`Validator`] runs a set of [`Rule`]s over each `Activity` and collects
+//! structured [`Finding`]s (rule id, severity, path, message) that serialise to JSON.
+
+use chrono::NaiveDate;
+use iati_types::Activity;
+use serde::{Deserialize, Serialize};
+
+pub mod rules;
+
+pub use crate::rules::{
+    DuplicateTransactionRef, FutureTransactionDate, IdentifierPrefix, MissingCurrency, NegativeDisbursement,
+    SectorPercentages, UnknownTransactionType, ValueDateOutsideActivity,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+/// One problem found in one activity.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Finding {
+    pub rule_id: String,
+    pub severity: Severity,
+    pub iati_identifier: String,
+    /// Location within the activity, XPath-like with 0-based indices matching
+    /// the `Activity` vectors, e.g. `transaction[2]/value/@value-date`.
+    pub path: String,
+    pub message: String,
+}
+
+impl Finding {
+    /// Build a finding for `rule` with its default severity.
+    pub fn new(rule: &dyn Rule, activity: &Activity, path: impl Into<String>, message: impl Into<String>) -> Self {
+        Finding {
+            rule_id: rule.id().to_string(),
+            severity: rule.severity(),
+            iati_identifier: activity.iati_identifier.clone(),
+            path: path.into(),
+            message: message.into(),
+        }
+    }
+}
+
+/// Inputs shared by all rules.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Context {
+    /// Reference date for "in the future" checks.
+    pub today: NaiveDate,
+}
+
+impl Context {
+    pub fn new(today: NaiveDate) -> Self {
+        Context { today }
+    }
+}
+
+impl Default for Context {
+    /// Uses today's UTC date.
+    fn default() -> Self {
+        Context::new(chrono::Utc::now().date_naive())
+    }
+}
+
+/// A single data quality check.
+pub trait Rule: Send + Sync {
+    /// Stable kebab-case identifier, e.g. `tx-date-in-future`.
+    fn id(&self) -> &'static str;
+
+    fn severity(&self) -> Severity;
+
+    /// Push any findings for `activity` onto `out`.
+    fn check(&self, activity: &Activity, ctx: &Context, out: &mut Vec<Finding>);
+}
+
+/// Findings for a batch of activities.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Report {
+    pub activities_checked: usize,
+    pub findings: Vec<Finding>,
+}
+
+impl Report {
+    pub fn count(&self, severity: Severity) -> usize {
+        self.findings.iter().filter(|f| f.severity == severity).count()
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.count(Severity::Error) > 0
+    }
+}
+
+/// Runs a pluggable set of rules.
+pub struct Validator {
+    rules: Vec<Box<dyn Rule>>,
+    ctx: Context,
+}
+
+impl Validator {
+    /// A validator with the built-in rule set.
+    pub fn new(ctx: Context) -> Self {
+        Validator::empty(ctx)
+            .with_rule(FutureTransactionDate)
+            .with_rule(ValueDateOutsideActivity)
+            .with_rule(NegativeDisbursement)
+            .with_rule(MissingCurrency)
+            .with_rule(SectorPercentages::default())
+            .with_rule(UnknownTransactionType)
+            .with_rule(DuplicateTransactionRef)
+            .with_rule(IdentifierPrefix)
+    }
+
+    /// A validator with no rules.
+    pub fn empty(ctx: Context) -> Self {
+        Validator { rules: Vec::new(), ctx }
+    }
+
+    /// Add a rule (builder-style).
+    pub fn with_rule(mut self, rule: impl Rule + 'static) -> Self {
+        self.rules.push(Box::new(rule));
+        self
+    }
+
+    /// Remove every rule with the given id (builder-style).
+    pub fn without_rule(mut self, id: &str) -> Self {
+        self.rules.retain(|r| r.id() != id);
+        self
+    }
+
+    pub fn rule_ids(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.rules.iter().map(|r| r.id())
+    }
+
+    /// Run every rule over one activity.
+    pub fn validate(&self, activity: &Activity) -> Vec<Finding> {
+        let mut out = Vec::new();
+        for rule in &self.rules {
+            rule.check(activity, &self.ctx, &mut out);
+        }
+        out
+    }
+
+    /// Run every rule over many activities.
+    pub fn validate_all<'a>(&self, activities: impl IntoIterator<Item = &'a Activity>) -> Report {
+        let mut report = Report::default();
+        for act in activities {
+            report.activities_checked += 1;
+            for rule in &self.rules {
+                rule.check(act, &self.ctx, &mut report.findings);
+            }
+        }
+        report
+    }
+}
+
+impl Default for Validator {
+    fn default() -> Self {
+        Validator::new(Context::default())
+    }
+}
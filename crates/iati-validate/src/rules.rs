@@ -0,0 +1,382 @@
+//! Built-in rules.
+
+use iati_types::{tx::TxType, Activity, IatiIdentifier, IdentifierError};
+use rust_decimal::Decimal;
+use std::collections::{BTreeMap, HashSet};
+
+use crate::{Context, Finding, Rule, Severity};
+
+/// Transaction dated after `Context::today`.
+pub struct FutureTransactionDate;
+
+impl Rule for FutureTransactionDate {
+    fn id(&self) -> &'static str {
+        "tx-date-in-future"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn check(&self, act: &Activity, ctx: &Context, out: &mut Vec<Finding>) {
+        for (i, tx) in act.transactions.iter().enumerate() {
+            if tx.date > ctx.today {
+                out.push(Finding::new(
+                    self,
+                    act,
+                    format!("transaction[{i}]/transaction-date/@iso-date"),
+                    format!("transaction date {} is after {}", tx.date, ctx.today),
+                ));
+            }
+        }
+    }
+}
+
+/// Transaction value-date before the activity start or after the activity end.
+pub struct ValueDateOutsideActivity;
+
+impl Rule for ValueDateOutsideActivity {
+    fn id(&self) -> &'static str {
+        "value-date-outside-activity"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn check(&self, act: &Activity, _ctx: &Context, out: &mut Vec<Finding>) {
+        for (i, tx) in act.transactions.iter().enumerate() {
+            let Some(vd) = tx.value.value_date else {
+                continue;
+            };
+            let problem = match (act.activity_start, act.activity_end) {
+                (Some(start), _) if vd < start => format!("value-date {vd} is before activity start {start}"),
+                (_, Some(end)) if vd > end => format!("value-date {vd} is after activity end {end}"),
+                _ => continue,
+            };
+            out.push(Finding::new(self, act, format!("transaction[{i}]/value/@value-date"), problem));
+        }
+    }
+}
+
+/// Disbursement (type 3) with a negative value.
+pub struct NegativeDisbursement;
+
+impl Rule for NegativeDisbursement {
+    fn id(&self) -> &'static str {
+        "negative-disbursement"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn check(&self, act: &Activity, _ctx: &Context, out: &mut Vec<Finding>) {
+        for (i, tx) in act.transactions.iter().enumerate() {
+            if tx.tx_type == TxType::Disbursement && tx.value.amount.is_sign_negative() && !tx.value.amount.is_zero() {
+                out.push(Finding::new(
+                    self,
+                    act,
+                    format!("transaction[{i}]/value"),
+                    format!("disbursement has negative value {}", tx.value.amount),
+                ));
+            }
+        }
+    }
+}
+
+/// Transaction or budget value with no '@currency' and no activity default-currency.
+pub struct MissingCurrency;
+
+impl Rule for MissingCurrency {
+    fn id(&self) -> &'static str {
+        "missing-currency"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn check(&self, act: &Activity, _ctx: &Context, out: &mut Vec<Finding>) {
+        if act.default_currency.is_some() {
+            return;
+        }
+        let message = "value has no @currency and the activity has no @default-currency";
+        for (i, tx) in act.transactions.iter().enumerate() {
+            if tx.value.currency.is_none() {
+                out.push(Finding::new(self, act, format!("transaction[{i}]/value/@currency"), message));
+            }
+        }
+        for (i, b) in act.budgets.iter().enumerate() {
+            if b.value.currency.is_none() {
+                out.push(Finding::new(self, act, format!("budget[{i}]/value/@currency"), message));
+            }
+        }
+    }
+}
+
+/// Sector percentages within each vocabulary must add up to 100.
+/// A single sector without a percentage counts as 100%.
+#[derive(Default)]
+pub struct SectorPercentages {
+    /// Accepted absolute difference from 100, for publishers that round (e.g. 3 x 33.33).
+    pub tolerance: Decimal,
+}
+
+impl Rule for SectorPercentages {
+    fn id(&self) -> &'static str {
+        "sector-percentages"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn check(&self, act: &Activity, _ctx: &Context, out: &mut Vec<Finding>) {
+        // vocabulary -> sector indices; a missing vocabulary is the default, "1"
+        let mut by_vocab: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+        for (i, s) in act.sectors.iter().enumerate() {
+            by_vocab.entry(s.vocabulary.as_deref().unwrap_or("1")).or_default().push(i);
+        }
+
+        for (vocab, idx) in by_vocab {
+            if idx.len() == 1 && act.sectors[idx[0]].percentage.is_none() {
+                continue;
+            }
+            let mut sum = Decimal::ZERO;
+            for &i in &idx {
+                match act.sectors[i].percentage {
+                    Some(p) => sum += p,
+                    None => out.push(Finding::new(
+                        self,
+                        act,
+                        format!("sector[{i}]/@percentage"),
+                        format!("sector in vocabulary {vocab} has no percentage, but the vocabulary has several sectors"),
+                    )),
+                }
+            }
+            if (sum - Decimal::ONE_HUNDRED).abs() > self.tolerance {
+                out.push(Finding::new(
+                    self,
+                    act,
+                    format!("sector[{}]/@percentage", idx[0]),
+                    format!("sector percentages in vocabulary {vocab} add up to {sum}, not 100"),
+                ));
+            }
+        }
+    }
+}
+
+/// Transaction type code outside the TransactionType codelist.
+pub struct UnknownTransactionType;
+
+impl Rule for UnknownTransactionType {
+    fn id(&self) -> &'static str {
+        "unknown-tx-type"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn check(&self, act: &Activity, _ctx: &Context, out: &mut Vec<Finding>) {
+        for (i, tx) in act.transactions.iter().enumerate() {
+            if let TxType::Unknown(code) = tx.tx_type {
+                out.push(Finding::new(
+                    self,
+                    act,
+                    format!("transaction[{i}]/transaction-type/@code"),
+                    format!("transaction type {code} is not in the TransactionType codelist"),
+                ));
+            }
+        }
+    }
+}
+
+/// The same 'transaction/@ref' used more than once within an activity.
+pub struct DuplicateTransactionRef;
+
+impl Rule for DuplicateTransactionRef {
+    fn id(&self) -> &'static str {
+        "duplicate-tx-ref"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn check(&self, act: &Activity, _ctx: &Context, out: &mut Vec<Finding>) {
+        let mut seen: HashSet<&str> = HashSet::new();
+        for (i, tx) in act.transactions.iter().enumerate() {
+            let Some(r) = tx.ref_id.as_deref() else {
+                continue;
+            };
+            if !seen.insert(r) {
+                out.push(Finding::new(
+                    self,
+                    act,
+                    format!("transaction[{i}]/@ref"),
+                    format!("transaction ref {r:?} is used more than once"),
+                ));
+            }
+        }
+    }
+}
+
+/// 'iati-identifier' with invalid characters or not prefixed by the
+/// reporting-org ref (or a type B1 other-identifier). Without either to check
+/// against, only the characters and the structure are checked: some organisation
+/// identifier, `-`, then a non-empty activity suffix.
+pub struct IdentifierPrefix;
+
+impl Rule for IdentifierPrefix {
+    fn id(&self) -> &'static str {
+        "iati-identifier"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn check(&self, act: &Activity, _ctx: &Context, out: &mut Vec<Finding>) {
+        let has_reporting_ref = act.reporting_org.as_ref().is_some_and(|o| o.ref_id.is_some());
+        let has_prefix = has_reporting_ref || act.other_identifiers.iter().any(|o| o.is_previous_reporting_org());
+        let checked = if has_prefix {
+            act.identifier()
+        } else {
+            structure_only(&act.iati_identifier)
+        };
+        if let Err(e) = checked {
+            out.push(Finding::new(self, act, "iati-identifier", e.to_string()));
+        }
+    }
+}
+
+/// `IatiIdentifier::parse`, except that when its agency heuristic leaves no
+/// suffix (`XM-TEST-1`) the identifier may still split at its last `-`.
+fn structure_only(s: &str) -> Result<IatiIdentifier, IdentifierError> {
+    match IatiIdentifier::parse(s) {
+        Err(IdentifierError::MissingActivitySuffix(_)) if s.rfind('-').is_some_and(|i| i > 0) => {
+            IatiIdentifier::with_org_prefix(s, &s[..s.rfind('-').unwrap_or_default()])
+        }
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Context, Severity, Validator};
+    use chrono::NaiveDate;
+    use iati_types::{money::Money, tx::Transaction, Activity, CurrencyCode, OrgRef, Sector, TxType};
+    use rust_decimal::Decimal;
+
+    fn d(y: i32, m: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, day).unwrap()
+    }
+
+    fn clean_activity() -> Activity {
+        let mut a = Activity::new("GB-COH-1-A");
+        a.reporting_org = Some(OrgRef {
+            ref_id: Some("GB-COH-1".into()),
+            ..Default::default()
+        });
+        a.default_currency = Some(CurrencyCode::from("USD"));
+        a.activity_start = Some(d(2023, 1, 1));
+        a.activity_end = Some(d(2023, 12, 31));
+        a.transactions.push(
+            Transaction::new(TxType::Disbursement, d(2023, 3, 1), Money::new(Decimal::new(10, 0))).with_ref("T1"),
+        );
+        a
+    }
+
+    fn validator() -> Validator {
+        Validator::new(Context::new(d(2024, 1, 1)))
+    }
+
+    fn rule_ids(a: &Activity) -> Vec<String> {
+        validator().validate(a).into_iter().map(|f| f.rule_id).collect()
+    }
+
+    #[test]
+    fn clean_activity_has_no_findings() {
+        assert!(validator().validate(&clean_activity()).is_empty());
+    }
+
+    #[test]
+    fn transaction_rules() {
+        let mut a = clean_activity();
+        a.default_currency = None;
+        let mut money = Money::new(Decimal::new(-5, 0));
+        money.value_date = Some(d(2022, 12, 1));
+        a.transactions.push(Transaction::new(TxType::Disbursement, d(2024, 6, 1), money).with_ref("T1"));
+        a.transactions.push(Transaction::new(TxType::Unknown(42), d(2023, 6, 1), Money::new(Decimal::ONE)));
+
+        let findings = validator().validate(&a);
+        let ids: Vec<&str> = findings.iter().map(|f| f.rule_id.as_str()).collect();
+        assert!(ids.contains(&"tx-date-in-future"));
+        assert!(ids.contains(&"value-date-outside-activity"));
+        assert!(ids.contains(&"negative-disbursement"));
+        assert!(ids.contains(&"unknown-tx-type"));
+        assert!(ids.contains(&"duplicate-tx-ref"));
+        assert_eq!(ids.iter().filter(|id| **id == "missing-currency").count(), 3);
+
+        let dup = findings.iter().find(|f| f.rule_id == "duplicate-tx-ref").unwrap();
+        assert_eq!(dup.path, "transaction[1]/@ref");
+        assert_eq!(dup.severity, Severity::Warning);
+    }
+
+    #[test]
+    fn sector_percentages_per_vocabulary() {
+        let sector = |code: &str, vocab: Option<&str>, pct: Option<i64>| Sector {
+            code: code.into(),
+            vocabulary: vocab.map(String::from),
+            percentage: pct.map(|p| Decimal::new(p, 0)),
+        };
+        let mut a = clean_activity();
+        a.sectors = vec![sector("11110", None, None)];
+        assert!(rule_ids(&a).is_empty());
+
+        a.sectors = vec![
+            sector("11110", Some("1"), Some(60)),
+            sector("12220", None, Some(40)),
+            sector("111", Some("2"), Some(50)),
+            sector("112", Some("2"), None),
+        ];
+        let findings = validator().validate(&a);
+        let paths: Vec<&str> = findings.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, vec!["sector[3]/@percentage", "sector[2]/@percentage"]);
+    }
+
+    #[test]
+    fn identifier_rule_and_report_json() {
+        let mut a = clean_activity();
+        a.iati_identifier = "XX-OTHER-9-A".into();
+        assert_eq!(rule_ids(&a), vec!["iati-identifier".to_string()]);
+
+        let report = validator().without_rule("iati-identifier").validate_all([&a, &clean_activity()]);
+        assert_eq!(report.activities_checked, 2);
+        assert!(!report.has_errors());
+
+        let report = validator().validate_all([&a]);
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["findings"][0]["severity"], "error");
+        assert_eq!(json["findings"][0]["rule_id"], "iati-identifier");
+        assert_eq!(json["findings"][0]["path"], "iati-identifier");
+    }
+
+    #[test]
+    fn identifier_without_reporting_org_checks_structure_only() {
+        let mut a = clean_activity();
+        a.reporting_org = None;
+        assert!(rule_ids(&a).is_empty());
+
+        a.iati_identifier = "XM-TEST-1".into(); // org XM-TEST, suffix 1
+        assert!(rule_ids(&a).is_empty());
+        a.iati_identifier = "XM-".into(); // no activity suffix
+        assert_eq!(rule_ids(&a), vec!["iati-identifier".to_string()]);
+        a.iati_identifier = "PROJECT1".into();
+        assert_eq!(rule_ids(&a), vec!["iati-identifier".to_string()]);
+        a.iati_identifier = "GB-COH-1/A".into();
+        assert_eq!(rule_ids(&a), vec!["iati-identifier".to_string()]);
+    }
+}
@@ -0,0 +1,345 @@
+//! Reading registry dumps: plain, gzipped and zipped XML files and directory trees.
+//!
+//! [`DatasetReader`] yields every activity with the path it came from, so each
+//! activity and each error can be traced to its file. Zip members are named
+//! `archive.zip/member.xml`.
+
+use std::collections::{HashSet, VecDeque};
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Cursor, Read};
+use std::path::{Path, PathBuf};
+
+use flate2::bufread::MultiGzDecoder;
+use iati_types::Activity;
+use zip::ZipArchive;
+
+use crate::{ActivityReader, ParseError};
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Whether a directory walk picks up `path`: `*.xml`, `*.xml.gz` or `*.zip` (any case).
+pub fn is_dataset_file(path: &Path) -> bool {
+    let name = path.file_name().map(|n| n.to_string_lossy().to_ascii_lowercase()).unwrap_or_default();
+    name.ends_with(".xml") || name.ends_with(".xml.gz") || name.ends_with(".zip")
+}
+
+/// A directory entry that could not be read, with its path.
+pub type WalkError = (PathBuf, io::Error);
+
+/// Every dataset file under `dir`, recursively, in sorted order.
+///
+/// A directory or entry that cannot be read (no permission, a symlink loop) is
+/// an `Err` in its place and the walk carries on. Symlinked directories are
+/// followed, but each directory is visited once.
+pub fn dataset_files(dir: &Path) -> Vec<Result<PathBuf, WalkError>> {
+    let mut out = Vec::new();
+    collect(dir, &mut HashSet::new(), &mut out);
+    out
+}
+
+fn collect(dir: &Path, visited: &mut HashSet<PathBuf>, out: &mut Vec<Result<PathBuf, WalkError>>) {
+    match fs::canonicalize(dir) {
+        Ok(real) => {
+            if !visited.insert(real) {
+                return;
+            }
+        }
+        Err(e) => return out.push(Err((dir.to_path_buf(), e))),
+    }
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => return out.push(Err((dir.to_path_buf(), e))),
+    };
+    let mut paths = Vec::new();
+    for entry in entries {
+        match entry {
+            Ok(entry) => paths.push(entry.path()),
+            Err(e) => out.push(Err((dir.to_path_buf(), e))),
+        }
+    }
+    paths.sort();
+    for path in paths {
+        match fs::metadata(&path) {
+            Ok(meta) if meta.is_dir() => collect(&path, visited, out),
+            Ok(_) if is_dataset_file(&path) => out.push(Ok(path)),
+            Ok(_) => {}
+            Err(e) => out.push(Err((path, e))),
+        }
+    }
+}
+
+/// `source`, gunzipped if it starts with the gzip magic bytes.
+pub fn decompressed<'a>(mut source: impl BufRead + 'a) -> io::Result<Box<dyn BufRead + 'a>> {
+    if source.fill_buf()?.starts_with(&GZIP_MAGIC) {
+        Ok(Box::new(BufReader::new(MultiGzDecoder::new(source))))
+    } else {
+        Ok(Box::new(source))
+    }
+}
+
+type Activities = ActivityReader<Box<dyn BufRead>>;
+
+/// A path still to read, or a directory entry that could not be read.
+type Pending = Result<PathBuf, WalkError>;
+
+/// Streams `(source path, activity)` from files, gzipped files, zip archives
+/// and directories. Directories are walked when reached, for [`is_dataset_file`]
+/// files; paths given directly are read whatever their name, and zip archives
+/// are recognised by their `.zip` extension.
+///
+/// Errors opening or reading a file are yielded against its path and the
+/// reader moves on to the next file. Zip members are decompressed in memory,
+/// up to [`DatasetReader::DEFAULT_MAX_MEMBER_BYTES`] each unless set with
+/// [`with_max_member_bytes`](DatasetReader::with_max_member_bytes).
+///
+/// ```no_run
+/// for (path, activity) in iati_xml::DatasetReader::new(["registry-dump/"]) {
+///     match activity {
+///         Ok(a) => println!("{}: {}", path.display(), a.iati_identifier),
+///         Err(e) => eprintln!("{}: {e}", path.display()),
+///     }
+/// }
+/// ```
+pub struct DatasetReader {
+    pending: VecDeque<Pending>,
+    archive: Option<(PathBuf, ZipArchive<BufReader<File>>, usize)>,
+    current: Option<(PathBuf, Activities)>,
+    max_member_bytes: u64,
+}
+
+impl DatasetReader {
+    /// Default limit on the decompressed size of one zip member (1 GiB).
+    pub const DEFAULT_MAX_MEMBER_BYTES: u64 = 1 << 30;
+
+    pub fn new<P: Into<PathBuf>>(paths: impl IntoIterator<Item = P>) -> Self {
+        DatasetReader {
+            pending: paths.into_iter().map(|p| Ok(p.into())).collect(),
+            archive: None,
+            current: None,
+            max_member_bytes: Self::DEFAULT_MAX_MEMBER_BYTES,
+        }
+    }
+
+    /// Set the largest zip member to read, in bytes (builder-style). Larger
+    /// members are an error against the member's path.
+    pub fn with_max_member_bytes(mut self, bytes: u64) -> Self {
+        self.max_member_bytes = bytes;
+        self
+    }
+
+    /// Start reading the next pending path. `Err` is reported against the path.
+    fn open_next(&mut self, path: &Path) -> Result<(), ParseError> {
+        if path.is_dir() {
+            for file in dataset_files(path).into_iter().rev() {
+                self.pending.push_front(file);
+            }
+        } else if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("zip")) {
+            let archive = ZipArchive::new(BufReader::new(File::open(path)?))?;
+            self.archive = Some((path.to_path_buf(), archive, 0));
+        } else {
+            let reader = decompressed(BufReader::new(File::open(path)?))?;
+            self.current = Some((path.to_path_buf(), ActivityReader::new(reader)));
+        }
+        Ok(())
+    }
+
+    /// Open the next XML member of the current archive, if any.
+    fn next_member(&mut self) -> Option<(PathBuf, Result<(), ParseError>)> {
+        let (path, archive, next) = self.archive.as_mut()?;
+        while *next < archive.len() {
+            let index = *next;
+            *next += 1;
+            let mut member = match archive.by_index(index) {
+                Ok(m) => m,
+                Err(e) => return Some((path.clone(), Err(e.into()))),
+            };
+            // A label, not a location: joining would let absolute or `../` names escape the archive.
+            let member_path = match member.name() {
+                Ok(name) => PathBuf::from(format!("{}/{name}", path.display())),
+                Err(e) => return Some((path.clone(), Err(e.into()))),
+            };
+            if member.is_dir() || !is_dataset_file(&member_path) {
+                continue;
+            }
+            // Members are read whole: the archive cannot hand out a reader that outlives this call.
+            // The header's size is untrusted, so the buffer grows with the data actually read,
+            // up to the limit.
+            let limit = self.max_member_bytes;
+            let mut bytes = Vec::new();
+            let opened = (&mut member)
+                .take(limit.saturating_add(1))
+                .read_to_end(&mut bytes)
+                .and_then(|read| match u64::try_from(read) {
+                    Ok(read) if read <= limit => Ok(()),
+                    _ => Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("zip member larger than {limit} bytes"),
+                    )),
+                })
+                .and_then(|()| decompressed(Cursor::new(bytes)))
+                .map(|reader| self.current = Some((member_path.clone(), ActivityReader::new(reader))));
+            return Some((member_path, opened.map_err(ParseError::from)));
+        }
+        self.archive = None;
+        None
+    }
+}
+
+impl Iterator for DatasetReader {
+    type Item = (PathBuf, Result<Activity, ParseError>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((path, activities)) = &mut self.current {
+                match activities.next() {
+                    Some(item) => return Some((path.clone(), item)),
+                    None => self.current = None,
+                }
+            }
+            if self.archive.is_some() {
+                match self.next_member() {
+                    Some((path, Err(e))) => return Some((path, Err(e))),
+                    Some((_, Ok(()))) | None => continue,
+                }
+            }
+            match self.pending.pop_front()? {
+                Ok(path) => {
+                    if let Err(e) = self.open_next(&path) {
+                        return Some((path, Err(e)));
+                    }
+                }
+                Err((path, e)) => return Some((path, Err(e.into()))),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::{write::GzEncoder, Compression};
+    use std::io::Write;
+    use zip::write::{SimpleFileOptions, ZipWriter};
+
+    fn xml(ids: &[&str]) -> String {
+        let activities: String = ids
+            .iter()
+            .map(|id| format!("<iati-activity><iati-identifier>{id}</iati-identifier></iati-activity>"))
+            .collect();
+        format!("<iati-activities version=\"2.03\">{activities}</iati-activities>")
+    }
+
+    fn gzip(text: &str) -> Vec<u8> {
+        let mut enc = GzEncoder::new(Vec::new(), Compression::default());
+        enc.write_all(text.as_bytes()).unwrap();
+        enc.finish().unwrap()
+    }
+
+    #[test]
+    fn reads_plain_gzipped_and_zipped_files_in_a_tree() {
+        let dir = std::env::temp_dir().join(format!("iati-xml-dataset-{}", std::process::id()));
+        fs::create_dir_all(dir.join("nested")).unwrap();
+        fs::write(dir.join("a.xml"), xml(&["A-1", "A-2"])).unwrap();
+        fs::write(dir.join("nested/b.xml.gz"), gzip(&xml(&["B-1"]))).unwrap();
+        fs::write(dir.join("notes.txt"), "not a dataset").unwrap();
+
+        let mut zip = ZipWriter::new(File::create(dir.join("c.zip")).unwrap());
+        zip.start_file("one.xml", SimpleFileOptions::default()).unwrap();
+        zip.write_all(xml(&["C-1"]).as_bytes()).unwrap();
+        zip.start_file("README", SimpleFileOptions::default()).unwrap();
+        zip.start_file("two.xml.gz", SimpleFileOptions::default()).unwrap();
+        zip.write_all(&gzip(&xml(&["C-2"]))).unwrap();
+        zip.start_file("bad.xml", SimpleFileOptions::default()).unwrap();
+        zip.write_all(b"<iati-activities><iati-activity><transaction><value>x</value></transaction></iati-activity></iati-activities>").unwrap();
+        zip.finish().unwrap();
+
+        let items: Vec<(String, Result<String, ParseError>)> = DatasetReader::new([&dir, &dir.join("missing.xml")])
+            .map(|(path, a)| (path.strip_prefix(&dir).unwrap().display().to_string(), a.map(|a| a.iati_identifier)))
+            .collect();
+        let ok: Vec<(&str, &str)> = items
+            .iter()
+            .filter_map(|(path, a)| Some((path.as_str(), a.as_ref().ok()?.as_str())))
+            .collect();
+        assert_eq!(
+            ok,
+            vec![
+                ("a.xml", "A-1"),
+                ("a.xml", "A-2"),
+                ("c.zip/one.xml", "C-1"),
+                ("c.zip/two.xml.gz", "C-2"),
+                ("nested/b.xml.gz", "B-1"),
+            ]
+        );
+        let errors: Vec<&str> = items.iter().filter(|(_, a)| a.is_err()).map(|(path, _)| path.as_str()).collect();
+        assert_eq!(errors, vec!["c.zip/bad.xml", "missing.xml"]);
+        assert!(matches!(items.last(), Some((_, Err(ParseError::Io(_))))));
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn zip_member_names_stay_inside_the_archive() {
+        let dir = std::env::temp_dir().join(format!("iati-xml-dataset-names-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let archive = dir.join("d.zip");
+        let mut zip = ZipWriter::new(File::create(&archive).unwrap());
+        for (name, id) in [("../up.xml", "D-1"), ("/abs.xml", "D-2")] {
+            zip.start_file(name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(xml(&[id]).as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+
+        let paths: Vec<String> = DatasetReader::new([&archive])
+            .map(|(path, a)| {
+                assert!(a.is_ok());
+                path.display().to_string()
+            })
+            .collect();
+        let prefix = archive.display().to_string();
+        assert_eq!(paths, vec![format!("{prefix}/../up.xml"), format!("{prefix}//abs.xml")]);
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unreadable_entries_do_not_stop_the_walk() {
+        let dir = std::env::temp_dir().join(format!("iati-xml-dataset-walk-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a.xml"), xml(&["A-1"])).unwrap();
+        std::os::unix::fs::symlink("self.xml", dir.join("self.xml")).ok(); // ELOOP
+        std::os::unix::fs::symlink(".", dir.join("up")).ok(); // a cycle, walked once
+
+        let files: Vec<Result<String, String>> = dataset_files(&dir)
+            .into_iter()
+            .map(|f| match f {
+                Ok(path) => Ok(path.strip_prefix(&dir).unwrap().display().to_string()),
+                Err((path, _)) => Err(path.strip_prefix(&dir).unwrap().display().to_string()),
+            })
+            .collect();
+        assert_eq!(files, vec![Ok("a.xml".to_string()), Err("self.xml".to_string())]);
+
+        let items: Vec<_> = DatasetReader::new([&dir]).collect();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].1.as_ref().unwrap().iati_identifier, "A-1");
+        assert!(matches!(items[1], (ref path, Err(ParseError::Io(_))) if path.ends_with("self.xml")));
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn zip_members_over_the_limit_are_errors() {
+        let dir = std::env::temp_dir().join(format!("iati-xml-dataset-limit-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let archive = dir.join("e.zip");
+        let mut zip = ZipWriter::new(File::create(&archive).unwrap());
+        zip.start_file("big.xml", SimpleFileOptions::default()).unwrap();
+        zip.write_all(xml(&["E-1"]).as_bytes()).unwrap();
+        zip.finish().unwrap();
+
+        let size = xml(&["E-1"]).len() as u64;
+        let exact: Vec<_> = DatasetReader::new([&archive]).with_max_member_bytes(size).collect();
+        assert!(matches!(exact.as_slice(), [(_, Ok(_))]));
+        let over: Vec<_> = DatasetReader::new([&archive]).with_max_member_bytes(size - 1).collect();
+        assert!(matches!(over.as_slice(), [(path, Err(ParseError::Io(e)))]
+            if path.ends_with("big.xml") && e.kind() == io::ErrorKind::InvalidData));
+        fs::remove_dir_all(dir).ok();
+    }
+}
@@ -1,19 +1,34 @@
 use chrono::NaiveDate;
 use iati_types::{
+    budget::{Budget, BudgetStatus, BudgetType},
     money::{CurrencyCode, Money},
+    identifier::OtherIdentifier,
     tx::{Transaction, TxType},
-    Activity,
+    Activity, OrgRef, Sector,
 };
 use quick_xml::{
-    events::{attributes::Attributes, Event},
+    escape::{resolve_predefined_entity, EscapeError},
+    events::{attributes::Attributes, BytesRef, Event},
     name::QName,
-    Reader, Writer,
+    Reader,
 };
 
 use rust_decimal::Decimal;
 use std::str::FromStr;
 use thiserror::Error;
 
+pub mod dataset;
+pub mod organisation;
+pub mod schema;
+pub mod stream;
+pub mod version;
+
+pub use crate::dataset::{dataset_files, decompressed, is_dataset_file, DatasetReader, WalkError};
+pub use crate::organisation::{parse_organisation, parse_organisations, OrganisationReader};
+pub use crate::schema::{validate_schema, SchemaError, SchemaErrorKind};
+pub use crate::stream::ActivityReader;
+pub use crate::version::{IatiVersion, InvalidVersion};
+
 #[derive(Debug, Error)]
 pub enum ParseError {
     #[error("XML error: {0}")]
@@ -24,6 +39,8 @@ pub enum ParseError {
     Encoding(#[from] quick_xml::encoding::EncodingError),
     #[error("io error: {0}")]                // <-- add this line
     Io(#[from] std::io::Error),              // <-- and this line
+    #[error("zip error: {0}")]
+    Zip(#[from] zip::result::ZipError),
     #[error("missing required field: {0}")]
     Missing(&'static str),
     #[error("invalid decimal: {0}")]
@@ -43,20 +60,189 @@ struct TxBuild {
     amount: Option<Decimal>,
     value_currency: Option<CurrencyCode>,
     value_date: Option<NaiveDate>,
+    provider_org: Option<OrgRef>,
+    receiver_org: Option<OrgRef>,
+    ref_id: Option<String>,
+}
+
+/// Which organisation element an `OrgRef` is being read from.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum OrgSlot {
+    Reporting,
+    Provider,
+    Receiver,
+    /// 'recipient-org' in an organisation file's 'recipient-org-budget'.
+    Recipient,
+}
+
+/// Read `@ref` and the linked activity id (`@provider-activity-id` or
+/// `@receiver-activity-id`) from an organisation element. Blank values are dropped.
+fn parse_org_attrs(mut attrs: Attributes<'_>, slot: OrgSlot) -> Result<OrgRef, ParseError> {
+    let mut org = OrgRef::default();
+    for a in attrs.with_checks(false) {
+        let a = a?;
+        let key = a.key;
+        let val = a.unescape_value()?.trim().to_string();
+        if val.is_empty() {
+            continue;
+        }
+        match (slot, key.as_ref()) {
+            (_, b"ref") => org.ref_id = Some(val),
+            (OrgSlot::Provider, b"provider-activity-id") => org.activity_id = Some(val),
+            (OrgSlot::Receiver, b"receiver-activity-id") => org.activity_id = Some(val),
+            _ => {}
+        }
+    }
+    Ok(org)
+}
+
+/// Store a finished organisation element on the transaction or activity being built.
+fn finish_org(
+    slot: OrgSlot,
+    org: OrgRef,
+    tx_build: &mut Option<TxBuild>,
+    reporting_org: &mut Option<OrgRef>,
+) {
+    match (slot, tx_build.as_mut()) {
+        (OrgSlot::Provider, Some(b)) => b.provider_org = Some(org),
+        (OrgSlot::Receiver, Some(b)) => b.receiver_org = Some(org),
+        (OrgSlot::Reporting, _) => *reporting_org = Some(org),
+        _ => {}
+    }
+}
+
+fn org_slot(name: &[u8], in_tx: bool) -> Option<OrgSlot> {
+    match name {
+        b"reporting-org" if !in_tx => Some(OrgSlot::Reporting),
+        b"provider-org" if in_tx => Some(OrgSlot::Provider),
+        b"receiver-org" if in_tx => Some(OrgSlot::Receiver),
+        _ => None,
+    }
+}
+
+fn parse_tx_attrs(mut attrs: Attributes<'_>) -> Result<TxBuild, ParseError> {
+    let mut b = TxBuild::default();
+    for a in attrs.with_checks(false) {
+        let a = a?;
+        if a.key == QName(b"ref") {
+            let r = a.unescape_value()?.trim().to_string();
+            b.ref_id = (!r.is_empty()).then_some(r);
+        }
+    }
+    Ok(b)
 }
 
+/// Read an activity-level `<sector>`.
+fn parse_sector(mut attrs: Attributes<'_>) -> Result<Sector, ParseError> {
+    let mut sector = Sector::default();
+    let mut has_code = false;
+    for a in attrs.with_checks(false) {
+        let a = a?;
+        let val = a.unescape_value()?.trim().to_string();
+        match a.key.as_ref() {
+            b"code" => {
+                sector.code = val;
+                has_code = true;
+            }
+            b"vocabulary" => sector.vocabulary = Some(val),
+            b"percentage" => sector.percentage = Some(Decimal::from_str(&val)?),
+            _ => {}
+        }
+    }
+    if !has_code {
+        return Err(ParseError::Missing("sector/@code"));
+    }
+    Ok(sector)
+}
+
+/// Planned and actual start/end dates from `<activity-date>` elements.
+#[derive(Default)]
+struct ActivityDates {
+    planned_start: Option<NaiveDate>,
+    actual_start: Option<NaiveDate>,
+    planned_end: Option<NaiveDate>,
+    actual_end: Option<NaiveDate>,
+}
+
+impl ActivityDates {
+    /// Record an `<activity-date>`; ActivityDateType 1-4 = planned/actual start, planned/actual end.
+    /// 1.0x names (`start-planned`, `start-actual`, `end-planned`, `end-actual`) map to the same codes.
+    fn record(&mut self, mut attrs: Attributes<'_>) -> Result<(), ParseError> {
+        let mut code: Option<u16> = None;
+        let mut iso: Option<String> = None;
+        for a in attrs.with_checks(false) {
+            let a = a?;
+            if a.key == QName(b"type") {
+                code = Some(match a.unescape_value()?.trim() {
+                    "start-planned" => 1,
+                    "start-actual" => 2,
+                    "end-planned" => 3,
+                    "end-actual" => 4,
+                    other => other.parse()?,
+                });
+            }
+            if a.key == QName(b"iso-date") {
+                iso = Some(a.unescape_value()?.into_owned());
+            }
+        }
+        let code = code.ok_or(ParseError::Missing("activity-date/@type"))?;
+        let iso = iso.ok_or(ParseError::Missing("activity-date/@iso-date"))?;
+        let date = Some(NaiveDate::parse_from_str(iso.trim(), "%Y-%m-%d")?);
+        match code {
+            1 => self.planned_start = date,
+            2 => self.actual_start = date,
+            3 => self.planned_end = date,
+            4 => self.actual_end = date,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Actual dates win over planned ones.
+    fn start(&self) -> Option<NaiveDate> {
+        self.actual_start.or(self.planned_start)
+    }
+
+    fn end(&self) -> Option<NaiveDate> {
+        self.actual_end.or(self.planned_end)
+    }
+}
+
+/// Read `@ref` and `@type` from an `<other-identifier>`.
+fn parse_other_identifier(mut attrs: Attributes<'_>) -> Result<OtherIdentifier, ParseError> {
+    let mut other = OtherIdentifier::default();
+    let mut has_ref = false;
+    for a in attrs.with_checks(false) {
+        let a = a?;
+        if a.key == QName(b"ref") {
+            other.ref_id = a.unescape_value()?.trim().to_string();
+            has_ref = true;
+        }
+        if a.key == QName(b"type") {
+            other.type_code = Some(a.unescape_value()?.trim().to_string());
+        }
+    }
+    if !has_ref {
+        return Err(ParseError::Missing("other-identifier/@ref"));
+    }
+    Ok(other)
+}
+
+/// Numeric 2.x codes, or 1.0x letter codes (`D`, `IF`, ...) mapped onto the same `TxType`.
 fn parse_tx_type(mut attrs: Attributes<'_>, tx_build: &mut Option<TxBuild>) -> Result<(), ParseError> {
-    let mut code: Option<u16> = None;
+    let mut code: Option<TxType> = None;
     for a in attrs.with_checks(false) {
         let a = a?;
         if a.key == QName(b"code") {
-            code = Some(a.unescape_value()?.into_owned().parse()?);
+            let raw = a.unescape_value()?;
+            code = Some(match raw.trim().parse::<u16>() {
+                Ok(n) => TxType::from(n),
+                Err(e) => TxType::from_v1_code(&raw).ok_or(e)?,
+            });
         }
     }
     if let Some(b) = tx_build.as_mut() {
-        b.tx_type = Some(TxType::from(
-            code.ok_or(ParseError::Missing("transaction-type/@code"))?,
-        ));
+        b.tx_type = Some(code.ok_or(ParseError::Missing("transaction-type/@code"))?);
     }
     Ok(())
 }
@@ -76,10 +262,88 @@ fn parse_tx_date(mut attrs: Attributes<'_>, tx_build: &mut Option<TxBuild>) -> R
     Ok(())
 }
 
+/// Builder used while reading a <budget>
+#[derive(Default)]
+struct BudgetBuild {
+    budget_type: Option<BudgetType>,
+    status: Option<BudgetStatus>,
+    period_start: Option<NaiveDate>,
+    period_end: Option<NaiveDate>,
+    amount: Option<Decimal>,
+    value_currency: Option<CurrencyCode>,
+    value_date: Option<NaiveDate>,
+}
+
+fn parse_budget_attrs(mut attrs: Attributes<'_>) -> Result<BudgetBuild, ParseError> {
+    let mut b = BudgetBuild::default();
+    for a in attrs.with_checks(false) {
+        let a = a?;
+        if a.key == QName(b"type") {
+            b.budget_type = Some(BudgetType::from(a.unescape_value()?.trim().parse::<u16>()?));
+        }
+        if a.key == QName(b"status") {
+            b.status = Some(BudgetStatus::from(a.unescape_value()?.trim().parse::<u16>()?));
+        }
+    }
+    Ok(b)
+}
+
+/// Read `@iso-date` from a dated element such as `period-start` or `period-end`.
+fn parse_iso_date_attr(mut attrs: Attributes<'_>, field: &'static str) -> Result<NaiveDate, ParseError> {
+    for a in attrs.with_checks(false) {
+        let a = a?;
+        if a.key == QName(b"iso-date") {
+            let s = a.unescape_value()?.into_owned();
+            return Ok(NaiveDate::parse_from_str(&s, "%Y-%m-%d")?);
+        }
+    }
+    Err(ParseError::Missing(field))
+}
+
+/// Read `@currency` and `@value-date` from a `<value>` element.
+fn parse_value_attrs(
+    mut attrs: Attributes<'_>,
+) -> Result<(Option<CurrencyCode>, Option<NaiveDate>), ParseError> {
+    let mut currency = None;
+    let mut value_date = None;
+    for a in attrs.with_checks(false) {
+        let a = a?;
+        if a.key == QName(b"currency") {
+            currency = Some(CurrencyCode::from(a.unescape_value()?.into_owned()));
+        }
+        if a.key == QName(b"value-date") {
+            let s = a.unescape_value()?.into_owned();
+            value_date = Some(NaiveDate::parse_from_str(&s, "%Y-%m-%d")?);
+        }
+    }
+    Ok((currency, value_date))
+}
+
+/// Append the text of a `&name;` or `&#...;` reference in character data to `text`.
+/// Only the five predefined XML entities are known; IATI files declare no others.
+pub(crate) fn push_ref(text: &mut String, r: &BytesRef<'_>) -> Result<(), ParseError> {
+    if let Some(c) = r.resolve_char_ref()? {
+        text.push(c);
+        return Ok(());
+    }
+    let name = r.decode()?;
+    match resolve_predefined_entity(&name) {
+        Some(s) => text.push_str(s),
+        None => {
+            let err = EscapeError::UnrecognizedEntity(0..name.len(), name.into_owned());
+            return Err(quick_xml::Error::from(err).into());
+        }
+    }
+    Ok(())
+}
+
 /// Parse a single `<iati-activity>` fragment and its `<transaction>` children into an `Activity`.
+///
+/// Text is not trimmed per event: `Aid &amp; Dev` arrives as three pieces, and
+/// trimming each would drop the spaces around the entity. Captured values are
+/// trimmed once they are complete.
 pub fn parse_activity(xml: &str) -> Result<Activity, ParseError> {
     let mut reader = Reader::from_str(xml);
-    reader.config_mut().trim_text(true); // quick-xml 0.38+
 
     let mut buf = Vec::new();
     let mut current_text: Option<String> = None;
@@ -88,8 +352,15 @@ pub fn parse_activity(xml: &str) -> Result<Activity, ParseError> {
     let mut default_currency: Option<CurrencyCode> = None;
     let mut iati_identifier: Option<String> = None;
     let mut transactions: Vec<Transaction> = Vec::new();
+    let mut budgets: Vec<Budget> = Vec::new();
+    let mut reporting_org: Option<OrgRef> = None;
+    let mut other_identifiers: Vec<OtherIdentifier> = Vec::new();
+    let mut sectors: Vec<Sector> = Vec::new();
+    let mut dates = ActivityDates::default();
 
     let mut tx_build: Option<TxBuild> = None;
+    let mut budget_build: Option<BudgetBuild> = None;
+    let mut org_build: Option<(OrgSlot, OrgRef)> = None;
 
     loop {
         match reader.read_event_into(&mut buf)? {
@@ -107,7 +378,13 @@ pub fn parse_activity(xml: &str) -> Result<Activity, ParseError> {
                     current_text = Some(String::new());
                 }
                 b"transaction" => {
-                    tx_build = Some(TxBuild::default());
+                    tx_build = Some(parse_tx_attrs(e.attributes())?);
+                }
+                b"sector" if tx_build.is_none() => {
+                    sectors.push(parse_sector(e.attributes())?);
+                }
+                b"activity-date" => {
+                    dates.record(e.attributes())?;
                 }
                 b"transaction-type" => {
                     parse_tx_type(e.attributes(), &mut tx_build)?;
@@ -115,20 +392,41 @@ pub fn parse_activity(xml: &str) -> Result<Activity, ParseError> {
                 b"transaction-date" => {
                     parse_tx_date(e.attributes(), &mut tx_build)?;
                 }
+                b"budget" => {
+                    budget_build = Some(parse_budget_attrs(e.attributes())?);
+                }
+                b"period-start" => {
+                    if let Some(b) = budget_build.as_mut() {
+                        b.period_start = Some(parse_iso_date_attr(e.attributes(), "period-start/@iso-date")?);
+                    }
+                }
+                b"period-end" => {
+                    if let Some(b) = budget_build.as_mut() {
+                        b.period_end = Some(parse_iso_date_attr(e.attributes(), "period-end/@iso-date")?);
+                    }
+                }
+                b"other-identifier" => {
+                    other_identifiers.push(parse_other_identifier(e.attributes())?);
+                }
+                name @ (b"reporting-org" | b"provider-org" | b"receiver-org") => {
+                    if let Some(slot) = org_slot(name, tx_build.is_some()) {
+                        org_build = Some((slot, parse_org_attrs(e.attributes(), slot)?));
+                        // 1.0x puts the name directly in the element, without <narrative>
+                        current_text = Some(String::new());
+                    }
+                }
+                b"narrative" => {
+                    // Only the first narrative is kept as the organisation name.
+                    if matches!(&org_build, Some((_, org)) if org.name.is_none()) {
+                        current_text = Some(String::new());
+                    }
+                }
                 b"value" => {
                     current_text = Some(String::new()); // capture text later
                     if let Some(b) = tx_build.as_mut() {
-                        for a in e.attributes().with_checks(false) {
-                            let a = a?;
-                            if a.key == QName(b"currency") {
-                                b.value_currency =
-                                    Some(CurrencyCode::from(a.unescape_value()?.into_owned()));
-                            }
-                            if a.key == QName(b"value-date") {
-                                let s = a.unescape_value()?.into_owned();
-                                b.value_date = Some(NaiveDate::parse_from_str(&s, "%Y-%m-%d")?);
-                            }
-                        }
+                        (b.value_currency, b.value_date) = parse_value_attrs(e.attributes())?;
+                    } else if let Some(b) = budget_build.as_mut() {
+                        (b.value_currency, b.value_date) = parse_value_attrs(e.attributes())?;
                     }
                 }
                 _ => {}
@@ -136,26 +434,43 @@ pub fn parse_activity(xml: &str) -> Result<Activity, ParseError> {
 
             // ---------- <empty/> ----------
             Event::Empty(e) => match e.name().as_ref() {
+                b"sector" if tx_build.is_none() => {
+                    sectors.push(parse_sector(e.attributes())?);
+                }
+                b"activity-date" => {
+                    dates.record(e.attributes())?;
+                }
                 b"transaction-type" => {
                     parse_tx_type(e.attributes(), &mut tx_build)?;
                 }
                 b"transaction-date" => {
                     parse_tx_date(e.attributes(), &mut tx_build)?;
                 }
+                b"period-start" => {
+                    if let Some(b) = budget_build.as_mut() {
+                        b.period_start = Some(parse_iso_date_attr(e.attributes(), "period-start/@iso-date")?);
+                    }
+                }
+                b"period-end" => {
+                    if let Some(b) = budget_build.as_mut() {
+                        b.period_end = Some(parse_iso_date_attr(e.attributes(), "period-end/@iso-date")?);
+                    }
+                }
+                b"other-identifier" => {
+                    other_identifiers.push(parse_other_identifier(e.attributes())?);
+                }
+                name @ (b"reporting-org" | b"provider-org" | b"receiver-org") => {
+                    if let Some(slot) = org_slot(name, tx_build.is_some()) {
+                        let org = parse_org_attrs(e.attributes(), slot)?;
+                        finish_org(slot, org, &mut tx_build, &mut reporting_org);
+                    }
+                }
                 b"value" => {
                     // Handle `<value .../>` as empty (no amount text)
                     if let Some(b) = tx_build.as_mut() {
-                        for a in e.attributes().with_checks(false) {
-                            let a = a?;
-                            if a.key == QName(b"currency") {
-                                b.value_currency =
-                                    Some(CurrencyCode::from(a.unescape_value()?.into_owned()));
-                            }
-                            if a.key == QName(b"value-date") {
-                                let s = a.unescape_value()?.into_owned();
-                                b.value_date = Some(NaiveDate::parse_from_str(&s, "%Y-%m-%d")?);
-                            }
-                        }
+                        (b.value_currency, b.value_date) = parse_value_attrs(e.attributes())?;
+                    } else if let Some(b) = budget_build.as_mut() {
+                        (b.value_currency, b.value_date) = parse_value_attrs(e.attributes())?;
                     }
                 }
                 _ => {}
@@ -167,6 +482,11 @@ pub fn parse_activity(xml: &str) -> Result<Activity, ParseError> {
                     s.push_str(&t.decode()?); // Cow<str> -> &str
                 }
             }
+            Event::GeneralRef(r) => {
+                if let Some(s) = current_text.as_mut() {
+                    push_ref(s, &r)?;
+                }
+            }
 
             // ---------- </end> ----------
             Event::End(e) => match e.name().as_ref() {
@@ -174,11 +494,45 @@ pub fn parse_activity(xml: &str) -> Result<Activity, ParseError> {
                     let val = current_text.take().unwrap_or_default();
                     iati_identifier = Some(val.trim().to_string());
                 }
+                b"narrative" => {
+                    if let (Some((_, org)), Some(text)) = (org_build.as_mut(), current_text.take()) {
+                        let text = text.trim();
+                        if !text.is_empty() {
+                            org.name = Some(text.to_string());
+                        }
+                    }
+                }
+                b"reporting-org" | b"provider-org" | b"receiver-org" => {
+                    if let Some((slot, mut org)) = org_build.take() {
+                        // Still capturing: no narrative was seen, so this is 1.0x plain text.
+                        if let Some(text) = current_text.take()
+                            && org.name.is_none()
+                            && !text.trim().is_empty()
+                        {
+                            org.name = Some(text.trim().to_string());
+                        }
+                        finish_org(slot, org, &mut tx_build, &mut reporting_org);
+                    }
+                }
                 b"value" => {
+                    let val = current_text.take().unwrap_or_default();
                     if let Some(b) = tx_build.as_mut() {
-                        let val = current_text.take().unwrap_or_default();
-                        let dec = Decimal::from_str(val.trim())?;
-                        b.amount = Some(dec);
+                        b.amount = Some(Decimal::from_str(val.trim())?);
+                    } else if let Some(b) = budget_build.as_mut() {
+                        b.amount = Some(Decimal::from_str(val.trim())?);
+                    }
+                }
+                b"budget" => {
+                    if let Some(b) = budget_build.take() {
+                        let start = b.period_start.ok_or(ParseError::Missing("period-start/@iso-date"))?;
+                        let end = b.period_end.ok_or(ParseError::Missing("period-end/@iso-date"))?;
+                        let mut money = Money::new(b.amount.ok_or(ParseError::Missing("budget/value"))?);
+                        money.currency = b.value_currency;
+                        money.value_date = b.value_date;
+                        let budget = Budget::new(start, end, money)
+                            .with_type(b.budget_type.unwrap_or_default())
+                            .with_status(b.status.unwrap_or_default());
+                        budgets.push(budget);
                     }
                 }
                 b"transaction" => {
@@ -188,7 +542,10 @@ pub fn parse_activity(xml: &str) -> Result<Activity, ParseError> {
                         let mut money = Money::new(b.amount.ok_or(ParseError::Missing("value"))?);
                         money.currency = b.value_currency;
                         money.value_date = b.value_date;
-                        let tx = Transaction::new(tx_type, date, money);
+                        let mut tx = Transaction::new(tx_type, date, money);
+                        tx.provider_org = b.provider_org;
+                        tx.receiver_org = b.receiver_org;
+                        tx.ref_id = b.ref_id;
                         transactions.push(tx);
                     }
                 }
@@ -205,67 +562,41 @@ pub fn parse_activity(xml: &str) -> Result<Activity, ParseError> {
     let mut activity = Activity::new(id);
     activity.default_currency = default_currency;
     activity.transactions = transactions;
+    activity.budgets = budgets;
+    activity.reporting_org = reporting_org;
+    activity.other_identifiers = other_identifiers;
+    activity.sectors = sectors;
+    activity.activity_start = dates.start();
+    activity.activity_end = dates.end();
     Ok(activity)
 }
 
-/// Parse a full `<iati-activities>` document, returning one `Activity` per `<iati-activity>`.
-pub fn parse_activities(xml: &str) -> Result<Vec<Activity>, ParseError> {
-    let mut reader = Reader::from_str(xml);
-    reader.config_mut().trim_text(true);
-
-    let mut buf = Vec::new();
-    let mut activities: Vec<Activity> = Vec::new();
-
-    // When we enter an <iati-activity>, capture all events until its matching </iati-activity>
-    let mut depth: usize = 0;
-    let mut writer: Option<Writer<Vec<u8>>> = None;
+/// A parsed `<iati-activities>` document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActivitiesDocument {
+    /// Declared `@version`; `None` if absent or unreadable.
+    pub version: Option<IatiVersion>,
+    pub activities: Vec<Activity>,
+}
 
-    loop {
-        match reader.read_event_into(&mut buf)? {
-            Event::Start(e) if e.name().as_ref() == b"iati-activity" => {
-                depth = 1;
-                let mut w = Writer::new(Vec::<u8>::new());
-                w.write_event(Event::Start(e.to_owned()))?;
-                writer = Some(w);
-            }
-            Event::Empty(e) if e.name().as_ref() == b"iati-activity" => {
-                // Rare degenerate case: <iati-activity .../> (no children)
-                let mut w = Writer::new(Vec::<u8>::new());
-                w.write_event(Event::Empty(e.to_owned()))?;
-                let bytes = w.into_inner();
-                let frag = String::from_utf8(bytes).unwrap_or_default();
-                activities.push(parse_activity(&frag)?);
-            }
-            ev @ Event::Start(_) | ev @ Event::Empty(_) | ev @ Event::Text(_) | ev @ Event::CData(_) | ev @ Event::Comment(_) | ev @ Event::PI(_) => {
-                if let Some(w) = writer.as_mut() {
-                    if let Event::Start(ref e) = ev {
-                        if e.name().as_ref() == b"iati-activity" {
-                            depth += 1;
-                        }
-                    }
-                    w.write_event(ev.to_owned())?;
-                }
-            }
-            Event::End(e) => {
-                if let Some(w) = writer.as_mut() {
-                    w.write_event(Event::End(e.to_owned()))?;
-                    if e.name().as_ref() == b"iati-activity" {
-                        depth = depth.saturating_sub(1);
-                        if depth == 0 {
-                            let bytes = writer.take().unwrap().into_inner();
-                            let frag = String::from_utf8(bytes).unwrap_or_default();
-                            activities.push(parse_activity(&frag)?);
-                        }
-                    }
-                }
-            }
-            Event::Eof => break,
-            _ => {}
-        }
-        buf.clear();
-    }
+/// Parse a full `<iati-activities>` document along with its declared version.
+///
+/// 1.0x forms are normalised into the 2.03 model whatever the declared version,
+/// since older files often declare it wrongly: letter transaction-type codes,
+/// organisation names as plain text, and named activity-date types.
+pub fn parse_document(xml: &str) -> Result<ActivitiesDocument, ParseError> {
+    let mut reader = ActivityReader::new(xml.as_bytes());
+    let activities = reader.by_ref().collect::<Result<Vec<_>, _>>()?;
+    Ok(ActivitiesDocument {
+        version: reader.version(),
+        activities,
+    })
+}
 
-    Ok(activities)
+/// Parse a full `<iati-activities>` document, returning one `Activity` per `<iati-activity>`.
+/// Stops at the first error; use `ActivityReader` to continue past bad activities.
+pub fn parse_activities(xml: &str) -> Result<Vec<Activity>, ParseError> {
+    ActivityReader::new(xml.as_bytes()).collect()
 }
 
 #[cfg(test)]
@@ -348,4 +679,276 @@ mod tests {
         let err = parse_activity(xml).unwrap_err();
         assert!(matches!(err, ParseError::Missing("value")));
     }
+
+    #[test]
+    fn parse_budgets_alongside_transactions() {
+        let xml = r#"
+        <iati-activity default-currency="EUR">
+            <iati-identifier>ACT-B</iati-identifier>
+            <budget type="2" status="2">
+                <period-start iso-date="2023-01-01"/>
+                <period-end iso-date="2023-12-31"/>
+                <value currency="USD" value-date="2023-01-01">1000.00</value>
+            </budget>
+            <budget>
+                <period-start iso-date="2024-01-01"/>
+                <period-end iso-date="2024-12-31"/>
+                <value value-date="2024-01-01">500</value>
+            </budget>
+            <transaction>
+                <transaction-type code="3"/>
+                <transaction-date iso-date="2023-05-01"/>
+                <value value-date="2023-05-01">10.00</value>
+            </transaction>
+        </iati-activity>
+        "#;
+
+        let act = parse_activity(xml).expect("parsed");
+        assert_eq!(act.transactions.len(), 1);
+        assert_eq!(act.budgets.len(), 2);
+
+        let b = &act.budgets[0];
+        assert_eq!(b.budget_type, BudgetType::Revised);
+        assert_eq!(b.status, BudgetStatus::Committed);
+        assert_eq!(b.period_end, NaiveDate::from_ymd_opt(2023, 12, 31).unwrap());
+        assert_eq!(b.value.amount, Decimal::new(100000, 2));
+        assert_eq!(b.value.currency.as_ref().unwrap().0, "USD");
+
+        let b = &act.budgets[1];
+        assert_eq!(b.budget_type, BudgetType::Original);
+        assert_eq!(b.status, BudgetStatus::Indicative);
+        assert!(b.value.currency.is_none());
+    }
+
+    #[test]
+    fn parse_orgs_and_linked_activity_ids() {
+        let xml = r#"
+        <iati-activity default-currency="USD">
+            <iati-identifier>GB-1-PROJ</iati-identifier>
+            <reporting-org ref="GB-1" type="10">
+                <narrative>UK Donor</narrative>
+                <narrative xml:lang="fr">Donateur</narrative>
+            </reporting-org>
+            <transaction>
+                <transaction-type code="1"/>
+                <transaction-date iso-date="2023-01-01"/>
+                <value>100</value>
+                <provider-org ref="XM-DAC-1" provider-activity-id="XM-DAC-1-FUND">
+                    <narrative>Pooled Fund</narrative>
+                </provider-org>
+                <receiver-org ref="GB-1"/>
+            </transaction>
+            <transaction>
+                <transaction-type code="3"/>
+                <transaction-date iso-date="2023-02-01"/>
+                <value>40</value>
+                <receiver-org ref="KE-NGO-7" receiver-activity-id="KE-NGO-7-X" provider-activity-id="ignored"/>
+            </transaction>
+        </iati-activity>
+        "#;
+
+        let act = parse_activity(xml).expect("parsed");
+        let rep = act.reporting_org.as_ref().unwrap();
+        assert_eq!(rep.ref_id.as_deref(), Some("GB-1"));
+        assert_eq!(rep.name.as_deref(), Some("UK Donor"));
+        assert!(rep.activity_id.is_none());
+
+        let prov = act.transactions[0].provider_org.as_ref().unwrap();
+        assert_eq!(prov.name.as_deref(), Some("Pooled Fund"));
+        assert_eq!(prov.activity_id.as_deref(), Some("XM-DAC-1-FUND"));
+        assert_eq!(
+            act.transactions[0].receiver_org.as_ref().unwrap().ref_id.as_deref(),
+            Some("GB-1")
+        );
+
+        assert!(act.identifier().is_ok());
+        assert!(act.transactions[1].provider_org.is_none());
+        let recv = act.transactions[1].receiver_org.as_ref().unwrap();
+        assert_eq!(recv.activity_id.as_deref(), Some("KE-NGO-7-X"));
+        assert!(recv.name.is_none());
+    }
+
+    #[test]
+    fn parse_sectors_dates_and_tx_refs() {
+        let xml = r#"
+        <iati-activity default-currency="USD">
+            <iati-identifier>ACT-S</iati-identifier>
+            <activity-date type="1" iso-date="2023-01-01"/>
+            <activity-date type="2" iso-date="2023-02-01"><narrative>Started late</narrative></activity-date>
+            <activity-date type="3" iso-date="2024-12-31"/>
+            <sector code="11110" vocabulary="1" percentage="60"/>
+            <sector code="111" vocabulary="2" percentage="40.5"/>
+            <transaction ref="TX-1">
+                <transaction-type code="3"/>
+                <transaction-date iso-date="2023-05-01"/>
+                <value>1</value>
+                <sector code="99999"/>
+            </transaction>
+        </iati-activity>
+        "#;
+
+        let act = parse_activity(xml).expect("parsed");
+        assert_eq!(act.activity_start, NaiveDate::from_ymd_opt(2023, 2, 1));
+        assert_eq!(act.activity_end, NaiveDate::from_ymd_opt(2024, 12, 31));
+        assert_eq!(act.sectors.len(), 2);
+        assert_eq!(act.sectors[0].code, "11110");
+        assert_eq!(act.sectors[1].vocabulary.as_deref(), Some("2"));
+        assert_eq!(act.sectors[1].percentage, Some(Decimal::new(405, 1)));
+        assert_eq!(act.transactions[0].ref_id.as_deref(), Some("TX-1"));
+    }
+
+    #[test]
+    fn parse_other_identifiers() {
+        let xml = r#"
+        <iati-activity>
+            <iati-identifier>GB-OLD-1-X</iati-identifier>
+            <reporting-org ref="GB-COH-2"/>
+            <other-identifier ref="GB-OLD-1" type="B1">
+                <owner-org ref="GB-OLD-1"><narrative>Old name</narrative></owner-org>
+            </other-identifier>
+            <other-identifier ref="INTERNAL-7" type="A1"/>
+        </iati-activity>
+        "#;
+
+        let act = parse_activity(xml).expect("parsed");
+        assert_eq!(act.other_identifiers.len(), 2);
+        assert!(act.other_identifiers[0].is_previous_reporting_org());
+        assert_eq!(act.other_identifiers[1].ref_id, "INTERNAL-7");
+        assert_eq!(act.identifier().unwrap().activity_suffix(), "X");
+    }
+
+    #[test]
+    fn parse_v1_document_and_detect_version() {
+        let xml = r#"
+        <iati-activities version="1.05">
+          <iati-activity default-currency="GBP">
+            <iati-identifier>GB-1-A</iati-identifier>
+            <reporting-org ref="GB-1" type="10">Old Style Org</reporting-org>
+            <activity-date type="start-actual" iso-date="2012-04-01"/>
+            <activity-date type="end-planned" iso-date="2014-03-31"/>
+            <transaction>
+              <transaction-type code="IF">Incoming Funds</transaction-type>
+              <provider-org ref="GB-2" provider-activity-id="GB-2-X">Funder</provider-org>
+              <value value-date="2012-05-01">100</value>
+              <transaction-date iso-date="2012-05-01"/>
+            </transaction>
+            <transaction>
+              <transaction-type code="D"/>
+              <value value-date="2012-06-01">40</value>
+              <transaction-date iso-date="2012-06-01"/>
+            </transaction>
+          </iati-activity>
+        </iati-activities>
+        "#;
+
+        let doc = parse_document(xml).expect("parsed");
+        assert_eq!(doc.version, Some(IatiVersion::V1_05));
+        assert!(doc.version.unwrap().is_v1());
+        let act = &doc.activities[0];
+        assert_eq!(act.reporting_org.as_ref().unwrap().name.as_deref(), Some("Old Style Org"));
+        assert_eq!(act.activity_start, NaiveDate::from_ymd_opt(2012, 4, 1));
+        assert_eq!(act.activity_end, NaiveDate::from_ymd_opt(2014, 3, 31));
+        assert_eq!(act.transactions[0].tx_type, TxType::IncomingFunds);
+        assert_eq!(act.transactions[1].tx_type, TxType::Disbursement);
+        let provider = act.transactions[0].provider_org.as_ref().unwrap();
+        assert_eq!(provider.name.as_deref(), Some("Funder"));
+        assert_eq!(provider.activity_id.as_deref(), Some("GB-2-X"));
+
+        let doc = parse_document(r#"<iati-activities version="2.03"></iati-activities>"#).unwrap();
+        assert_eq!(doc.version, Some(IatiVersion::V2_03));
+        assert_eq!(doc.version.unwrap().to_string(), "2.03");
+        assert!(doc.activities.is_empty());
+        assert_eq!(parse_document("<iati-activities/>").unwrap().version, None);
+    }
+
+    #[test]
+    fn entities_in_narratives_keep_their_spaces() {
+        let xml = r#"<iati-activities version="2.03">
+            <iati-activity default-currency="EUR">
+                <iati-identifier>XM-1</iati-identifier>
+                <reporting-org ref="XM"><narrative>Aid &amp; Dev</narrative></reporting-org>
+                <transaction>
+                    <transaction-type code="3"/>
+                    <transaction-date iso-date="2024-01-01"/>
+                    <value>1</value>
+                    <receiver-org><narrative>R &lt;D&gt; &#233;</narrative></receiver-org>
+                </transaction>
+            </iati-activity>
+        </iati-activities>"#;
+
+        // streamed, the fragment is re-serialised before parsing
+        let acts: Vec<Activity> = ActivityReader::new(xml.as_bytes()).collect::<Result<_, _>>().unwrap();
+        for act in [&acts[0], &parse_activity(xml).unwrap()] {
+            assert_eq!(act.reporting_org.as_ref().unwrap().name.as_deref(), Some("Aid & Dev"));
+            let receiver = act.transactions[0].receiver_org.as_ref().unwrap();
+            assert_eq!(receiver.name.as_deref(), Some("R <D> é"));
+        }
+
+        let unknown = xml.replace("&amp;", "&nbsp;");
+        assert!(matches!(parse_activity(&unknown), Err(ParseError::Xml(_))));
+    }
+
+    #[test]
+    fn v1_plain_text_org_names() {
+        let xml = r#"
+        <iati-activity>
+          <iati-identifier>GB-1-A</iati-identifier>
+          <reporting-org ref="GB-1">  Plain Reporter </reporting-org>
+          <transaction>
+            <transaction-type code="C"/>
+            <transaction-date iso-date="2012-05-01"/>
+            <value>5</value>
+            <provider-org ref="GB-2">Plain Provider</provider-org>
+            <receiver-org ref="GB-3"><narrative>Narrative Receiver</narrative></receiver-org>
+          </transaction>
+        </iati-activity>
+        "#;
+        let act = parse_activity(xml).expect("parsed");
+        assert_eq!(act.reporting_org.unwrap().name.as_deref(), Some("Plain Reporter"));
+        let tx = &act.transactions[0];
+        assert_eq!(tx.tx_type, TxType::OutgoingCommitment);
+        assert_eq!(tx.provider_org.as_ref().unwrap().name.as_deref(), Some("Plain Provider"));
+        assert_eq!(tx.receiver_org.as_ref().unwrap().name.as_deref(), Some("Narrative Receiver"));
+    }
+
+    #[test]
+    fn v1_named_activity_date_types() {
+        let dates = |elements: &str| {
+            let xml = format!("<iati-activity><iati-identifier>GB-1-A</iati-identifier>{elements}</iati-activity>");
+            let act = parse_activity(&xml).expect("parsed");
+            (act.activity_start, act.activity_end)
+        };
+        let d = |y, m, day| NaiveDate::from_ymd_opt(y, m, day);
+
+        // planned dates are used when there is no actual one
+        assert_eq!(
+            dates(r#"<activity-date type="start-planned" iso-date="2012-01-01"/><activity-date type="end-planned" iso-date="2013-01-01"/>"#),
+            (d(2012, 1, 1), d(2013, 1, 1))
+        );
+        // actual dates win, in either order
+        assert_eq!(
+            dates(
+                r#"<activity-date type="end-actual" iso-date="2013-06-30"/>
+                   <activity-date type="start-actual" iso-date="2012-02-01"/>
+                   <activity-date type="start-planned" iso-date="2012-01-01"/>
+                   <activity-date type="end-planned" iso-date="2013-01-01"/>"#
+            ),
+            (d(2012, 2, 1), d(2013, 6, 30))
+        );
+        let bad = "<iati-activity><iati-identifier>GB-1-A</iati-identifier><activity-date type=\"begun\" iso-date=\"2012-01-01\"/></iati-activity>";
+        assert!(matches!(parse_activity(bad), Err(ParseError::Int(_))));
+    }
+
+    #[test]
+    fn parse_document_detects_version() {
+        let version = |root: &str| parse_document(&format!("{root}</iati-activities>")).unwrap().version;
+        assert_eq!(version(r#"<iati-activities version="1.01">"#), Some(IatiVersion::V1_01));
+        assert_eq!(version(r#"<iati-activities version=" 2.3 ">"#), Some(IatiVersion::V2_03));
+        assert_eq!(version(r#"<iati-activities version="2.02" generated-datetime="2020-01-01T00:00:00">"#), Some(IatiVersion::V2_02));
+        assert_eq!(version(r#"<iati-activities version="two">"#), None);
+        assert_eq!(version("<iati-activities>"), None);
+        assert!(!IatiVersion::V2_01.is_v1());
+        assert_eq!("2".parse::<IatiVersion>(), Err(InvalidVersion("2".into())));
+        assert_eq!("x.y".parse::<IatiVersion>().unwrap_err().to_string(), "invalid IATI version \"x.y\"");
+    }
 }
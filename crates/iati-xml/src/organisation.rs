@@ -0,0 +1,449 @@
+//! Parser for the IATI Organisation Standard (`<iati-organisations>`).
+
+use chrono::NaiveDate;
+use iati_types::{
+    budget::BudgetStatus,
+    money::{CurrencyCode, Money},
+    organisation::{DocumentLink, OrgBudget, Organisation, RecipientCountryBudget, RecipientOrgBudget},
+    OrgRef,
+};
+use quick_xml::{
+    events::{attributes::Attributes, BytesStart, Event},
+    name::QName,
+    Reader,
+};
+use rust_decimal::Decimal;
+use std::io::BufRead;
+use std::str::FromStr;
+
+use crate::stream::FragmentReader;
+use crate::{parse_iso_date_attr, parse_org_attrs, parse_value_attrs, push_ref, IatiVersion, OrgSlot, ParseError};
+
+/// Builder used while reading one of the period-based elements
+/// (`total-budget`, `recipient-org-budget`, `recipient-country-budget`, `total-expenditure`).
+#[derive(Default)]
+struct PeriodBuild {
+    status: Option<BudgetStatus>,
+    period_start: Option<NaiveDate>,
+    period_end: Option<NaiveDate>,
+    amount: Option<Decimal>,
+    value_currency: Option<CurrencyCode>,
+    value_date: Option<NaiveDate>,
+    recipient_org: Option<OrgRef>,
+    country: Option<String>,
+}
+
+impl PeriodBuild {
+    fn finish(self) -> Result<(OrgBudget, Option<OrgRef>, Option<String>), ParseError> {
+        let start = self.period_start.ok_or(ParseError::Missing("period-start/@iso-date"))?;
+        let end = self.period_end.ok_or(ParseError::Missing("period-end/@iso-date"))?;
+        let mut money = Money::new(self.amount.ok_or(ParseError::Missing("value"))?);
+        money.currency = self.value_currency;
+        money.value_date = self.value_date;
+        let mut budget = OrgBudget::new(start, end, money);
+        budget.status = self.status.unwrap_or_default();
+        Ok((budget, self.recipient_org, self.country))
+    }
+}
+
+const PERIOD_ELEMENTS: [&[u8]; 4] = [
+    b"total-budget",
+    b"recipient-org-budget",
+    b"recipient-country-budget",
+    b"total-expenditure",
+];
+
+fn attr(mut attrs: Attributes<'_>, key: &[u8]) -> Result<Option<String>, ParseError> {
+    for a in attrs.with_checks(false) {
+        let a = a?;
+        if a.key == QName(key) {
+            return Ok(Some(a.unescape_value()?.trim().to_string()));
+        }
+    }
+    Ok(None)
+}
+
+/// Parser state for one `<iati-organisation>`.
+#[derive(Default)]
+struct OrgParser {
+    /// Names of the currently open elements, outermost first.
+    path: Vec<Vec<u8>>,
+    text: Option<String>,
+    identifier: Option<String>,
+    org: Option<Organisation>,
+    name: Option<String>,
+    default_currency: Option<CurrencyCode>,
+    reporting_org: Option<OrgRef>,
+    org_ref: Option<OrgRef>,
+    period: Option<PeriodBuild>,
+    doc: Option<DocumentLink>,
+}
+
+impl OrgParser {
+    fn parent(&self) -> &[u8] {
+        self.path.last().map(Vec::as_slice).unwrap_or_default()
+    }
+
+    fn grandparent(&self) -> &[u8] {
+        self.path.iter().rev().nth(1).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Does the narrative about to open belong to something we keep, without a name yet?
+    fn wants_narrative(&self) -> bool {
+        match self.parent() {
+            b"name" => self.grandparent() == b"iati-organisation" && self.name.is_none(),
+            b"reporting-org" | b"recipient-org" => matches!(&self.org_ref, Some(o) if o.name.is_none()),
+            b"title" => matches!(&self.doc, Some(d) if d.title.is_none()),
+            _ => false,
+        }
+    }
+
+    fn open(&mut self, e: &BytesStart<'_>) -> Result<(), ParseError> {
+        let name = e.name();
+        let parent = self.parent().to_vec();
+        let parent = parent.as_slice();
+        match name.as_ref() {
+            b"iati-organisation" => {
+                if let Some(c) = attr(e.attributes(), b"default-currency")? {
+                    self.default_currency = Some(CurrencyCode::from(c));
+                }
+            }
+            b"organisation-identifier" => self.text = Some(String::new()),
+            b"reporting-org" if parent == b"iati-organisation" => {
+                self.org_ref = Some(parse_org_attrs(e.attributes(), OrgSlot::Reporting)?);
+            }
+            b"recipient-org" if parent == b"recipient-org-budget" => {
+                self.org_ref = Some(parse_org_attrs(e.attributes(), OrgSlot::Recipient)?);
+            }
+            b"recipient-country" if parent == b"recipient-country-budget" => {
+                if let Some(p) = self.period.as_mut() {
+                    p.country = attr(e.attributes(), b"code")?;
+                }
+            }
+            n if parent == b"iati-organisation" && PERIOD_ELEMENTS.contains(&n) => {
+                let status = attr(e.attributes(), b"status")?
+                    .map(|s| s.parse::<u16>())
+                    .transpose()?
+                    .map(BudgetStatus::from);
+                self.period = Some(PeriodBuild {
+                    status,
+                    ..Default::default()
+                });
+            }
+            b"period-start" if PERIOD_ELEMENTS.contains(&parent) => {
+                let date = parse_iso_date_attr(e.attributes(), "period-start/@iso-date")?;
+                if let Some(p) = self.period.as_mut() {
+                    p.period_start = Some(date);
+                }
+            }
+            b"period-end" if PERIOD_ELEMENTS.contains(&parent) => {
+                let date = parse_iso_date_attr(e.attributes(), "period-end/@iso-date")?;
+                if let Some(p) = self.period.as_mut() {
+                    p.period_end = Some(date);
+                }
+            }
+            // `value` inside budget-line / expense-line is ignored.
+            b"value" if PERIOD_ELEMENTS.contains(&parent) => {
+                self.text = Some(String::new());
+                if let Some(p) = self.period.as_mut() {
+                    (p.value_currency, p.value_date) = parse_value_attrs(e.attributes())?;
+                }
+            }
+            b"document-link" if parent == b"iati-organisation" => {
+                self.doc = Some(DocumentLink {
+                    url: attr(e.attributes(), b"url")?.ok_or(ParseError::Missing("document-link/@url"))?,
+                    format: attr(e.attributes(), b"format")?,
+                    ..Default::default()
+                });
+            }
+            b"category" if parent == b"document-link" => {
+                if let (Some(d), Some(code)) = (self.doc.as_mut(), attr(e.attributes(), b"code")?) {
+                    d.categories.push(code);
+                }
+            }
+            b"language" if parent == b"document-link" => {
+                if let (Some(d), Some(code)) = (self.doc.as_mut(), attr(e.attributes(), b"code")?) {
+                    d.languages.push(code);
+                }
+            }
+            b"document-date" if parent == b"document-link" => {
+                let date = parse_iso_date_attr(e.attributes(), "document-date/@iso-date")?;
+                if let Some(d) = self.doc.as_mut() {
+                    d.document_date = Some(date);
+                }
+            }
+            b"narrative" if self.wants_narrative() => self.text = Some(String::new()),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Called after the element has been popped from `path`.
+    fn close(&mut self, name: &[u8]) -> Result<(), ParseError> {
+        let parent = self.parent().to_vec();
+        let parent = parent.as_slice();
+        match name {
+            b"organisation-identifier" => {
+                self.identifier = self.text.take().map(|t| t.trim().to_string());
+            }
+            b"narrative" => {
+                let Some(text) = self.text.take() else {
+                    return Ok(());
+                };
+                let text = text.trim();
+                if text.is_empty() {
+                    return Ok(());
+                }
+                match parent {
+                    b"name" => self.name = Some(text.to_string()),
+                    b"reporting-org" | b"recipient-org" => {
+                        if let Some(o) = self.org_ref.as_mut() {
+                            o.name = Some(text.to_string());
+                        }
+                    }
+                    b"title" => {
+                        if let Some(d) = self.doc.as_mut() {
+                            d.title = Some(text.to_string());
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            b"value" if PERIOD_ELEMENTS.contains(&parent) => {
+                let val = self.text.take().unwrap_or_default();
+                if let Some(p) = self.period.as_mut()
+                    && !val.trim().is_empty()
+                {
+                    p.amount = Some(Decimal::from_str(val.trim())?);
+                }
+            }
+            b"reporting-org" if parent == b"iati-organisation" => {
+                self.reporting_org = self.org_ref.take();
+            }
+            b"recipient-org" if parent == b"recipient-org-budget" => {
+                if let Some(p) = self.period.as_mut() {
+                    p.recipient_org = self.org_ref.take();
+                }
+            }
+            n if parent == b"iati-organisation" && PERIOD_ELEMENTS.contains(&n) => {
+                let Some(p) = self.period.take() else {
+                    return Ok(());
+                };
+                let (budget, recipient_org, country) = p.finish()?;
+                let org = self.org.get_or_insert_with(|| Organisation::new(""));
+                match n {
+                    b"total-budget" => org.total_budgets.push(budget),
+                    b"total-expenditure" => org.total_expenditures.push(budget),
+                    b"recipient-org-budget" => org.recipient_org_budgets.push(RecipientOrgBudget {
+                        recipient_org: recipient_org.ok_or(ParseError::Missing("recipient-org"))?,
+                        budget,
+                    }),
+                    _ => org.recipient_country_budgets.push(RecipientCountryBudget {
+                        country: country.ok_or(ParseError::Missing("recipient-country/@code"))?,
+                        budget,
+                    }),
+                }
+            }
+            b"document-link" if parent == b"iati-organisation" => {
+                if let Some(d) = self.doc.take() {
+                    self.org.get_or_insert_with(|| Organisation::new("")).document_links.push(d);
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Organisation, ParseError> {
+        let id = self.identifier.ok_or(ParseError::Missing("organisation-identifier"))?;
+        let mut org = self.org.unwrap_or_else(|| Organisation::new(""));
+        org.organisation_identifier = id;
+        org.name = self.name;
+        org.default_currency = self.default_currency;
+        org.reporting_org = self.reporting_org;
+        Ok(org)
+    }
+}
+
+/// Parse a single `<iati-organisation>` fragment into an `Organisation`.
+pub fn parse_organisation(xml: &str) -> Result<Organisation, ParseError> {
+    // Untrimmed, as in `parse_activity`, so entities keep their surrounding spaces.
+    let mut reader = Reader::from_str(xml);
+
+    let mut buf = Vec::new();
+    let mut p = OrgParser::default();
+
+    loop {
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(e) => {
+                p.open(&e)?;
+                p.path.push(e.name().as_ref().to_vec());
+            }
+            Event::Empty(e) => {
+                p.open(&e)?;
+                p.close(e.name().as_ref())?;
+            }
+            Event::Text(t) => {
+                if let Some(s) = p.text.as_mut() {
+                    s.push_str(&t.decode()?);
+                }
+            }
+            Event::GeneralRef(r) => {
+                if let Some(s) = p.text.as_mut() {
+                    push_ref(s, &r)?;
+                }
+            }
+            Event::End(e) => {
+                p.path.pop();
+                p.close(e.name().as_ref())?;
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    p.finish()
+}
+
+/// Parse a full `<iati-organisations>` document, returning one `Organisation` per `<iati-organisation>`.
+pub fn parse_organisations(xml: &str) -> Result<Vec<Organisation>, ParseError> {
+    OrganisationReader::new(xml.as_bytes()).collect()
+}
+
+/// Streaming reader over an `<iati-organisations>` document, yielding one
+/// `Organisation` per `<iati-organisation>`.
+pub struct OrganisationReader<R> {
+    inner: FragmentReader<R>,
+}
+
+impl<R: BufRead> OrganisationReader<R> {
+    pub fn new(source: R) -> Self {
+        OrganisationReader {
+            inner: FragmentReader::new(source, b"iati-organisation"),
+        }
+    }
+
+    /// Version declared on `<iati-organisations>`, available once the first item has been requested.
+    pub fn version(&self) -> Option<IatiVersion> {
+        self.inner.version()
+    }
+}
+
+impl<R: BufRead> Iterator for OrganisationReader<R> {
+    type Item = Result<Organisation, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next_with(parse_organisation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOC: &str = r#"
+    <iati-organisations version="2.03">
+        <iati-organisation default-currency="EUR" last-updated-datetime="2024-01-01T00:00:00">
+            <organisation-identifier>GB-COH-123456</organisation-identifier>
+            <name><narrative>Example Org</narrative><narrative xml:lang="fr">Exemple</narrative></name>
+            <reporting-org ref="GB-COH-123456" type="21"><narrative>Example Org</narrative></reporting-org>
+            <total-budget status="2">
+                <period-start iso-date="2024-01-01"/>
+                <period-end iso-date="2024-12-31"/>
+                <value value-date="2024-01-01">1000000</value>
+                <budget-line ref="1">
+                    <value currency="USD" value-date="2024-01-01">250000</value>
+                    <narrative>Line</narrative>
+                </budget-line>
+            </total-budget>
+            <recipient-org-budget>
+                <recipient-org ref="XM-DAC-41114"><narrative>UNDP</narrative></recipient-org>
+                <period-start iso-date="2024-01-01"/>
+                <period-end iso-date="2024-12-31"/>
+                <value currency="USD" value-date="2024-01-01">300000</value>
+            </recipient-org-budget>
+            <recipient-country-budget status="1">
+                <recipient-country code="KE"/>
+                <period-start iso-date="2024-01-01"/>
+                <period-end iso-date="2024-12-31"/>
+                <value value-date="2024-01-01">50000.50</value>
+            </recipient-country-budget>
+            <total-expenditure>
+                <period-start iso-date="2023-01-01"/>
+                <period-end iso-date="2023-12-31"/>
+                <value value-date="2023-01-01">900000</value>
+                <expense-line><value value-date="2023-01-01">1</value></expense-line>
+            </total-expenditure>
+            <document-link format="application/pdf" url="https://example.org/report.pdf">
+                <title><narrative>Annual report</narrative></title>
+                <category code="B01"/>
+                <language code="en"/>
+                <document-date iso-date="2024-03-01"/>
+            </document-link>
+        </iati-organisation>
+        <iati-organisation>
+            <organisation-identifier>XM-DAC-41114</organisation-identifier>
+        </iati-organisation>
+    </iati-organisations>
+    "#;
+
+    #[test]
+    fn parse_organisation_document() {
+        let orgs = parse_organisations(DOC).expect("parsed");
+        assert_eq!(orgs.len(), 2);
+
+        let o = &orgs[0];
+        assert_eq!(o.organisation_identifier, "GB-COH-123456");
+        assert_eq!(o.name.as_deref(), Some("Example Org"));
+        assert_eq!(o.default_currency.as_ref().unwrap().0, "EUR");
+        assert_eq!(o.reporting_org.as_ref().unwrap().ref_id.as_deref(), Some("GB-COH-123456"));
+
+        assert_eq!(o.total_budgets.len(), 1);
+        assert_eq!(o.total_budgets[0].status, BudgetStatus::Committed);
+        // the budget-line value must not replace the total
+        assert_eq!(o.total_budgets[0].value.amount, Decimal::new(1000000, 0));
+        assert!(o.total_budgets[0].value.currency.is_none());
+
+        let rob = &o.recipient_org_budgets[0];
+        assert_eq!(rob.recipient_org.ref_id.as_deref(), Some("XM-DAC-41114"));
+        assert_eq!(rob.recipient_org.name.as_deref(), Some("UNDP"));
+        assert_eq!(rob.budget.value.currency.as_ref().unwrap().0, "USD");
+
+        assert_eq!(o.recipient_country_budgets[0].country, "KE");
+        assert_eq!(o.recipient_country_budgets[0].budget.value.amount, Decimal::new(5000050, 2));
+
+        assert_eq!(o.total_expenditures[0].value.amount, Decimal::new(900000, 0));
+
+        let d = &o.document_links[0];
+        assert_eq!(d.url, "https://example.org/report.pdf");
+        assert_eq!(d.format.as_deref(), Some("application/pdf"));
+        assert_eq!(d.title.as_deref(), Some("Annual report"));
+        assert_eq!(d.categories, vec!["B01".to_string()]);
+        assert_eq!(d.document_date, NaiveDate::from_ymd_opt(2024, 3, 1));
+
+        assert_eq!(orgs[1].organisation_identifier, "XM-DAC-41114");
+        assert!(orgs[1].total_budgets.is_empty());
+    }
+
+    #[test]
+    fn entities_in_organisation_narratives() {
+        let doc = DOC.replace("<narrative>Example Org</narrative><narrative", "<narrative>Example &amp; Co</narrative><narrative");
+        let orgs = parse_organisations(&doc).unwrap();
+        assert_eq!(orgs[0].name.as_deref(), Some("Example & Co"));
+    }
+
+    #[test]
+    fn streaming_reader_reports_errors_per_organisation() {
+        let xml = r#"
+        <iati-organisations>
+            <iati-organisation><name><narrative>No id</narrative></name></iati-organisation>
+            <iati-organisation><organisation-identifier>OK-1</organisation-identifier></iati-organisation>
+        </iati-organisations>
+        "#;
+        let results: Vec<_> = OrganisationReader::new(xml.as_bytes()).collect();
+        assert_eq!(results.len(), 2);
+        assert!(matches!(results[0], Err(ParseError::Missing("organisation-identifier"))));
+        assert_eq!(results[1].as_ref().unwrap().organisation_identifier, "OK-1");
+    }
+}
@@ -0,0 +1,486 @@
+//! Structural validation against an embedded description of the IATI 2.03
+//! activity schema: element nesting, order, cardinality and required attributes.
+//!
+//! This is not a full XSD validator (no datatypes or codelists), but it catches
+//! the structural mistakes quick-xml happily parses: wrong element order,
+//! repeated singleton elements, missing required elements and attributes.
+//! Elements in other namespaces (`prefix:name`) are extensions and are skipped.
+
+use quick_xml::{
+    events::{BytesStart, Event},
+    Reader,
+};
+use std::collections::HashMap;
+use std::fmt;
+use thiserror::Error;
+
+/// Allowed child element, in schema sequence order.
+struct Child {
+    name: &'static str,
+    min: u32,
+    /// `None` = unbounded
+    max: Option<u32>,
+}
+
+const fn one(name: &'static str) -> Child {
+    Child { name, min: 1, max: Some(1) }
+}
+const fn opt(name: &'static str) -> Child {
+    Child { name, min: 0, max: Some(1) }
+}
+const fn many(name: &'static str) -> Child {
+    Child { name, min: 0, max: None }
+}
+const fn some(name: &'static str) -> Child {
+    Child { name, min: 1, max: None }
+}
+
+struct ElementSpec {
+    name: &'static str,
+    required_attrs: &'static [&'static str],
+    /// `None` = content not checked.
+    children: Option<&'static [Child]>,
+}
+
+const fn el(name: &'static str, required_attrs: &'static [&'static str], children: &'static [Child]) -> ElementSpec {
+    ElementSpec { name, required_attrs, children: Some(children) }
+}
+/// Element whose children are not described here.
+const fn open(name: &'static str, required_attrs: &'static [&'static str]) -> ElementSpec {
+    ElementSpec { name, required_attrs, children: None }
+}
+
+const NARRATIVES: &[Child] = &[some("narrative")];
+const OPT_NARRATIVES: &[Child] = &[many("narrative")];
+
+/// IATI 2.03 activity schema (iati-activities-schema.xsd), as sequences.
+static SCHEMA: &[ElementSpec] = &[
+    el("iati-activities", &["version"], &[many("iati-activity")]),
+    el("iati-activity", &[], &[
+        one("iati-identifier"),
+        one("reporting-org"),
+        one("title"),
+        many("description"),
+        some("participating-org"),
+        many("other-identifier"),
+        one("activity-status"),
+        some("activity-date"),
+        many("contact-info"),
+        opt("activity-scope"),
+        many("recipient-country"),
+        many("recipient-region"),
+        many("location"),
+        many("sector"),
+        many("tag"),
+        opt("country-budget-items"),
+        many("humanitarian-scope"),
+        many("policy-marker"),
+        opt("collaboration-type"),
+        opt("default-flow-type"),
+        opt("default-finance-type"),
+        many("default-aid-type"),
+        opt("default-tied-status"),
+        many("budget"),
+        many("planned-disbursement"),
+        opt("capital-spend"),
+        many("transaction"),
+        many("document-link"),
+        many("related-activity"),
+        many("legacy-data"),
+        opt("conditions"),
+        many("result"),
+        opt("crs-add"),
+        opt("fss"),
+    ]),
+    el("iati-identifier", &[], &[]),
+    el("reporting-org", &["ref", "type"], NARRATIVES),
+    el("title", &[], NARRATIVES),
+    el("description", &[], NARRATIVES),
+    el("participating-org", &["role"], OPT_NARRATIVES),
+    el("other-identifier", &["ref", "type"], &[opt("owner-org")]),
+    el("owner-org", &[], OPT_NARRATIVES),
+    el("activity-status", &["code"], &[]),
+    el("activity-date", &["type", "iso-date"], OPT_NARRATIVES),
+    el("activity-scope", &["code"], &[]),
+    el("recipient-country", &["code"], OPT_NARRATIVES),
+    el("recipient-region", &["code"], OPT_NARRATIVES),
+    el("sector", &["code"], OPT_NARRATIVES),
+    el("tag", &["code", "vocabulary"], OPT_NARRATIVES),
+    el("policy-marker", &["code"], OPT_NARRATIVES),
+    el("collaboration-type", &["code"], &[]),
+    el("default-flow-type", &["code"], &[]),
+    el("default-finance-type", &["code"], &[]),
+    el("default-aid-type", &["code"], &[]),
+    el("default-tied-status", &["code"], &[]),
+    el("budget", &[], &[one("period-start"), one("period-end"), one("value")]),
+    el("planned-disbursement", &[], &[
+        one("period-start"),
+        opt("period-end"),
+        one("value"),
+        opt("provider-org"),
+        opt("receiver-org"),
+    ]),
+    el("period-start", &["iso-date"], &[]),
+    el("period-end", &["iso-date"], &[]),
+    el("value", &["value-date"], &[]),
+    el("capital-spend", &["percentage"], &[]),
+    el("transaction", &[], &[
+        one("transaction-type"),
+        one("transaction-date"),
+        one("value"),
+        opt("description"),
+        opt("provider-org"),
+        opt("receiver-org"),
+        opt("disbursement-channel"),
+        many("sector"),
+        many("recipient-country"),
+        many("recipient-region"),
+        opt("flow-type"),
+        opt("finance-type"),
+        many("aid-type"),
+        opt("tied-status"),
+    ]),
+    el("transaction-type", &["code"], &[]),
+    el("transaction-date", &["iso-date"], &[]),
+    el("provider-org", &[], OPT_NARRATIVES),
+    el("receiver-org", &[], OPT_NARRATIVES),
+    el("disbursement-channel", &["code"], &[]),
+    el("flow-type", &["code"], &[]),
+    el("finance-type", &["code"], &[]),
+    el("aid-type", &["code"], &[]),
+    el("tied-status", &["code"], &[]),
+    el("document-link", &["url", "format"], &[
+        one("title"),
+        opt("description"),
+        some("category"),
+        many("language"),
+        opt("document-date"),
+    ]),
+    el("category", &["code"], &[]),
+    el("language", &["code"], &[]),
+    el("document-date", &["iso-date"], &[]),
+    el("related-activity", &["ref", "type"], &[]),
+    el("legacy-data", &["name", "value"], &[]),
+    el("conditions", &["attached"], &[many("condition")]),
+    el("condition", &["type"], OPT_NARRATIVES),
+    open("contact-info", &[]),
+    open("location", &[]),
+    open("country-budget-items", &["vocabulary"]),
+    open("humanitarian-scope", &["type", "vocabulary", "code"]),
+    open("result", &["type"]),
+    open("crs-add", &[]),
+    open("fss", &["extraction-date"]),
+    open("narrative", &[]),
+];
+
+fn spec(name: &str) -> Option<&'static ElementSpec> {
+    SCHEMA.iter().find(|s| s.name == name)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum SchemaErrorKind {
+    #[error("element <{0}> is not allowed here")]
+    UnexpectedElement(String),
+    #[error("element <{element}> must come before <{after}>")]
+    OutOfOrder { element: String, after: String },
+    #[error("element <{element}> may occur at most {max} time(s)")]
+    TooMany { element: String, max: u32 },
+    #[error("missing required element <{element}>")]
+    MissingElement { element: String },
+    #[error("missing required attribute @{attribute}")]
+    MissingAttribute { attribute: String },
+    #[error("malformed XML: {0}")]
+    Malformed(String),
+}
+
+/// A schema violation with its position in the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaError {
+    /// 1-based line number.
+    pub line: usize,
+    /// 1-based column, in characters.
+    pub column: usize,
+    /// Byte offset into the input.
+    pub offset: usize,
+    /// XPath of the offending (or parent, for missing children) element, e.g.
+    /// `/iati-activities/iati-activity[2]/transaction[1]`.
+    pub path: String,
+    pub kind: SchemaErrorKind,
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}: {}", self.line, self.column, self.path, self.kind)
+    }
+}
+
+impl std::error::Error for SchemaError {}
+
+struct Frame {
+    path: String,
+    spec: Option<&'static ElementSpec>,
+    /// Don't check this element's subtree (extension or unexpected element).
+    skip: bool,
+    /// Current position in the child sequence.
+    pos: usize,
+    /// Occurrences per child in `spec.children`.
+    counts: Vec<u32>,
+    /// Occurrences per child name, for XPath indices.
+    seen: HashMap<String, usize>,
+}
+
+impl Frame {
+    fn new(path: String, spec: Option<&'static ElementSpec>, skip: bool) -> Self {
+        let n = spec.and_then(|s| s.children).map_or(0, <[Child]>::len);
+        Frame {
+            path,
+            spec,
+            skip,
+            pos: 0,
+            counts: vec![0; n],
+            seen: HashMap::new(),
+        }
+    }
+}
+
+struct Checker<'a> {
+    xml: &'a str,
+    line_starts: Vec<usize>,
+    errors: Vec<SchemaError>,
+}
+
+impl Checker<'_> {
+    fn push(&mut self, offset: usize, path: &str, kind: SchemaErrorKind) {
+        let line = self.line_starts.partition_point(|&s| s <= offset);
+        let start = self.line_starts[line - 1];
+        let column = self.xml.get(start..offset).map_or(0, |s| s.chars().count()) + 1;
+        self.errors.push(SchemaError {
+            line,
+            column,
+            offset,
+            path: path.to_string(),
+            kind,
+        });
+    }
+
+    /// Report required children of `frame` from its current position up to `until`.
+    fn check_missing(&mut self, frame: &Frame, until: usize, offset: usize) {
+        let Some(children) = frame.spec.and_then(|s| s.children) else {
+            return;
+        };
+        for (j, child) in children.iter().enumerate().take(until).skip(frame.pos) {
+            if frame.counts[j] < child.min {
+                self.push(offset, &frame.path, SchemaErrorKind::MissingElement {
+                    element: child.name.to_string(),
+                });
+            }
+        }
+    }
+
+    fn open(&mut self, stack: &mut [Frame], e: &BytesStart<'_>, offset: usize) -> Frame {
+        let name = String::from_utf8_lossy(e.name().as_ref()).into_owned();
+        let extension = name.contains(':');
+
+        let Some(parent) = stack.last_mut() else {
+            // document root
+            let spec = spec(&name).filter(|s| matches!(s.name, "iati-activities" | "iati-activity"));
+            let path = format!("/{name}");
+            if spec.is_none() {
+                self.push(offset, &path, SchemaErrorKind::UnexpectedElement(name));
+                return Frame::new(path, None, true);
+            }
+            self.check_attrs(spec, e, &path, offset);
+            return Frame::new(path, spec, false);
+        };
+
+        let n = parent.seen.entry(name.clone()).or_default();
+        *n += 1;
+        let path = format!("{}/{}[{}]", parent.path, name, n);
+
+        if parent.skip || extension {
+            return Frame::new(path, None, true);
+        }
+        let Some(children) = parent.spec.and_then(|s| s.children) else {
+            return Frame::new(path, None, true);
+        };
+
+        let Some(idx) = children.iter().position(|c| c.name == name) else {
+            self.push(offset, &path, SchemaErrorKind::UnexpectedElement(name));
+            return Frame::new(path, None, true);
+        };
+
+        if idx < parent.pos {
+            let after = children[parent.pos].name.to_string();
+            self.push(offset, &path, SchemaErrorKind::OutOfOrder { element: name.clone(), after });
+        } else {
+            self.check_missing(parent, idx, offset);
+            parent.pos = idx;
+        }
+
+        parent.counts[idx] += 1;
+        if let Some(max) = children[idx].max
+            && parent.counts[idx] == max + 1
+        {
+            self.push(offset, &path, SchemaErrorKind::TooMany { element: name.clone(), max });
+        }
+
+        let spec = spec(&name);
+        self.check_attrs(spec, e, &path, offset);
+        Frame::new(path, spec, false)
+    }
+
+    fn check_attrs(&mut self, spec: Option<&'static ElementSpec>, e: &BytesStart<'_>, path: &str, offset: usize) {
+        let Some(spec) = spec else {
+            return;
+        };
+        for attr in spec.required_attrs {
+            let present = e
+                .attributes()
+                .with_checks(false)
+                .flatten()
+                .any(|a| a.key.as_ref() == attr.as_bytes());
+            if !present {
+                self.push(offset, path, SchemaErrorKind::MissingAttribute {
+                    attribute: attr.to_string(),
+                });
+            }
+        }
+    }
+
+    fn close(&mut self, frame: Frame, offset: usize) {
+        if frame.skip {
+            return;
+        }
+        let n = frame.counts.len();
+        self.check_missing(&frame, n, offset);
+    }
+}
+
+/// Check an `<iati-activities>` document (or a single `<iati-activity>`) against
+/// the embedded 2.03 schema description. Returns every violation found, in document order.
+pub fn validate_schema(xml: &str) -> Vec<SchemaError> {
+    let mut checker = Checker {
+        xml,
+        line_starts: std::iter::once(0)
+            .chain(xml.match_indices('\n').map(|(i, _)| i + 1))
+            .collect(),
+        errors: Vec::new(),
+    };
+
+    let mut reader = Reader::from_str(xml);
+    let mut stack: Vec<Frame> = Vec::new();
+
+    loop {
+        let offset = reader.buffer_position() as usize;
+        match reader.read_event() {
+            Ok(Event::Start(e)) => {
+                let frame = checker.open(&mut stack, &e, offset);
+                stack.push(frame);
+            }
+            Ok(Event::Empty(e)) => {
+                let frame = checker.open(&mut stack, &e, offset);
+                checker.close(frame, offset);
+            }
+            Ok(Event::End(_)) => {
+                if let Some(frame) = stack.pop() {
+                    checker.close(frame, offset);
+                }
+            }
+            Ok(Event::Eof) => break,
+            Ok(_) => {}
+            Err(e) => {
+                let path = stack.last().map(|f| f.path.clone()).unwrap_or_default();
+                let offset = reader.error_position() as usize;
+                checker.push(offset.min(xml.len()), &path, SchemaErrorKind::Malformed(e.to_string()));
+                break;
+            }
+        }
+    }
+
+    checker.errors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(xml: &str) -> Vec<SchemaErrorKind> {
+        validate_schema(xml).into_iter().map(|e| e.kind).collect()
+    }
+
+    const VALID: &str = r#"<iati-activities version="2.03">
+  <iati-activity default-currency="USD">
+    <iati-identifier>GB-1-A</iati-identifier>
+    <reporting-org ref="GB-1" type="10"><narrative>Org</narrative></reporting-org>
+    <title><narrative>Title</narrative></title>
+    <participating-org role="1"/>
+    <activity-status code="2"/>
+    <activity-date type="1" iso-date="2023-01-01"/>
+    <sector code="11110"/>
+    <transaction>
+      <transaction-type code="3"/>
+      <transaction-date iso-date="2023-05-01"/>
+      <value value-date="2023-05-01">10</value>
+      <ext:note xmlns:ext="http://example.org">anything <b>goes</b></ext:note>
+    </transaction>
+  </iati-activity>
+</iati-activities>"#;
+
+    #[test]
+    fn valid_document_has_no_errors() {
+        assert_eq!(validate_schema(VALID), vec![]);
+    }
+
+    #[test]
+    fn reports_order_cardinality_and_attributes_with_positions() {
+        let xml = VALID
+            .replace(
+                r#"<title><narrative>Title</narrative></title>"#,
+                r#"<title><narrative>Title</narrative></title><title><narrative>Again</narrative></title>"#,
+            )
+            .replace(r#"<sector code="11110"/>"#, r#"<sector code="11110"/><activity-scope code="1"/>"#)
+            .replace(r#"<value value-date="2023-05-01">10</value>"#, "<value>10</value>");
+
+        let errors = validate_schema(&xml);
+        let kinds: Vec<_> = errors.iter().map(|e| e.kind.clone()).collect();
+        assert_eq!(kinds, vec![
+            SchemaErrorKind::TooMany { element: "title".into(), max: 1 },
+            SchemaErrorKind::OutOfOrder { element: "activity-scope".into(), after: "sector".into() },
+            SchemaErrorKind::MissingAttribute { attribute: "value-date".into() },
+        ]);
+
+        let scope = &errors[1];
+        assert_eq!(scope.line, 9);
+        assert_eq!(scope.column, 27);
+        assert_eq!(scope.path, "/iati-activities/iati-activity[1]/activity-scope[1]");
+        assert_eq!(
+            errors[2].to_string(),
+            "13:7: /iati-activities/iati-activity[1]/transaction[1]/value[1]: missing required attribute @value-date"
+        );
+    }
+
+    #[test]
+    fn reports_missing_and_unexpected_elements() {
+        let xml = r#"<iati-activity>
+            <iati-identifier>X</iati-identifier>
+            <bogus/>
+            <transaction><transaction-date iso-date="2023-01-01"/><value value-date="2023-01-01">1</value></transaction>
+        </iati-activity>"#;
+        assert_eq!(kinds(xml), vec![
+            SchemaErrorKind::UnexpectedElement("bogus".into()),
+            SchemaErrorKind::MissingElement { element: "reporting-org".into() },
+            SchemaErrorKind::MissingElement { element: "title".into() },
+            SchemaErrorKind::MissingElement { element: "participating-org".into() },
+            SchemaErrorKind::MissingElement { element: "activity-status".into() },
+            SchemaErrorKind::MissingElement { element: "activity-date".into() },
+            SchemaErrorKind::MissingElement { element: "transaction-type".into() },
+        ]);
+    }
+
+    #[test]
+    fn malformed_xml_is_reported() {
+        let errors = validate_schema("<iati-activities version=\"2.03\">\n<iati-activity></iati-activities>");
+        assert!(matches!(errors.last().unwrap().kind, SchemaErrorKind::Malformed(_)));
+        assert_eq!(errors.last().unwrap().line, 2);
+    }
+}
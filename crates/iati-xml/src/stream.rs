@@ -0,0 +1,165 @@
+//! Streaming readers: yield one record at a time from any `BufRead`, so only
+//! the current `<iati-activity>` / `<iati-organisation>` is held in memory.
+
+use quick_xml::{
+    events::{BytesStart, Event},
+    Reader, Writer,
+};
+use std::io::BufRead;
+
+use crate::{parse_activity, IatiVersion, ParseError};
+use iati_types::Activity;
+
+/// `@version` of a root element.
+fn version_attr(e: &BytesStart<'_>) -> Result<Option<String>, ParseError> {
+    for a in e.attributes().with_checks(false) {
+        let a = a?;
+        if a.key.as_ref() == b"version" {
+            return Ok(Some(a.unescape_value()?.trim().to_string()));
+        }
+    }
+    Ok(None)
+}
+
+/// Cuts complete `<tag>...</tag>` fragments out of a document.
+pub(crate) struct FragmentReader<R> {
+    reader: Reader<R>,
+    buf: Vec<u8>,
+    tag: &'static [u8],
+    done: bool,
+    /// `@version` of the document root, once it has been read.
+    root_version: Option<String>,
+    seen_root: bool,
+}
+
+impl<R: BufRead> FragmentReader<R> {
+    pub(crate) fn new(source: R, tag: &'static [u8]) -> Self {
+        // No trim_text: fragments are copied event by event, and trimming each text
+        // piece would eat the spaces around entity references (`Aid &amp; Dev`).
+        FragmentReader {
+            reader: Reader::from_reader(source),
+            buf: Vec::new(),
+            tag,
+            done: false,
+            root_version: None,
+            seen_root: false,
+        }
+    }
+
+    /// Declared `@version` of the root element, once it has been read.
+    pub(crate) fn version(&self) -> Option<IatiVersion> {
+        self.root_version.as_deref()?.parse().ok()
+    }
+
+    /// Next complete fragment, or `None` at the end of the document.
+    pub(crate) fn next_fragment(&mut self) -> Result<Option<String>, ParseError> {
+        if self.done {
+            return Ok(None);
+        }
+        let mut depth: usize = 0;
+        let mut writer: Option<Writer<Vec<u8>>> = None;
+
+        loop {
+            self.buf.clear();
+            let event = match self.reader.read_event_into(&mut self.buf) {
+                Ok(ev) => ev,
+                Err(e) => {
+                    self.done = true;
+                    return Err(e.into());
+                }
+            };
+            if !self.seen_root
+                && let Event::Start(e) | Event::Empty(e) = &event
+            {
+                self.seen_root = true;
+                self.root_version = version_attr(e)?;
+            }
+            match event {
+                Event::Start(e) if writer.is_none() && e.name().as_ref() == self.tag => {
+                    depth = 1;
+                    let mut w = Writer::new(Vec::<u8>::new());
+                    w.write_event(Event::Start(e.to_owned()))?;
+                    writer = Some(w);
+                }
+                Event::Empty(e) if writer.is_none() && e.name().as_ref() == self.tag => {
+                    // Rare degenerate case: <tag .../> (no children)
+                    let mut w = Writer::new(Vec::<u8>::new());
+                    w.write_event(Event::Empty(e.to_owned()))?;
+                    return Ok(Some(String::from_utf8(w.into_inner()).unwrap_or_default()));
+                }
+                ev @ (Event::Start(_) | Event::Empty(_) | Event::Text(_) | Event::GeneralRef(_) | Event::CData(_) | Event::Comment(_) | Event::PI(_)) => {
+                    if let Some(w) = writer.as_mut() {
+                        if let Event::Start(ref e) = ev
+                            && e.name().as_ref() == self.tag
+                        {
+                            depth += 1;
+                        }
+                        w.write_event(ev.to_owned())?;
+                    }
+                }
+                Event::End(e) => {
+                    if let Some(w) = writer.as_mut() {
+                        w.write_event(Event::End(e.to_owned()))?;
+                        if e.name().as_ref() == self.tag {
+                            depth = depth.saturating_sub(1);
+                            if depth == 0 {
+                                let bytes = writer.take().unwrap().into_inner();
+                                return Ok(Some(String::from_utf8(bytes).unwrap_or_default()));
+                            }
+                        }
+                    }
+                }
+                Event::Eof => {
+                    self.done = true;
+                    return Ok(None);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Parse the next fragment with `parse`, as an iterator item.
+    pub(crate) fn next_with<T>(&mut self, parse: fn(&str) -> Result<T, ParseError>) -> Option<Result<T, ParseError>> {
+        match self.next_fragment() {
+            Ok(Some(frag)) => Some(parse(&frag)),
+            Ok(None) => None,
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+/// Streaming reader over an `<iati-activities>` document, yielding one `Activity` per `<iati-activity>`.
+///
+/// ```no_run
+/// use std::{fs::File, io::BufReader};
+///
+/// let file = BufReader::new(File::open("activities.xml").unwrap());
+/// for activity in iati_xml::ActivityReader::new(file) {
+///     println!("{}", activity.unwrap().iati_identifier);
+/// }
+/// ```
+pub struct ActivityReader<R> {
+    inner: FragmentReader<R>,
+}
+
+impl<R: BufRead> ActivityReader<R> {
+    pub fn new(source: R) -> Self {
+        ActivityReader {
+            inner: FragmentReader::new(source, b"iati-activity"),
+        }
+    }
+
+    /// Version declared on `<iati-activities>`, available once the first item
+    /// has been requested. `None` if the document declares no (valid) version.
+    pub fn version(&self) -> Option<IatiVersion> {
+        self.inner.version()
+    }
+}
+
+impl<R: BufRead> Iterator for ActivityReader<R> {
+    type Item = Result<Activity, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next_with(parse_activity)
+    }
+}
@@ -0,0 +1,59 @@
+//! IATI Standard version, from `<iati-activities @version>` / `<iati-organisations @version>`.
+
+use std::fmt;
+use std::str::FromStr;
+
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("invalid IATI version {0:?}")]
+pub struct InvalidVersion(pub String);
+
+/// A declared IATI Standard version such as `2.03` or `1.05`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct IatiVersion {
+    pub major: u8,
+    pub minor: u8,
+}
+
+impl IatiVersion {
+    pub const V1_01: IatiVersion = IatiVersion::new(1, 1);
+    pub const V1_02: IatiVersion = IatiVersion::new(1, 2);
+    pub const V1_03: IatiVersion = IatiVersion::new(1, 3);
+    pub const V1_04: IatiVersion = IatiVersion::new(1, 4);
+    pub const V1_05: IatiVersion = IatiVersion::new(1, 5);
+    pub const V2_01: IatiVersion = IatiVersion::new(2, 1);
+    pub const V2_02: IatiVersion = IatiVersion::new(2, 2);
+    pub const V2_03: IatiVersion = IatiVersion::new(2, 3);
+
+    pub const fn new(major: u8, minor: u8) -> Self {
+        IatiVersion { major, minor }
+    }
+
+    /// 1.0x documents use letter transaction-type codes, plain text instead of
+    /// `narrative`, and named activity-date types.
+    pub fn is_v1(self) -> bool {
+        self.major == 1
+    }
+}
+
+impl fmt::Display for IatiVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{:02}", self.major, self.minor)
+    }
+}
+
+impl FromStr for IatiVersion {
+    type Err = InvalidVersion;
+
+    /// Accepts `2.03`, `2.3` and `1.0x`-style strings.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let invalid = || InvalidVersion(s.to_string());
+        let (major, minor) = s.split_once('.').ok_or_else(invalid)?;
+        match (major.parse(), minor.parse()) {
+            (Ok(major), Ok(minor)) => Ok(IatiVersion::new(major, minor)),
+            _ => Err(invalid()),
+        }
+    }
+}